@@ -1,9 +1,12 @@
 use png::{BitDepth, ColorType, Encoder};
+use puzzle_geom::geom::{board_obstacles_geom, board_to_geom, normalize, piece_geom};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs;
 
+pub use puzzle_geom::{Board, Counts, Piece, Point, PolygonPoint, Shape, ShapeDef, ShapesCatalog};
+
 thread_local! {
     static LABEL_MAP: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
 }
@@ -38,54 +41,6 @@ fn is_en() -> bool {
     LANGUAGE.with(|s| s.borrow().as_str() == "en")
 }
 
-#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum PolygonPoint {
-    Point([f64; 2]),
-    /// Corner with rounding radius: [x, y, r]
-    Rounded([f64; 3]),
-}
-
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct Board {
-    #[serde(rename = "type")]
-    pub type_: Option<String>,
-    pub w: Option<f64>,
-    pub h: Option<f64>,
-    pub polygons: Option<Vec<Vec<PolygonPoint>>>,
-}
-
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct Piece {
-    pub id: Option<String>,
-    #[serde(rename = "type")]
-    pub type_: String,
-    pub at: Option<[f64; 2]>,
-    pub rotation: Option<f64>,
-    pub anchor: Option<String>,
-    pub flip: Option<bool>,
-    pub w: Option<f64>,
-    pub h: Option<f64>,
-    pub side: Option<f64>,
-    pub a: Option<f64>,
-    pub b: Option<f64>,
-    pub n: Option<u32>,
-    pub d: Option<f64>,
-    pub r: Option<f64>,
-    pub base_bottom: Option<f64>,
-    pub base_top: Option<f64>,
-    pub height: Option<f64>,
-    pub base: Option<f64>,
-    pub offset_top: Option<f64>,
-    pub points: Option<Vec<[f64; 2]>>,
-}
-
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PuzzleSpec {
     pub units: Option<String>,
@@ -94,71 +49,64 @@ pub struct PuzzleSpec {
     pub board: Option<Board>,
     pub pieces: Option<Vec<Piece>>,
     pub parts: Option<Vec<PartSpec>>,
-    pub counts: Option<HashMap<String, u32>>,
+    pub counts: Option<Counts>,
+    /// Numbering order for `counts` (see `CountsSpec::order`).
+    pub order: Option<Vec<String>>,
     pub shapes_file: Option<String>,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartSpec {
-    #[serde(rename = "type")]
-    pub type_: String,
+    #[serde(flatten)]
+    pub shape: Shape,
     pub count: u32,
-    pub w: Option<f64>,
-    pub h: Option<f64>,
-    pub side: Option<f64>,
-    pub a: Option<f64>,
-    pub b: Option<f64>,
-    pub n: Option<u32>,
-    pub d: Option<f64>,
-    pub r: Option<f64>,
-    pub base_bottom: Option<f64>,
-    pub base_top: Option<f64>,
-    pub height: Option<f64>,
-    pub base: Option<f64>,
-    pub offset_top: Option<f64>,
-    pub points: Option<Vec<[f64; 2]>>,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct ShapeDef {
-    pub id: String,
-    #[serde(rename = "type")]
-    pub type_: String,
-    pub w: Option<f64>,
-    pub h: Option<f64>,
-    pub side: Option<f64>,
-    pub a: Option<f64>,
-    pub b: Option<f64>,
-    pub n: Option<u32>,
-    pub d: Option<f64>,
-    pub r: Option<f64>,
-    pub base_bottom: Option<f64>,
-    pub base_top: Option<f64>,
-    pub height: Option<f64>,
-    pub base: Option<f64>,
-    pub offset_top: Option<f64>,
-    pub points: Option<Vec<[f64; 2]>>,
-    pub label: Option<String>,
-    pub label_en: Option<String>,
-    pub label_zh: Option<String>,
-}
-
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct ShapesCatalog {
-    pub shapes: Vec<ShapeDef>,
-}
-
-fn rotate_point(p: Point, c: Point, ang: f64, flip: bool) -> Point {
-    let mut dx = p.x - c.x;
-    let dy = p.y - c.y;
-    if flip {
-        dx = -dx;
-    }
-    let (s, ca) = ang.to_radians().sin_cos();
-    Point {
-        x: c.x + dx * ca - dy * s,
-        y: c.y + dx * s + dy * ca,
+// Rasterize an SVG to PNG bytes. `font` becomes the sans-serif family so
+// text renders identically in the browser and on the command line.
+pub fn render_svg_to_png_bytes(
+    svg: &str,
+    w_px: u32,
+    h_px: u32,
+    font: &[u8],
+) -> Result<Vec<u8>, String> {
+    let mut opt = usvg::Options::default();
+    let mut fontdb = usvg::fontdb::Database::new();
+    fontdb.load_font_data(font.to_vec());
+    let family_name = {
+        let mut it = fontdb.faces();
+        if let Some(face) = it.next() {
+            face.families.first().map(|(n, _)| n.clone())
+        } else {
+            None
+        }
+    };
+    if let Some(name) = family_name {
+        fontdb.set_sans_serif_family(name);
     }
+    opt.fontdb = std::sync::Arc::new(fontdb);
+    let tree = usvg::Tree::from_str(svg, &opt).map_err(|e| format!("SVG parse error: {e:?}"))?;
+    let mut pixmap = tiny_skia::Pixmap::new(w_px, h_px).ok_or("pixmap alloc failed")?;
+    let mut pm = pixmap.as_mut();
+    resvg::render(&tree, tiny_skia::Transform::identity(), &mut pm);
+    encode_rgba_to_png_bytes(pixmap.width(), pixmap.height(), pixmap.data())
+        .map_err(|e| format!("encode: {e}"))
+}
+
+pub fn piece_color(i: usize) -> String {
+    // Fixed 8-color palette in the exact order: red, orange, yellow,
+    // green, cyan, blue, purple, pink. Colors are stable and cycle by index%8.
+    const PALETTE: [&str; 8] = [
+        "red",    // 红
+        "orange", // 橙
+        "yellow", // 黄
+        "green",  // 绿
+        "cyan",   // 青
+        "blue",   // 蓝
+        "purple", // 紫
+        "pink",   // 粉
+    ];
+    PALETTE[i % PALETTE.len()].to_string()
 }
 
 // Shared PNG encoder: RGBA -> PNG bytes (deterministic for same input)
@@ -180,301 +128,6 @@ pub fn encode_rgba_to_png_bytes(
     }
     Ok(buf)
 }
-fn piece_rotation(p: &Piece) -> f64 {
-    p.rotation.unwrap_or(0.0)
-}
-fn piece_flip(p: &Piece) -> bool {
-    p.flip.unwrap_or(false)
-}
-
-fn piece_geom(p: &Piece) -> (Vec<Point>, Point) {
-    let rot = piece_rotation(p);
-    let flip = piece_flip(p);
-    let anchor = p.anchor.clone().unwrap_or_else(|| "bottomleft".to_string());
-    let apply = |pts: Vec<Point>, ctr: Point| -> (Vec<Point>, Point) {
-        let out = pts
-            .into_iter()
-            .map(|q| rotate_point(q, ctr, rot, flip))
-            .collect();
-        (out, ctr)
-    };
-    match p.type_.as_str() {
-        "rect" => {
-            let w = p.w.unwrap_or(0.0);
-            let h = p.h.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = if anchor == "center" {
-                Point {
-                    x: at[0] - w / 2.0,
-                    y: at[1] - h / 2.0,
-                }
-            } else {
-                Point { x: at[0], y: at[1] }
-            };
-            let tl = Point {
-                x: bl.x,
-                y: bl.y + h,
-            };
-            let tr = Point {
-                x: bl.x + w,
-                y: bl.y + h,
-            };
-            let br = Point {
-                x: bl.x + w,
-                y: bl.y,
-            };
-            let ctr = Point {
-                x: bl.x + w / 2.0,
-                y: bl.y + h / 2.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
-        }
-        "equilateral_triangle" => {
-            let s = p.side.unwrap_or(0.0);
-            let h = s * 3.0_f64.sqrt() / 2.0;
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = if anchor == "center" {
-                Point {
-                    x: at[0] - s / 2.0,
-                    y: at[1] - h / 3.0,
-                }
-            } else {
-                Point { x: at[0], y: at[1] }
-            };
-            let a = Point { x: bl.x, y: bl.y };
-            let b = Point {
-                x: bl.x + s,
-                y: bl.y,
-            };
-            let c = Point {
-                x: bl.x + s / 2.0,
-                y: bl.y + h,
-            };
-            let ctr = Point {
-                x: (a.x + b.x + c.x) / 3.0,
-                y: (a.y + b.y + c.y) / 3.0,
-            };
-            apply(vec![a, b, c], ctr)
-        }
-        "right_triangle" => {
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let a = Point { x: at[0], y: at[1] };
-            let b = Point {
-                x: at[0] + p.a.unwrap_or(0.0),
-                y: at[1],
-            };
-            let c = Point {
-                x: at[0],
-                y: at[1] + p.b.unwrap_or(0.0),
-            };
-            let ctr = Point {
-                x: (a.x + b.x + c.x) / 3.0,
-                y: (a.y + b.y + c.y) / 3.0,
-            };
-            apply(vec![a, b, c], ctr)
-        }
-        "regular_polygon" => {
-            let n = p.n.unwrap_or(3) as i32;
-            let side = p.side.unwrap_or(0.0);
-            let r = side / (2.0 * (std::f64::consts::PI / n as f64).sin());
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let ctr = Point { x: at[0], y: at[1] };
-            let base_ang = piece_rotation(p) + if piece_flip(p) { 180.0 } else { 0.0 };
-            let mut pts = Vec::new();
-            for i in 0..n {
-                let a = (base_ang + (i as f64) * 360.0 / (n as f64)).to_radians();
-                pts.push(Point {
-                    x: ctr.x + r * a.cos(),
-                    y: ctr.y + r * a.sin(),
-                });
-            }
-            (pts, ctr)
-        }
-        "circle" => {
-            let r = p.d.unwrap_or_else(|| p.r.unwrap_or(0.0) * 2.0) / 2.0;
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let ctr = Point { x: at[0], y: at[1] };
-            let k = 32;
-            let mut pts = Vec::new();
-            for i in 0..k {
-                let a = (i as f64) * 2.0 * std::f64::consts::PI / (k as f64);
-                pts.push(Point {
-                    x: ctr.x + r * a.cos(),
-                    y: ctr.y + r * a.sin(),
-                });
-            }
-            (pts, ctr)
-        }
-        "isosceles_trapezoid" => {
-            let b0 = p.base_bottom.unwrap_or(0.0);
-            let b1 = p.base_top.unwrap_or(0.0);
-            let h = p.height.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = Point { x: at[0], y: at[1] };
-            let br = Point {
-                x: bl.x + b0,
-                y: bl.y,
-            };
-            let tl = Point {
-                x: bl.x + (b0 - b1) / 2.0,
-                y: bl.y + h,
-            };
-            let tr = Point {
-                x: tl.x + b1,
-                y: tl.y,
-            };
-            let ctr = Point {
-                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
-                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
-        }
-        "parallelogram" => {
-            let b = p.base.unwrap_or(0.0);
-            let h = p.height.unwrap_or(0.0);
-            let off = p.offset_top.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = Point { x: at[0], y: at[1] };
-            let br = Point {
-                x: bl.x + b,
-                y: bl.y,
-            };
-            let tl = Point {
-                x: bl.x + off,
-                y: bl.y + h,
-            };
-            let tr = Point {
-                x: tl.x + b,
-                y: tl.y,
-            };
-            let ctr = Point {
-                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
-                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
-        }
-        "polygon" => {
-            let pts = p
-                .points
-                .clone()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|v| Point { x: v[0], y: v[1] })
-                .collect::<Vec<_>>();
-            let n = pts.len().max(1) as f64;
-            let ctr = pts.iter().fold(Point { x: 0.0, y: 0.0 }, |acc, q| Point {
-                x: acc.x + q.x,
-                y: acc.y + q.y,
-            });
-            let ctr = Point {
-                x: ctr.x / n,
-                y: ctr.y / n,
-            };
-            (pts, ctr)
-        }
-        _ => (Vec::new(), Point { x: 0.0, y: 0.0 }),
-    }
-}
-
-fn normalize(p: Point) -> Point {
-    let len = (p.x * p.x + p.y * p.y).sqrt();
-    if len == 0.0 {
-        Point { x: 0.0, y: 0.0 }
-    } else {
-        Point {
-            x: p.x / len,
-            y: p.y / len,
-        }
-    }
-}
-
-fn poly_to_points(poly: &[PolygonPoint]) -> Vec<Point> {
-    let mut out: Vec<Point> = Vec::new();
-    let n = poly.len();
-    let mut i = 0;
-    while i < n {
-        match &poly[i] {
-            PolygonPoint::Point([x, y]) => {
-                out.push(Point { x: *x, y: *y });
-                i += 1;
-            }
-            PolygonPoint::Rounded([x, y, r]) => {
-                if out.is_empty() || i + 1 >= n {
-                    i += 1;
-                    continue;
-                }
-                let prev = *out.last().unwrap();
-                let next_xy = match &poly[i + 1] {
-                    PolygonPoint::Point([nx, ny]) => Point { x: *nx, y: *ny },
-                    PolygonPoint::Rounded([nx, ny, _]) => Point { x: *nx, y: *ny },
-                };
-                let corner = Point { x: *x, y: *y };
-                let radius = *r;
-                let v1 = normalize(Point {
-                    x: prev.x - corner.x,
-                    y: prev.y - corner.y,
-                });
-                let v2 = normalize(Point {
-                    x: next_xy.x - corner.x,
-                    y: next_xy.y - corner.y,
-                });
-                let start = Point {
-                    x: corner.x + v1.x * radius,
-                    y: corner.y + v1.y * radius,
-                };
-                let end = Point {
-                    x: corner.x + v2.x * radius,
-                    y: corner.y + v2.y * radius,
-                };
-                out.push(start);
-                let center = Point {
-                    x: corner.x + (v1.x + v2.x) * radius,
-                    y: corner.y + (v1.y + v2.y) * radius,
-                };
-                let start_ang = (start.y - center.y).atan2(start.x - center.x);
-                let end_ang = (end.y - center.y).atan2(end.x - center.x);
-                let steps = 24;
-                for j in 1..=steps {
-                    let t = j as f64 / steps as f64;
-                    let ang = start_ang + (end_ang - start_ang) * t;
-                    out.push(Point {
-                        x: center.x + radius * ang.cos(),
-                        y: center.y + radius * ang.sin(),
-                    });
-                }
-                i += 1;
-            }
-        }
-    }
-    out
-}
-
-fn board_to_geom(board: &Board) -> Option<Vec<Vec<Point>>> {
-    match board.type_.as_deref() {
-        Some("rect") => {
-            let w = board.w.unwrap_or(0.0);
-            let h = board.h.unwrap_or(0.0);
-            Some(vec![vec![
-                Point { x: 0.0, y: 0.0 },
-                Point { x: w, y: 0.0 },
-                Point { x: w, y: h },
-                Point { x: 0.0, y: h },
-            ]])
-        }
-        Some("polygon") => {
-            if let Some(polys) = &board.polygons {
-                let geoms = polys
-                    .iter()
-                    .map(|poly| poly_to_points(poly))
-                    .collect::<Vec<_>>();
-                if geoms.is_empty() { None } else { Some(geoms) }
-            } else {
-                None
-            }
-        }
-        _ => None,
-    }
-}
 
 #[derive(Clone)]
 struct Segment {
@@ -557,8 +210,10 @@ fn polygon_segments(poly: &[PolygonPoint]) -> Vec<Segment> {
     segs
 }
 
+// Outline segments of the board, followed by those of its obstacles so their
+// sizes are dimensioned as well.
 fn board_segments(board: &Board) -> Vec<Segment> {
-    match board.type_.as_deref() {
+    let mut segs = match board.type_.as_deref() {
         Some("rect") => {
             let w = board.w.unwrap_or(0.0);
             let h = board.h.unwrap_or(0.0);
@@ -597,7 +252,10 @@ fn board_segments(board: &Board) -> Vec<Segment> {
             }
         }
         _ => Vec::new(),
-    }
+    };
+    let obstacles = board.obstacles.iter().flatten().filter(|p| p.len() >= 3);
+    segs.extend(obstacles.flat_map(|p| polygon_segments(p)));
+    segs
 }
 
 fn translate_geom(pts: &[Point], dx: f64, dy: f64) -> Vec<Point> {
@@ -671,34 +329,29 @@ fn group_key_for_piece(p: &Piece) -> String {
         return id.clone();
     }
     // Fallback: stable signature from type and key parameters (no localization)
-    match p.type_.as_str() {
-        "rect" => format!("rect:w={};h={}", p.w.unwrap_or(0.0), p.h.unwrap_or(0.0)),
-        "equilateral_triangle" => format!("equilateral_triangle:side={}", p.side.unwrap_or(0.0)),
-        "right_triangle" => format!(
-            "right_triangle:a={};b={}",
-            p.a.unwrap_or(0.0),
-            p.b.unwrap_or(0.0)
-        ),
-        "regular_polygon" => format!(
-            "regular_polygon:n={};side={}",
-            p.n.unwrap_or(0),
-            p.side.unwrap_or(0.0)
-        ),
-        "circle" => format!("circle:d={};r={}", p.d.unwrap_or(0.0), p.r.unwrap_or(0.0)),
-        "isosceles_trapezoid" => format!(
+    match &p.shape {
+        Shape::Rect { w, h } => format!("rect:w={};h={}", w, h),
+        Shape::EquilateralTriangle { side } => format!("equilateral_triangle:side={}", side),
+        Shape::RightTriangle { a, b } => format!("right_triangle:a={};b={}", a, b),
+        Shape::RegularPolygon { n, side } => format!("regular_polygon:n={};side={}", n, side),
+        Shape::Circle { d } => format!("circle:d={}", d),
+        Shape::IsoscelesTrapezoid {
+            base_bottom,
+            base_top,
+            height,
+        } => format!(
             "isosceles_trapezoid:bb={};bt={};h={}",
-            p.base_bottom.unwrap_or(0.0),
-            p.base_top.unwrap_or(0.0),
-            p.height.unwrap_or(0.0)
+            base_bottom, base_top, height
         ),
-        "parallelogram" => format!(
+        Shape::Parallelogram {
+            base,
+            offset_top,
+            height,
+        } => format!(
             "parallelogram:base={};off={};h={}",
-            p.base.unwrap_or(0.0),
-            p.offset_top.unwrap_or(0.0),
-            p.height.unwrap_or(0.0)
+            base, offset_top, height
         ),
-        "polygon" => "polygon".to_string(),
-        other => other.to_string(),
+        Shape::Polygon { .. } => "polygon".to_string(),
     }
 }
 
@@ -722,24 +375,7 @@ pub fn build_blueprint_svg(
     if let Some(parts) = &p.parts {
         for ps in parts {
             for _ in 0..ps.count {
-                flat_pieces.push(Piece {
-                    type_: ps.type_.clone(),
-                    w: ps.w,
-                    h: ps.h,
-                    side: ps.side,
-                    a: ps.a,
-                    b: ps.b,
-                    n: ps.n,
-                    d: ps.d,
-                    r: ps.r,
-                    base_bottom: ps.base_bottom,
-                    base_top: ps.base_top,
-                    height: ps.height,
-                    base: ps.base,
-                    offset_top: ps.offset_top,
-                    points: ps.points.clone(),
-                    ..Default::default()
-                });
+                flat_pieces.push(Piece::new(ps.shape.clone()));
             }
         }
     } else if let Some(counts) = &p.counts {
@@ -764,27 +400,12 @@ pub fn build_blueprint_svg(
                 label_map.insert(s.id.clone(), lbl);
             }
         }
-        for (id, cnt) in counts.iter() {
+        for (id, cnt) in counts.ordered(p.order.as_deref()) {
             if let Some(sd) = by_id.get(id) {
-                for _ in 0..*cnt {
+                for _ in 0..cnt {
                     flat_pieces.push(Piece {
                         id: Some(sd.id.clone()),
-                        type_: sd.type_.clone(),
-                        w: sd.w,
-                        h: sd.h,
-                        side: sd.side,
-                        a: sd.a,
-                        b: sd.b,
-                        n: sd.n,
-                        d: sd.d,
-                        r: sd.r,
-                        base_bottom: sd.base_bottom,
-                        base_top: sd.base_top,
-                        height: sd.height,
-                        base: sd.base,
-                        offset_top: sd.offset_top,
-                        points: sd.points.clone(),
-                        ..Default::default()
+                        ..Piece::new(sd.shape.clone())
                     });
                 }
             }
@@ -931,7 +552,7 @@ pub fn build_blueprint_svg(
         ));
     }
     let mut row_top = table_top_mm;
-    for ((label, items), row_h) in groups.into_iter().zip(row_heights.into_iter()) {
+    for ((label, items), row_h) in groups.into_iter().zip(row_heights) {
         s.push_str(&format!(
             "<text x=\"{:.2}\" y=\"{:.2}\" fill=\"#333\" font-size=\"26\">{}</text>\n",
             mm2px(pad_mm + 2.0),
@@ -969,6 +590,13 @@ pub fn build_blueprint_svg(
         let g = translate_geoms(&board_geom, -minx + x_mm, -miny + board_top);
         s.push_str(&paths_from_geoms(&g, &to_px));
         if let Some(b) = &p.board {
+            let obstacles = board_obstacles_geom(b);
+            if !obstacles.is_empty() {
+                let g = translate_geoms(&obstacles, -minx + x_mm, -miny + board_top);
+                s.push_str("<g fill=\"#6f4e37\">\n");
+                s.push_str(&paths_from_geoms(&g, &to_px));
+                s.push_str("</g>\n");
+            }
             let segs = board_segments(b);
             for seg in segs {
                 let start = Point {
@@ -1039,6 +667,73 @@ pub fn build_blueprint_svg(
     (s, w_px, h_px)
 }
 
+// Render posed pieces on their board as an SVG (e.g. a solved puzzle).
+// Pieces are filled with the shared palette and numbered like the web UI.
+pub fn build_layout_svg(
+    board: Option<&Board>,
+    pieces: &[Piece],
+    px_per_mm: f64,
+) -> (String, u32, u32) {
+    let pad_mm = 5.0;
+    let board_geom = board.and_then(board_to_geom).unwrap_or_default();
+    let board_outer = board
+        .and_then(|b| puzzle_geom::geom::board_outer_geom(b, puzzle_geom::constants::RING_WIDTH_MM))
+        .unwrap_or_default();
+    let piece_geoms: Vec<(Vec<Point>, Point)> = pieces.iter().map(piece_geom).collect();
+    let mut all: Vec<Vec<Point>> = board_outer.clone();
+    all.extend(board_geom.iter().cloned());
+    all.extend(piece_geoms.iter().map(|(g, _)| g.clone()));
+    let (minx, miny, maxx, maxy) = if all.iter().any(|g| !g.is_empty()) {
+        bounds_of_all(&all)
+    } else {
+        (0.0, 0.0, 100.0, 100.0)
+    };
+    let total_w_mm = maxx - minx + pad_mm * 2.0;
+    let total_h_mm = maxy - miny + pad_mm * 2.0;
+    let w_px = (total_w_mm * px_per_mm).ceil() as u32;
+    let h_px = (total_h_mm * px_per_mm).ceil() as u32;
+    let to_px = |p: Point| {
+        (
+            (p.x - minx + pad_mm) * px_per_mm,
+            (total_h_mm - (p.y - miny + pad_mm)) * px_per_mm,
+        )
+    };
+
+    let mut s = String::new();
+    s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    s.push_str(&format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" stroke=\"#333\" stroke-width=\"1.8\" stroke-linejoin=\"round\" font-family=\"sans-serif\" font-size=\"26\">\n", w_px, h_px, w_px, h_px));
+    s.push_str(
+        "<rect x=\"0\" y=\"0\" width=\"100%\" height=\"100%\" fill=\"#ffffff\" stroke=\"none\"/>\n",
+    );
+    // Same colors as the canvas board: wooden ring around a white inner area.
+    if !board_outer.is_empty() {
+        s.push_str("<g fill=\"#6f4e37\" stroke=\"none\">\n");
+        s.push_str(&paths_from_geoms(&board_outer, &to_px));
+        s.push_str("</g>\n");
+    }
+    if !board_geom.is_empty() {
+        s.push_str("<g fill=\"#ffffff\">\n");
+        s.push_str(&paths_from_geoms(&board_geom, &to_px));
+        s.push_str("</g>\n");
+    }
+    let obstacles = board.map(board_obstacles_geom).unwrap_or_default();
+    if !obstacles.is_empty() {
+        s.push_str("<g fill=\"#6f4e37\">\n");
+        s.push_str(&paths_from_geoms(&obstacles, &to_px));
+        s.push_str("</g>\n");
+    }
+    for (i, (p, (g, ctr))) in pieces.iter().zip(&piece_geoms).enumerate() {
+        let color = piece_color(p.__color_idx.unwrap_or(i));
+        s.push_str(&format!("<g fill=\"{}\" fill-opacity=\"0.85\">\n", color));
+        s.push_str(&path_from_points(g, &to_px));
+        s.push_str("</g>\n");
+        let (tx, ty) = to_px(*ctr);
+        s.push_str(&format!("<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#111\" stroke=\"#fff\" stroke-width=\"4\" paint-order=\"stroke\">{}</text>\n", tx, ty, p.__label_idx.unwrap_or(i) + 1));
+    }
+    s.push_str("</svg>\n");
+    (s, w_px, h_px)
+}
+
 fn path_from_points<F>(pts: &[Point], to_px: &F) -> String
 where
     F: Fn(Point) -> (f64, f64),
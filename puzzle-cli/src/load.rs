@@ -0,0 +1,131 @@
+// File loading shared by the subcommands. Mirrors the browser loader:
+// counts specs are tried first, then full puzzles.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use puzzle_geom::layout::build_puzzle_from_counts;
+use puzzle_geom::{CountsSpec, Puzzle, ShapesCatalog, Solution, SolutionsFile};
+
+/// Parsed contents of a JSON file given on the command line.
+pub enum Loaded {
+    /// Counts + board spec together with the catalog it refers to.
+    Counts {
+        spec: CountsSpec,
+        catalog: ShapesCatalog,
+    },
+    /// Fully posed puzzle (e.g. a saved layout or a solution).
+    Full(Puzzle),
+    /// Chooser index (`puzzles.json`): name -> puzzle path.
+    Index(BTreeMap<String, String>),
+    /// Solutions sidecar (`<id>.solution.json`), checked with its puzzle.
+    Solutions,
+    /// Shapes catalog (`shapes.json`).
+    Catalog(ShapesCatalog),
+}
+
+impl Loaded {
+    /// Puzzle as the web app would show it right after loading.
+    pub fn to_puzzle(&self) -> Option<Puzzle> {
+        match self {
+            Loaded::Counts { spec, catalog } => Some(build_puzzle_from_counts(spec, catalog)),
+            Loaded::Full(p) => Some(p.clone()),
+            Loaded::Index(_) | Loaded::Solutions | Loaded::Catalog(_) => None,
+        }
+    }
+}
+
+pub fn load_file(path: &Path, shapes: Option<&Path>) -> Result<Loaded, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("{}: cannot read: {}", path.display(), e))?;
+    if is_sidecar(&text) {
+        return serde_json::from_str::<SolutionsFile>(&text)
+            .map(|_| Loaded::Solutions)
+            .map_err(|e| format!("{}: invalid solutions file: {}", path.display(), e));
+    }
+    if is_catalog(&text) {
+        return serde_json::from_str::<ShapesCatalog>(&text)
+            .map(Loaded::Catalog)
+            .map_err(|e| format!("{}: invalid shapes catalog: {}", path.display(), e));
+    }
+    if let Ok(mut spec) = serde_json::from_str::<CountsSpec>(&text) {
+        let catalog = load_catalog(path, spec.shapes_file.as_deref(), shapes)?;
+        if spec.solutions.is_empty() {
+            spec.solutions = sidecar_solutions(path)?;
+        }
+        return Ok(Loaded::Counts { spec, catalog });
+    }
+    // Any object parses as a (possibly empty) Puzzle, so check the index
+    // shape first: it is the only format that is a flat string map.
+    if let Ok(index) = serde_json::from_str::<BTreeMap<String, String>>(&text) {
+        return Ok(Loaded::Index(index));
+    }
+    let mut puzzle = serde_json::from_str::<Puzzle>(&text)
+        .map_err(|e| format!("{}: unrecognized puzzle JSON format: {}", path.display(), e))?;
+    if puzzle.solutions.is_empty() {
+        puzzle.solutions = sidecar_solutions(path)?;
+    }
+    Ok(Loaded::Full(puzzle))
+}
+
+// A solutions sidecar has `solutions` but neither pieces nor a board.
+fn is_sidecar(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text).is_ok_and(|v| {
+        v.get("solutions").is_some() && v.get("pieces").is_none() && v.get("board").is_none()
+    })
+}
+
+// A catalog has `shapes` but no counts or pieces.
+fn is_catalog(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text).is_ok_and(|v| {
+        v.get("shapes").is_some() && v.get("counts").is_none() && v.get("pieces").is_none()
+    })
+}
+
+// Solutions from `<stem>.solution.json` next to the puzzle, as the browser
+// loader fetches them; none when there is no such file.
+fn sidecar_solutions(puzzle_path: &Path) -> Result<Vec<Solution>, String> {
+    let path = puzzle_path.with_extension("solution.json");
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_str::<SolutionsFile>(&text)
+        .map(|f| f.solutions)
+        .map_err(|e| format!("{}: invalid solutions file: {}", path.display(), e))
+}
+
+// Catalog lookup order: --shapes, the spec's `shapes_file` (relative to the
+// puzzle file, then to the working directory), ./shapes.json, bundled copy.
+fn load_catalog(
+    puzzle_path: &Path,
+    shapes_file: Option<&str>,
+    shapes: Option<&Path>,
+) -> Result<ShapesCatalog, String> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Some(p) = shapes {
+        candidates.push(p.to_path_buf());
+    } else if let Some(sf) = shapes_file {
+        let sf = sf.trim_start_matches('/');
+        if let Some(dir) = puzzle_path.parent() {
+            candidates.push(dir.join(sf));
+        }
+        candidates.push(PathBuf::from(sf));
+    } else {
+        candidates.push(PathBuf::from("shapes.json"));
+    }
+    for c in &candidates {
+        if let Ok(txt) = fs::read_to_string(c) {
+            return serde_json::from_str::<ShapesCatalog>(&txt)
+                .map_err(|e| format!("{}: failed to parse shapes catalog: {}", c.display(), e));
+        }
+    }
+    if shapes.is_some() {
+        return Err(format!(
+            "{}: cannot read shapes catalog",
+            candidates[0].display()
+        ));
+    }
+    serde_json::from_str::<ShapesCatalog>(include_str!("../../shapes.json"))
+        .map_err(|e| format!("bundled shapes.json: {}", e))
+}
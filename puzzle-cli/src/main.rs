@@ -0,0 +1,447 @@
+//! Command-line companion to the web app: validate puzzle files, solve
+//! them, and render blueprints or layouts to SVG/PNG. Intended for
+//! pre-commit checks over `puzzle/*.json` and `puzzles.json`.
+
+mod load;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use puzzle_geom::catalog::catalog_issues;
+use puzzle_geom::geom::board_to_geom;
+use puzzle_geom::layout::build_puzzle_from_counts;
+use puzzle_geom::solution::apply_solution;
+use puzzle_geom::solver::{SolveError, solve, solve_puzzle};
+use puzzle_geom::validate::{ValidationReport, Violation, fill_area_mismatch, validate};
+use puzzle_geom::{Piece, Puzzle};
+
+use crate::load::{Loaded, load_file};
+
+#[derive(Parser)]
+#[command(
+    name = "puzzle-cli",
+    version,
+    about = "Validate, solve and render puzzle JSON files"
+)]
+struct Cli {
+    /// Shapes catalog used for counts specs (default: `shapes_file`, then ./shapes.json)
+    #[arg(long, global = true)]
+    shapes: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check puzzle files; exits non-zero if any check fails.
+    ///
+    /// Posed puzzles are checked for overlaps, pieces outside the border or
+    /// on an obstacle, and their goal. Counts specs must reference known
+    /// shapes and have a valid board, and for a fill goal the pieces' area
+    /// must match the board's. Known solutions (inline or in the
+    /// `.solution.json` sidecar) must cover the puzzle's pieces and pass the
+    /// same checks. A `puzzles.json` index validates every puzzle it lists.
+    /// A shapes catalog must have unique ids, positive sizes and simple
+    /// polygon outlines.
+    ///
+    /// Use `--json` for machine-readable output.
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Also require counts specs to be solvable by the built-in solver
+        #[arg(long)]
+        solve: bool,
+        /// Print each posed puzzle's validation report as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render the printable blueprint (board + parts table).
+    Blueprint {
+        puzzle: PathBuf,
+        /// Output file; format follows the extension (.svg or .png)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Label language (en or zh)
+        #[arg(long, default_value = "en")]
+        lang: String,
+    },
+    /// Render posed pieces on the board, e.g. a saved solution.
+    Render {
+        solution: PathBuf,
+        /// Output file; format follows the extension (.svg or .png)
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Solve a puzzle and print the posed result as JSON.
+    Solve {
+        puzzle: PathBuf,
+        /// Write the solution here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+const PX_PER_MM: f64 = 4.0; // same export resolution as the web blueprint
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let shapes = cli.shapes.as_deref();
+    let res = match &cli.command {
+        Command::Validate { files, solve, json } => run_validate(files, shapes, *solve, *json),
+        Command::Blueprint {
+            puzzle,
+            output,
+            lang,
+        } => run_blueprint(puzzle, output, lang, shapes),
+        Command::Render { solution, output } => run_render(solution, output, shapes),
+        Command::Solve { puzzle, output } => run_solve(puzzle, output.as_deref(), shapes),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_validate(
+    files: &[PathBuf],
+    shapes: Option<&Path>,
+    check_solve: bool,
+    json: bool,
+) -> Result<(), String> {
+    let mut failed = 0usize;
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut queue: Vec<PathBuf> = files.to_vec();
+    let mut i = 0;
+    while i < queue.len() {
+        let path = queue[i].clone();
+        i += 1;
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let mut report = None;
+        let problems = match load_file(&path, shapes) {
+            Ok(Loaded::Index(index)) => {
+                // Index paths are relative to the index file's directory.
+                let base = path.parent().unwrap_or(Path::new(""));
+                let mut problems = Vec::new();
+                for (name, rel) in &index {
+                    let p = base.join(rel);
+                    if p.is_file() {
+                        queue.push(p);
+                    } else {
+                        problems.push(format!("{}: {} does not exist", name, p.display()));
+                    }
+                }
+                problems
+            }
+            Ok(Loaded::Counts { spec, catalog }) => {
+                let known: HashSet<&str> = catalog.shapes.iter().map(|s| s.id.as_str()).collect();
+                let mut problems: Vec<String> = spec
+                    .counts
+                    .iter()
+                    .map(|(id, _)| id)
+                    .filter(|id| !known.contains(id))
+                    .map(|id| format!("Unknown shape id {:?}", id))
+                    .collect();
+                if spec.board.as_ref().and_then(board_to_geom).is_none() {
+                    problems.push("Board is missing or invalid".to_string());
+                }
+                let obstacles = spec.board.iter().flat_map(|b| b.obstacles.iter().flatten());
+                for (i, poly) in obstacles.enumerate() {
+                    if poly.len() < 3 {
+                        problems.push(format!("Obstacle {} has fewer than three points", i + 1));
+                    }
+                }
+                if problems.is_empty()
+                    && check_solve
+                    && let Err(e) = solve(&spec, &catalog)
+                {
+                    problems.push(solve_error_text(e).to_string());
+                }
+                if problems.is_empty() {
+                    let puzzle = build_puzzle_from_counts(&spec, &catalog);
+                    if let Some((pieces, board)) = fill_area_mismatch(&puzzle) {
+                        problems.push(format!(
+                            "Fill goal: pieces cover {:.1} mm² but the board is {:.1} mm²",
+                            pieces, board
+                        ));
+                    }
+                    problems.extend(solution_problems(&puzzle));
+                }
+                problems
+            }
+            Ok(Loaded::Full(p)) => {
+                let r = validate(&p);
+                let mut problems = placement_problems(&r);
+                problems.extend(solution_problems(&p));
+                report = Some(r);
+                problems
+            }
+            Ok(Loaded::Solutions) => Vec::new(),
+            Ok(Loaded::Catalog(catalog)) => catalog_issues(&catalog)
+                .iter()
+                .map(|(i, e)| format!("{}: {}", catalog.shapes[*i].id, e))
+                .collect(),
+            Err(e) => vec![e],
+        };
+        if !problems.is_empty() {
+            failed += 1;
+        }
+        if json {
+            let line = serde_json::json!({
+                "file": path.display().to_string(),
+                "ok": problems.is_empty(),
+                "problems": problems,
+                "report": report,
+            });
+            println!("{}", line);
+        } else if problems.is_empty() {
+            println!("ok    {}", path.display());
+        } else {
+            println!("FAIL  {}", path.display());
+            for p in problems {
+                println!("      {}", p);
+            }
+        }
+    }
+    if failed > 0 {
+        Err(format!("{} file(s) failed validation", failed))
+    } else {
+        Ok(())
+    }
+}
+
+// Same report as the in-app validation panel; pieces are numbered from 1.
+fn placement_problems(report: &ValidationReport) -> Vec<String> {
+    report
+        .violations
+        .iter()
+        .map(|v| match *v {
+            Violation::Overlap {
+                a,
+                b,
+                penetration_mm,
+            } => format!(
+                "Piece {} overlaps piece {} by {:.2} mm",
+                a + 1,
+                b + 1,
+                penetration_mm
+            ),
+            Violation::OutsideBorder { piece, depth_mm } => format!(
+                "Piece {} is outside the border by {:.2} mm",
+                piece + 1,
+                depth_mm
+            ),
+            Violation::OnObstacle {
+                piece,
+                obstacle,
+                depth_mm,
+            } => format!(
+                "Piece {} covers obstacle {} by {:.2} mm",
+                piece + 1,
+                obstacle + 1,
+                depth_mm
+            ),
+            Violation::Unplaced { piece } => format!("Piece {} is still in the tray", piece + 1),
+            Violation::Uncovered { area_mm2 } => {
+                format!("{:.1} mm² of the board is still uncovered", area_mm2)
+            }
+            Violation::Silhouette {
+                missing_mm2,
+                excess_mm2,
+            } => format!(
+                "Shape does not match the silhouette: {:.1} mm² missing, {:.1} mm² extra",
+                missing_mm2, excess_mm2
+            ),
+        })
+        .collect()
+}
+
+fn solve_error_text(err: SolveError) -> &'static str {
+    match err {
+        SolveError::NoBoard => "Board is missing or invalid",
+        SolveError::NoSolution => "No solution found",
+        SolveError::LimitReached => "No solution found within the search limit",
+    }
+}
+
+// Each known solution must pose exactly the puzzle's pieces and validate.
+fn solution_problems(puzzle: &Puzzle) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, sol) in puzzle.solutions.iter().enumerate() {
+        let name = sol
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Solution {}", i + 1));
+        let Some(pieces) = apply_solution(&puzzle.pieces, sol) else {
+            problems.push(format!("{}: pieces do not match the puzzle", name));
+            continue;
+        };
+        let posed = Puzzle {
+            pieces,
+            ..puzzle.clone()
+        };
+        for p in placement_problems(&validate(&posed)) {
+            problems.push(format!("{}: {}", name, p));
+        }
+    }
+    problems
+}
+
+fn load_puzzle(path: &Path, shapes: Option<&Path>) -> Result<Loaded, String> {
+    match load_file(path, shapes)? {
+        Loaded::Index(_) => Err(format!(
+            "{}: is a puzzle index, pass a single puzzle",
+            path.display()
+        )),
+        Loaded::Solutions => Err(format!(
+            "{}: is a solutions file, pass its puzzle",
+            path.display()
+        )),
+        Loaded::Catalog(_) => Err(format!(
+            "{}: is a shapes catalog, pass a puzzle",
+            path.display()
+        )),
+        other => Ok(other),
+    }
+}
+
+fn run_blueprint(
+    path: &Path,
+    output: &Path,
+    lang: &str,
+    shapes: Option<&Path>,
+) -> Result<(), String> {
+    let loaded = load_puzzle(path, shapes)?;
+    blueprint_core::set_language(lang);
+    let zh = lang == "zh";
+    if let Loaded::Counts { catalog, .. } = &loaded {
+        let mut map: HashMap<String, String> = HashMap::new();
+        for s in &catalog.shapes {
+            let chosen = if zh {
+                s.label_zh.clone()
+            } else {
+                s.label_en.clone()
+            };
+            if let Some(lbl) = chosen {
+                map.insert(s.id.clone(), lbl);
+            }
+        }
+        blueprint_core::set_label_map(&map);
+    }
+    let puzzle = loaded.to_puzzle().unwrap_or_default();
+    // Poses are reset, matching the web export.
+    let pieces = puzzle
+        .pieces
+        .iter()
+        .map(|p| Piece {
+            at: Some([0.0, 0.0]),
+            rotation: Some(0.0),
+            anchor: Some("bottomleft".to_string()),
+            flip: Some(false),
+            ..p.clone()
+        })
+        .collect::<Vec<_>>();
+    let note = if zh {
+        puzzle.note_zh.clone().or_else(|| puzzle.note_en.clone())
+    } else {
+        puzzle.note_en.clone().or_else(|| puzzle.note_zh.clone())
+    };
+    let title = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+    let spec = blueprint_core::PuzzleSpec {
+        units: puzzle.units.clone(),
+        title,
+        note,
+        board: puzzle.board.clone(),
+        pieces: Some(pieces),
+        parts: None,
+        counts: None,
+        order: None,
+        shapes_file: None,
+    };
+    let (svg, w_px, h_px) = blueprint_core::build_blueprint_svg(&spec, PX_PER_MM, None);
+    write_image(output, &svg, w_px, h_px)
+}
+
+fn run_render(path: &Path, output: &Path, shapes: Option<&Path>) -> Result<(), String> {
+    let puzzle = load_puzzle(path, shapes)?.to_puzzle().unwrap_or_default();
+    let (svg, w_px, h_px) =
+        blueprint_core::build_layout_svg(puzzle.board.as_ref(), &puzzle.pieces, PX_PER_MM);
+    write_image(output, &svg, w_px, h_px)
+}
+
+fn run_solve(path: &Path, output: Option<&Path>, shapes: Option<&Path>) -> Result<(), String> {
+    let solution = match load_puzzle(path, shapes)? {
+        Loaded::Counts { spec, catalog } => solve(&spec, &catalog),
+        Loaded::Full(p) => solve_puzzle(&p),
+        Loaded::Index(_) | Loaded::Solutions | Loaded::Catalog(_) => Err(SolveError::NoBoard),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), solve_error_text(e).to_lowercase()))?;
+    let json = serde_json::to_string_pretty(&solution).map_err(|e| e.to_string())?;
+    match output {
+        Some(out) => fs::write(out, json + "\n")
+            .map_err(|e| format!("{}: cannot write: {}", out.display(), e)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+fn write_image(output: &Path, svg: &str, w_px: u32, h_px: u32) -> Result<(), String> {
+    let ext = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let bytes = match ext.as_str() {
+        "svg" => svg.as_bytes().to_vec(),
+        "png" => blueprint_core::render_svg_to_png_bytes(svg, w_px, h_px, fonts::FONT_BYTES)?,
+        _ => {
+            return Err(format!(
+                "{}: output must end in .svg or .png",
+                output.display()
+            ));
+        }
+    };
+    fs::write(output, bytes).map_err(|e| format!("{}: cannot write: {}", output.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every shipped `<id>.solution.json` poses its puzzle without violations.
+    #[test]
+    fn shipped_solutions_validate() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../puzzle");
+        let mut checked = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let Some(id) = name.strip_suffix(".solution.json") else {
+                continue;
+            };
+            let puzzle = load_file(&dir.join(format!("{id}.json")), None)
+                .unwrap()
+                .to_puzzle()
+                .unwrap();
+            assert!(!puzzle.solutions.is_empty(), "{name}: no solutions");
+            for (i, sol) in puzzle.solutions.iter().enumerate() {
+                let pieces = apply_solution(&puzzle.pieces, sol).unwrap();
+                let report = validate(&Puzzle {
+                    pieces,
+                    ..puzzle.clone()
+                });
+                assert_eq!(report.violations, vec![], "{name}: solution {}", i + 1);
+            }
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+}
@@ -0,0 +1,321 @@
+// Checks for shape catalog entries, shared by the shape editor and the CLI.
+// A shape passes when every size is a positive number and, for a free-form
+// polygon, the outline is simple (no edge touches a non-adjacent one) and
+// encloses some area.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::constants::{MAX_POLYGON_SIDES, VALIDATION_EPS_MM};
+use crate::models::{Point as Pt, Shape, ShapeDef, ShapesCatalog};
+
+/// A problem with one shape. Vertex and edge numbers are indices into the
+/// polygon's `points`; edge `i` runs from point `i` to the next one.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShapeIssue {
+    /// The shape has no id.
+    MissingId,
+    /// Another shape earlier in the catalog has the same id.
+    DuplicateId { id: String },
+    /// A size parameter is zero, negative or not a number.
+    BadSize { field: &'static str },
+    /// Regular polygon with fewer than three sides.
+    TooFewSides { n: u32 },
+    /// Regular polygon with more than `MAX_POLYGON_SIDES` sides.
+    TooManySides { n: u32 },
+    /// Polygon with fewer than three vertices.
+    TooFewPoints { count: usize },
+    /// Vertex `index` lies on the previous one.
+    RepeatedPoint { index: usize },
+    /// Edges `a` and `b` cross or touch.
+    SelfIntersecting { a: usize, b: usize },
+    /// The outline encloses no area.
+    ZeroArea,
+}
+
+impl fmt::Display for ShapeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeIssue::MissingId => write!(f, "Shape has no id"),
+            ShapeIssue::DuplicateId { id } => write!(f, "Id {:?} is used more than once", id),
+            ShapeIssue::BadSize { field } => write!(f, "{} must be a positive number", field),
+            ShapeIssue::TooFewSides { n } => {
+                write!(f, "A regular polygon needs 3 or more sides, not {}", n)
+            }
+            ShapeIssue::TooManySides { n } => write!(
+                f,
+                "A regular polygon can have at most {} sides, not {}",
+                MAX_POLYGON_SIDES, n
+            ),
+            ShapeIssue::TooFewPoints { count } => {
+                write!(f, "A polygon needs 3 or more points, not {}", count)
+            }
+            ShapeIssue::RepeatedPoint { index } => {
+                write!(f, "Point {} repeats the previous point", index + 1)
+            }
+            ShapeIssue::SelfIntersecting { a, b } => {
+                write!(f, "Edges {} and {} cross or touch", a + 1, b + 1)
+            }
+            ShapeIssue::ZeroArea => write!(f, "The outline encloses no area"),
+        }
+    }
+}
+
+/// Problems with `shape` on its own; empty when it is usable.
+pub fn shape_issues(shape: &Shape) -> Vec<ShapeIssue> {
+    let sizes: &[(&'static str, f64)] = match *shape {
+        Shape::Rect { w, h } => &[("w", w), ("h", h)],
+        Shape::EquilateralTriangle { side } => &[("side", side)],
+        Shape::RightTriangle { a, b } => &[("a", a), ("b", b)],
+        Shape::RegularPolygon { side, .. } => &[("side", side)],
+        Shape::Circle { d } => &[("d", d)],
+        Shape::IsoscelesTrapezoid {
+            base_bottom,
+            base_top,
+            height,
+        } => &[
+            ("base_bottom", base_bottom),
+            ("base_top", base_top),
+            ("height", height),
+        ],
+        Shape::Parallelogram { base, height, .. } => &[("base", base), ("height", height)],
+        Shape::Polygon { .. } => &[],
+    };
+    let mut out: Vec<ShapeIssue> = sizes
+        .iter()
+        .filter(|(_, v)| !(v.is_finite() && *v > 0.0))
+        .map(|&(field, _)| ShapeIssue::BadSize { field })
+        .collect();
+    match shape {
+        Shape::RegularPolygon { n, .. } if *n < 3 => out.push(ShapeIssue::TooFewSides { n: *n }),
+        Shape::RegularPolygon { n, .. } if *n > MAX_POLYGON_SIDES => {
+            out.push(ShapeIssue::TooManySides { n: *n })
+        }
+        Shape::Parallelogram { offset_top, .. } if !offset_top.is_finite() => {
+            out.push(ShapeIssue::BadSize {
+                field: "offset_top",
+            })
+        }
+        Shape::Polygon { points } => out.extend(polygon_issues(points)),
+        _ => {}
+    }
+    out
+}
+
+/// Problems with every shape of `catalog`, keyed by index into `shapes`.
+pub fn catalog_issues(catalog: &ShapesCatalog) -> Vec<(usize, ShapeIssue)> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut out = Vec::new();
+    for (i, def) in catalog.shapes.iter().enumerate() {
+        if !seen.insert(def.id.as_str()) {
+            let id = def.id.clone();
+            out.push((i, ShapeIssue::DuplicateId { id }));
+        }
+        out.extend(def_issues(def).into_iter().map(|e| (i, e)));
+    }
+    out
+}
+
+/// Problems with a catalog entry on its own (its id and its shape).
+pub fn def_issues(def: &ShapeDef) -> Vec<ShapeIssue> {
+    let mut out = Vec::new();
+    if def.id.trim().is_empty() {
+        out.push(ShapeIssue::MissingId);
+    }
+    out.extend(shape_issues(&def.shape));
+    out
+}
+
+fn polygon_issues(points: &[[f64; 2]]) -> Vec<ShapeIssue> {
+    if points.iter().flatten().any(|v| !v.is_finite()) {
+        return vec![ShapeIssue::BadSize { field: "points" }];
+    }
+    let n = points.len();
+    if n < 3 {
+        return vec![ShapeIssue::TooFewPoints { count: n }];
+    }
+    let pts: Vec<Pt> = points.iter().map(|v| Pt { x: v[0], y: v[1] }).collect();
+    let mut out: Vec<ShapeIssue> = (0..n)
+        .filter(|&i| dist(pts[i], pts[(i + n - 1) % n]) <= VALIDATION_EPS_MM)
+        .map(|index| ShapeIssue::RepeatedPoint { index })
+        .collect();
+    if !out.is_empty() {
+        return out;
+    }
+    for a in 0..n {
+        for b in a + 2..n {
+            // The last edge shares a vertex with the first
+            if a == 0 && b == n - 1 {
+                continue;
+            }
+            let (p1, p2) = (pts[a], pts[(a + 1) % n]);
+            let (q1, q2) = (pts[b], pts[(b + 1) % n]);
+            if segments_touch(p1, p2, q1, q2) {
+                out.push(ShapeIssue::SelfIntersecting { a, b });
+            }
+        }
+    }
+    let area = (0..n)
+        .map(|i| {
+            let (p, q) = (pts[i], pts[(i + 1) % n]);
+            p.x * q.y - q.x * p.y
+        })
+        .sum::<f64>()
+        / 2.0;
+    // A sliver thinner than the tolerance counts as no area
+    let perimeter: f64 = (0..n).map(|i| dist(pts[i], pts[(i + 1) % n])).sum();
+    if out.is_empty() && area.abs() <= perimeter * VALIDATION_EPS_MM / 2.0 {
+        out.push(ShapeIssue::ZeroArea);
+    }
+    out
+}
+
+fn dist(a: Pt, b: Pt) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+// Whether two segments cross or come within the tolerance of each other.
+fn segments_touch(p1: Pt, p2: Pt, q1: Pt, q2: Pt) -> bool {
+    fn cross(a: Pt, b: Pt, c: Pt) -> f64 {
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    }
+    let d1 = cross(p1, p2, q1);
+    let d2 = cross(p1, p2, q2);
+    let d3 = cross(q1, q2, p1);
+    let d4 = cross(q1, q2, p2);
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+    [
+        seg_dist(q1, p1, p2),
+        seg_dist(q2, p1, p2),
+        seg_dist(p1, q1, q2),
+        seg_dist(p2, q1, q2),
+    ]
+    .iter()
+    .any(|&d| d <= VALIDATION_EPS_MM)
+}
+
+fn seg_dist(p: Pt, a: Pt, b: Pt) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 <= 1e-12 {
+        return dist(p, a);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+    dist(
+        p,
+        Pt {
+            x: a.x + t * dx,
+            y: a.y + t * dy,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(id: &str, shape: Shape) -> ShapeDef {
+        ShapeDef {
+            id: id.to_string(),
+            shape,
+            label: None,
+            label_en: None,
+            label_zh: None,
+        }
+    }
+
+    fn polygon(points: &[[f64; 2]]) -> Shape {
+        Shape::Polygon {
+            points: points.to_vec(),
+        }
+    }
+
+    #[test]
+    fn simple_polygon_passes() {
+        let square = polygon(&[[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]]);
+        assert_eq!(shape_issues(&square), vec![]);
+    }
+
+    #[test]
+    fn bow_tie_is_self_intersecting() {
+        let bow_tie = polygon(&[[0.0, 0.0], [10.0, 10.0], [10.0, 0.0], [0.0, 10.0]]);
+        assert_eq!(
+            shape_issues(&bow_tie),
+            vec![ShapeIssue::SelfIntersecting { a: 0, b: 2 }]
+        );
+    }
+
+    #[test]
+    fn flat_polygon_has_no_area() {
+        let flat = polygon(&[[0.0, 0.0], [10.0, 0.0], [20.0, 0.0]]);
+        assert_eq!(shape_issues(&flat), vec![ShapeIssue::ZeroArea]);
+    }
+
+    #[test]
+    fn degenerate_sizes_are_reported() {
+        let rect = Shape::Rect {
+            w: 0.0,
+            h: f64::NAN,
+        };
+        assert_eq!(
+            shape_issues(&rect),
+            vec![
+                ShapeIssue::BadSize { field: "w" },
+                ShapeIssue::BadSize { field: "h" },
+            ]
+        );
+        let circle = Shape::Circle { d: -5.0 };
+        assert_eq!(
+            shape_issues(&circle),
+            vec![ShapeIssue::BadSize { field: "d" }]
+        );
+        let line = polygon(&[[0.0, 0.0], [10.0, 0.0]]);
+        assert_eq!(
+            shape_issues(&line),
+            vec![ShapeIssue::TooFewPoints { count: 2 }]
+        );
+    }
+
+    #[test]
+    fn regular_polygon_sides_are_bounded() {
+        let sides = |n| shape_issues(&Shape::RegularPolygon { n, side: 10.0 });
+        assert_eq!(sides(2), vec![ShapeIssue::TooFewSides { n: 2 }]);
+        assert_eq!(sides(3), vec![]);
+        assert_eq!(sides(MAX_POLYGON_SIDES), vec![]);
+        assert_eq!(
+            sides(MAX_POLYGON_SIDES + 1),
+            vec![ShapeIssue::TooManySides {
+                n: MAX_POLYGON_SIDES + 1
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_point_at_the_later_shape() {
+        let catalog = ShapesCatalog {
+            shapes: vec![
+                def("a", Shape::Circle { d: 10.0 }),
+                def("b", Shape::Circle { d: 10.0 }),
+                def("a", Shape::Circle { d: 20.0 }),
+                def(" ", Shape::Circle { d: 20.0 }),
+            ],
+        };
+        assert_eq!(
+            catalog_issues(&catalog),
+            vec![
+                (
+                    2,
+                    ShapeIssue::DuplicateId {
+                        id: "a".to_string()
+                    }
+                ),
+                (3, ShapeIssue::MissingId),
+            ]
+        );
+    }
+}
@@ -0,0 +1,379 @@
+// Overlap and containment rules shared by validation and the solver.
+
+use parry2d::math::{Isometry, Point as NaPoint, Real, Vector};
+use parry2d::shape::SharedShape;
+
+use crate::constants::VALIDATION_EPS_MM;
+use crate::geom::{
+    board_obstacles_geom, bounds_of_points, convex_hull, convex_penetration, dist_to_outline,
+    piece_geom, poly_contains_point, triangulate_polygon,
+};
+use crate::models::{Board, Piece, Point as Pt};
+
+/// Collision proxy of a posed piece shared by validation and the solver.
+/// Circles keep their exact radius so contacts use a true ball. The convex
+/// parts, Parry shape and bounds are built once so pairwise tests do not
+/// re-triangulate.
+pub struct PieceProxy {
+    pub geom: Vec<Pt>,
+    pub ctr: Pt,
+    pub radius: Option<f64>,
+    /// Axis-aligned bounds (min x, min y, max x, max y) for broad phase.
+    pub bounds: (f64, f64, f64, f64),
+    // Convex parts of a polygon (see `convex_parts`); empty for circles.
+    pub(crate) parts: Vec<Vec<Pt>>,
+    // Parry shape: circles at the origin (see `proxy_iso`), polygons in
+    // world space.
+    shape: Option<SharedShape>,
+}
+
+impl PieceProxy {
+    pub fn new(geom: Vec<Pt>, ctr: Pt, radius: Option<f64>) -> Self {
+        let bounds = match radius {
+            Some(r) => (ctr.x - r, ctr.y - r, ctr.x + r, ctr.y + r),
+            None => bounds_of_points(&geom),
+        };
+        let mut pr = PieceProxy {
+            geom,
+            ctr,
+            radius,
+            bounds,
+            parts: Vec::new(),
+            shape: None,
+        };
+        if radius.is_none() {
+            pr.parts = convex_parts(&pr.geom);
+        }
+        pr.shape = shape_relative_to(&pr, Pt { x: 0.0, y: 0.0 });
+        pr
+    }
+}
+
+/// Proxy of a piece in its current pose, reusing the cached outline while it
+/// is still valid for that pose.
+pub fn piece_proxy(p: &Piece) -> PieceProxy {
+    let (geom, ctr) = match p.cached_geom() {
+        Some((g, c)) => (g.to_vec(), c),
+        None => piece_geom(p),
+    };
+    PieceProxy::new(geom, ctr, p.shape.circle_radius())
+}
+
+/// Proxies of the board's obstacles, so they can be tested against pieces
+/// like fixed pieces.
+pub fn obstacle_proxies(board: &Board) -> Vec<PieceProxy> {
+    board_obstacles_geom(board)
+        .into_iter()
+        .map(|g| {
+            let n = g.len() as f64;
+            let ctr = Pt {
+                x: g.iter().map(|p| p.x).sum::<f64>() / n,
+                y: g.iter().map(|p| p.y).sum::<f64>() / n,
+            };
+            PieceProxy::new(g, ctr, None)
+        })
+        .collect()
+}
+
+/// Whether two bounds overlap by more than `VALIDATION_EPS_MM` on both axes.
+/// Pieces whose bounds do not can't penetrate beyond tolerance either.
+pub fn bounds_overlap(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    let e = VALIDATION_EPS_MM;
+    a.0 < b.2 - e && b.0 < a.2 - e && a.1 < b.3 - e && b.1 < a.3 - e
+}
+
+/// The proxy's collision shape relative to its center, for physics bodies
+/// placed at `pr.ctr`. Same geometry as the validation checks, so circles
+/// use their true radius.
+pub fn proxy_local_shape(pr: &PieceProxy) -> Option<SharedShape> {
+    shape_relative_to(pr, pr.ctr)
+}
+
+fn shape_relative_to(pr: &PieceProxy, origin: Pt) -> Option<SharedShape> {
+    if let Some(r) = pr.radius {
+        return Some(SharedShape::ball(r as Real));
+    }
+    let parts: Vec<(Isometry<Real>, SharedShape)> = pr
+        .parts
+        .iter()
+        .filter_map(|part| {
+            let p: Vec<NaPoint<Real>> = part
+                .iter()
+                .map(|q| NaPoint::new((q.x - origin.x) as Real, (q.y - origin.y) as Real))
+                .collect();
+            let shape = if p.len() == 3 {
+                Some(SharedShape::triangle(p[0], p[1], p[2]))
+            } else {
+                SharedShape::convex_hull(&p)
+            };
+            shape.map(|s| (Isometry::identity(), s))
+        })
+        .collect();
+    (!parts.is_empty()).then(|| SharedShape::compound(parts))
+}
+
+fn proxy_iso(pr: &PieceProxy) -> Isometry<Real> {
+    if pr.radius.is_some() {
+        Isometry::new(Vector::new(pr.ctr.x as Real, pr.ctr.y as Real), 0.0)
+    } else {
+        Isometry::identity()
+    }
+}
+
+// Convex pieces of a polygon proxy: the outline itself when it is convex,
+// else earcut triangles, or the hull.
+pub(crate) fn convex_parts(geom: &[Pt]) -> Vec<Vec<Pt>> {
+    if convex_hull(geom.to_vec()).len() == geom.len() {
+        return vec![geom.to_vec()];
+    }
+    let tris = triangulate_polygon(geom);
+    if tris.is_empty() {
+        vec![convex_hull(geom.to_vec())]
+    } else {
+        tris.iter().map(|t| t.to_vec()).collect()
+    }
+}
+
+/// Whether two pieces overlap by more than `VALIDATION_EPS_MM`.
+pub fn proxies_overlap(a: &PieceProxy, b: &PieceProxy) -> bool {
+    proxies_penetration(a, b).is_some()
+}
+
+/// Penetration depth (mm) of two overlapping pieces, or `None` when they are
+/// apart or within `VALIDATION_EPS_MM`.
+pub fn proxies_penetration(a: &PieceProxy, b: &PieceProxy) -> Option<f64> {
+    let depth = match (a.radius, b.radius) {
+        // Polygon pairs: separating-axis depth per convex part. Parry's
+        // EPA reports zero depth for coincident parts (stacked identical
+        // pieces).
+        (None, None) => a
+            .parts
+            .iter()
+            .flat_map(|ta| b.parts.iter().map(move |tb| convex_penetration(ta, tb)))
+            .fold(f64::NEG_INFINITY, f64::max),
+        (Some(ra), Some(rb)) => ra + rb - (a.ctr.x - b.ctr.x).hypot(a.ctr.y - b.ctr.y),
+        // Measured from the center: Parry finds no contact for a ball inside
+        // a polygon that touches its outline from within.
+        (Some(r), None) => circle_depth(a.ctr, r, &b.geom),
+        (None, Some(r)) => circle_depth(b.ctr, r, &a.geom),
+    };
+    (depth > VALIDATION_EPS_MM).then_some(depth)
+}
+
+// How far a circle reaches into a polygon: its radius past the outline, plus
+// the center's own depth when the center is inside.
+pub(crate) fn circle_depth(ctr: Pt, r: f64, poly: &[Pt]) -> f64 {
+    let d = dist_to_outline(poly, ctr);
+    if poly_contains_point(poly, ctr) {
+        r + d
+    } else {
+        r - d
+    }
+}
+
+/// Three-layer containment check: a piece is outside the border when it
+/// reaches the outer layer, penetrates the inner border beyond tolerance, or
+/// sits in the ring without touching the inner border.
+pub fn proxy_outside_border(pr: &PieceProxy, inner: &[Vec<Pt>], outer: Option<&[Vec<Pt>]>) -> bool {
+    proxy_border_depth(pr, inner, outer).is_some()
+}
+
+/// How far (mm) a piece that fails the containment check reaches past the
+/// inner border; `None` when the piece counts as inside.
+pub fn proxy_border_depth(
+    pr: &PieceProxy,
+    inner: &[Vec<Pt>],
+    outer: Option<&[Vec<Pt>]>,
+) -> Option<f64> {
+    let eps_mm = VALIDATION_EPS_MM;
+    // Rapier-based: use minimal Parry distance to inner border segments
+    // d < -eps_mm => inner border penetration (i.e., into middle ring)
+    let mut d = f64::INFINITY;
+    if let Some(sp) = &pr.shape {
+        let iso_shape = proxy_iso(pr);
+        // Board segments live in world space; do NOT translate them.
+        let iso_seg = Isometry::identity();
+        for g in inner {
+            let n = g.len();
+            for j in 0..n {
+                let a = g[j];
+                let b = g[(j + 1) % n];
+                let seg = SharedShape::segment(
+                    NaPoint::new(a.x as Real, a.y as Real),
+                    NaPoint::new(b.x as Real, b.y as Real),
+                );
+                if let Ok(Some(ct)) =
+                    parry2d::query::contact(&iso_shape, sp.as_ref(), &iso_seg, seg.as_ref(), 1.0e3)
+                {
+                    d = d.min(ct.dist as f64);
+                }
+            }
+        }
+    }
+    // Farthest vertex outside the inner border.
+    let vertex_depth = pr
+        .geom
+        .iter()
+        .filter(|p| !inner.iter().any(|g| poly_contains_point(g, **p)))
+        .map(|p| {
+            inner
+                .iter()
+                .map(|g| dist_to_outline(g, *p))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max);
+    let depth = if d.is_finite() {
+        vertex_depth.max(-d)
+    } else {
+        vertex_depth
+    };
+    // Outer containment: if not fully inside outer, it's in the outer layer -> error
+    let outside_outer = if let Some(out) = outer {
+        !pr.geom
+            .iter()
+            .all(|p| out.iter().any(|poly| poly_contains_point(poly, *p)))
+    } else {
+        false
+    };
+    if outside_outer || (d.is_finite() && d < -eps_mm) {
+        return Some(depth);
+    }
+    let fully_inside = pr
+        .geom
+        .iter()
+        .all(|p| inner.iter().any(|g| poly_contains_point(g, *p)));
+    // Inside outer but not fully inside inner => touching the ring (beyond tolerance)
+    let touching = d.is_finite() && d <= eps_mm;
+    (!fully_inside && !touching).then_some(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PolygonPoint, Shape};
+
+    fn rect(w: f64, h: f64, x: f64, y: f64) -> PieceProxy {
+        let mut p = Piece::new(Shape::Rect { w, h });
+        p.at = Some([x, y]);
+        piece_proxy(&p)
+    }
+
+    fn circle(d: f64, x: f64, y: f64) -> PieceProxy {
+        let mut p = Piece::new(Shape::Circle { d });
+        p.at = Some([x, y]);
+        piece_proxy(&p)
+    }
+
+    fn square_board(side: f64) -> Vec<Vec<Pt>> {
+        vec![vec![
+            Pt { x: 0.0, y: 0.0 },
+            Pt { x: side, y: 0.0 },
+            Pt { x: side, y: side },
+            Pt { x: 0.0, y: side },
+        ]]
+    }
+
+    #[test]
+    fn overlapping_rects_report_their_depth() {
+        let a = rect(30.0, 30.0, 0.0, 0.0);
+        let b = rect(30.0, 30.0, 25.0, 10.0);
+        let depth = proxies_penetration(&a, &b).unwrap();
+        assert!((depth - 5.0).abs() < 1e-6);
+        assert!(proxies_overlap(&a, &b));
+    }
+
+    #[test]
+    fn touching_or_tolerated_contact_is_not_an_overlap() {
+        let a = rect(30.0, 30.0, 0.0, 0.0);
+        assert!(proxies_penetration(&a, &rect(30.0, 30.0, 30.0, 0.0)).is_none());
+        assert!(proxies_penetration(&a, &rect(30.0, 30.0, 29.95, 0.0)).is_none());
+        assert!(proxies_penetration(&a, &rect(30.0, 30.0, 40.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn stacked_identical_pieces_overlap_fully() {
+        let a = rect(30.0, 60.0, 10.0, 10.0);
+        let b = rect(30.0, 60.0, 10.0, 10.0);
+        // Measured per convex part, so less than the full width but deep
+        let depth = proxies_penetration(&a, &b).unwrap();
+        assert!(depth > 20.0);
+    }
+
+    #[test]
+    fn circles_use_their_true_radius() {
+        let a = circle(30.0, 0.0, 0.0);
+        let depth = proxies_penetration(&a, &circle(30.0, 28.0, 0.0)).unwrap();
+        assert!((depth - 2.0).abs() < 1e-3);
+        // Clear of the tessellated outline's facets by less than the tolerance
+        assert!(proxies_penetration(&a, &circle(30.0, 30.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn circle_inside_a_polygon_overlaps() {
+        let a = rect(30.0, 60.0, 0.0, 0.0);
+        // Touches the rectangle's sides from within
+        let depth = proxies_penetration(&circle(30.0, 15.0, 15.0), &a).unwrap();
+        assert!((depth - 30.0).abs() < 1e-6);
+        let depth = proxies_penetration(&a, &circle(20.0, 15.0, 30.0)).unwrap();
+        assert!((depth - 25.0).abs() < 1e-6);
+        assert!(proxies_penetration(&a, &circle(30.0, 45.0, 30.0)).is_none());
+    }
+
+    #[test]
+    fn piece_inside_the_border() {
+        let board = square_board(100.0);
+        assert!(proxy_border_depth(&rect(30.0, 30.0, 10.0, 10.0), &board, None).is_none());
+        // Flush with the border counts as inside
+        assert!(proxy_border_depth(&rect(30.0, 30.0, 0.0, 70.0), &board, None).is_none());
+    }
+
+    #[test]
+    fn piece_across_the_border_reports_how_far_it_reaches() {
+        let board = square_board(100.0);
+        let depth = proxy_border_depth(&rect(30.0, 30.0, 75.0, 10.0), &board, None).unwrap();
+        assert!((depth - 5.0).abs() < 1e-6);
+        assert!(proxy_outside_border(
+            &rect(30.0, 30.0, -2.0, 10.0),
+            &board,
+            None
+        ));
+    }
+
+    #[test]
+    fn piece_beyond_the_outer_ring_is_outside() {
+        let board = square_board(100.0);
+        let outer = vec![vec![
+            Pt { x: -8.0, y: -8.0 },
+            Pt { x: 108.0, y: -8.0 },
+            Pt { x: 108.0, y: 108.0 },
+            Pt { x: -8.0, y: 108.0 },
+        ]];
+        let far = rect(30.0, 30.0, 200.0, 10.0);
+        assert!(proxy_outside_border(&far, &board, Some(&outer)));
+    }
+
+    #[test]
+    fn obstacles_penetrate_like_fixed_pieces() {
+        let corner = |x, y| PolygonPoint::Point([x, y]);
+        let board = Board {
+            type_: Some("rect".into()),
+            w: Some(100.0),
+            h: Some(100.0),
+            obstacles: Some(vec![vec![
+                corner(40.0, 40.0),
+                corner(60.0, 40.0),
+                corner(60.0, 60.0),
+                corner(40.0, 60.0),
+            ]]),
+            ..Default::default()
+        };
+        let obstacles = obstacle_proxies(&board);
+        assert_eq!(obstacles.len(), 1);
+        let ob = &obstacles[0];
+        let depth = proxies_penetration(&rect(30.0, 30.0, 15.0, 40.0), ob).unwrap();
+        assert!((depth - 5.0).abs() < 1e-6);
+        let depth = proxies_penetration(&circle(20.0, 50.0, 65.0), ob).unwrap();
+        assert!((depth - 5.0).abs() < 1e-6);
+        assert!(proxies_penetration(&rect(30.0, 30.0, 10.0, 40.0), ob).is_none());
+    }
+}
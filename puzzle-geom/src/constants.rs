@@ -0,0 +1,18 @@
+// Geometry constants shared across crates. Values are in millimeters.
+
+/// Ring width for the board middle layer (mm).
+pub const RING_WIDTH_MM: f64 = 8.0;
+/// Validation tolerance: touching or tiny overlaps are allowed (mm).
+pub const VALIDATION_EPS_MM: f64 = 0.10;
+/// Uncovered board area allowed for a fill goal (and mismatch for a
+/// silhouette), as a sliver this wide (mm) along the board outline.
+pub const FILL_SLIVER_MM: f64 = 0.10;
+/// Most sides a regular polygon may have; past this it is drawn as a circle
+/// anyway.
+pub const MAX_POLYGON_SIDES: u32 = 64;
+/// Distance (mm) within which a piece counts as lying in its solution place.
+pub const SOLUTION_TOL_MM: f64 = 1.0;
+/// Gap between the board's outer ring and the staging tray (mm).
+pub const TRAY_GAP_MM: f64 = 15.0;
+/// Spacing between pieces packed into the tray (mm).
+pub const TRAY_SPACING_MM: f64 = 5.0;
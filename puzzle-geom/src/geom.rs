@@ -0,0 +1,772 @@
+// Piece and board geometry in millimetres.
+
+use earcutr::earcut;
+
+use crate::constants::VALIDATION_EPS_MM;
+use crate::models::{Board, Piece, Point as Pt, PolygonPoint, Shape};
+
+pub fn rotate_point(p: Pt, c: Pt, ang: f64, flip: bool) -> Pt {
+    let mut dx = p.x - c.x;
+    let dy = p.y - c.y;
+    if flip {
+        dx = -dx;
+    }
+    let (s, ca) = ang.sin_cos();
+    Pt {
+        x: c.x + dx * ca - dy * s,
+        y: c.y + dx * s + dy * ca,
+    }
+}
+
+/// Rebuild the cached `__geom`/`__ctr` of a piece whose pose changed since
+/// they were computed. Returns whether anything was rebuilt.
+pub fn refresh_geom_cache(p: &mut Piece) -> bool {
+    if p.cached_geom().is_some() {
+        return false;
+    }
+    let (geom, ctr) = piece_geom(p);
+    p.__geom = Some(geom);
+    p.__ctr = Some(ctr);
+    p.__pose = Some(p.pose());
+    true
+}
+
+fn piece_rotation(p: &Piece) -> f64 {
+    (p.rotation.unwrap_or(0.0)).to_radians()
+}
+
+fn piece_flip(p: &Piece) -> bool {
+    p.flip.unwrap_or(false)
+}
+
+pub fn piece_geom(p: &Piece) -> (Vec<Pt>, Pt) {
+    let rot = piece_rotation(p);
+    let flip = piece_flip(p);
+    let anchor = p.anchor.clone().unwrap_or_else(|| "bottomleft".to_string());
+    let apply = |pts: Vec<Pt>, ctr: Pt| -> (Vec<Pt>, Pt) {
+        let out = pts
+            .into_iter()
+            .map(|q| rotate_point(q, ctr, rot, flip))
+            .collect();
+        (out, ctr)
+    };
+    match p.shape {
+        Shape::Rect { w, h } => {
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let bl = if anchor == "center" {
+                Pt {
+                    x: at[0] - w / 2.0,
+                    y: at[1] - h / 2.0,
+                }
+            } else {
+                Pt { x: at[0], y: at[1] }
+            };
+            let tl = Pt {
+                x: bl.x,
+                y: bl.y + h,
+            };
+            let tr = Pt {
+                x: bl.x + w,
+                y: bl.y + h,
+            };
+            let br = Pt {
+                x: bl.x + w,
+                y: bl.y,
+            };
+            let ctr = Pt {
+                x: bl.x + w / 2.0,
+                y: bl.y + h / 2.0,
+            };
+            apply(vec![bl, br, tr, tl], ctr)
+        }
+        Shape::EquilateralTriangle { side: s } => {
+            let h = s * 3.0_f64.sqrt() / 2.0;
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let bl = if anchor == "center" {
+                Pt {
+                    x: at[0] - s / 2.0,
+                    y: at[1] - h / 3.0,
+                }
+            } else {
+                Pt { x: at[0], y: at[1] }
+            };
+            let a = Pt { x: bl.x, y: bl.y };
+            let b = Pt {
+                x: bl.x + s,
+                y: bl.y,
+            };
+            let c = Pt {
+                x: bl.x + s / 2.0,
+                y: bl.y + h,
+            };
+            let ctr = Pt {
+                x: (a.x + b.x + c.x) / 3.0,
+                y: (a.y + b.y + c.y) / 3.0,
+            };
+            apply(vec![a, b, c], ctr)
+        }
+        Shape::RightTriangle { a: la, b: lb } => {
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let a = Pt { x: at[0], y: at[1] };
+            let b = Pt {
+                x: at[0] + la,
+                y: at[1],
+            };
+            let c = Pt {
+                x: at[0],
+                y: at[1] + lb,
+            };
+            let ctr = Pt {
+                x: (a.x + b.x + c.x) / 3.0,
+                y: (a.y + b.y + c.y) / 3.0,
+            };
+            apply(vec![a, b, c], ctr)
+        }
+        Shape::RegularPolygon { n, side } => {
+            let n = n as i32;
+            let r = side / (2.0 * (std::f64::consts::PI / n as f64).sin());
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let ctr = Pt { x: at[0], y: at[1] };
+            let base_ang = piece_rotation(p)
+                + if piece_flip(p) {
+                    std::f64::consts::PI
+                } else {
+                    0.0
+                };
+            let mut pts = Vec::new();
+            for i in 0..n {
+                let a = base_ang + (i as f64) * 2.0 * std::f64::consts::PI / (n as f64);
+                pts.push(Pt {
+                    x: ctr.x + r * a.cos(),
+                    y: ctr.y + r * a.sin(),
+                });
+            }
+            (pts, ctr)
+        }
+        Shape::Circle { d } => {
+            // Use a polyline for computation/hit-testing; render as a true circle.
+            let r = d / 2.0;
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let ctr = Pt { x: at[0], y: at[1] };
+            let pts = tessellate_circle_polyline(ctr, r, 0.3);
+            (pts, ctr)
+        }
+        Shape::IsoscelesTrapezoid {
+            base_bottom: b0,
+            base_top: b1,
+            height: h,
+        } => {
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let bl = Pt { x: at[0], y: at[1] };
+            let br = Pt {
+                x: bl.x + b0,
+                y: bl.y,
+            };
+            let tl = Pt {
+                x: bl.x + (b0 - b1) / 2.0,
+                y: bl.y + h,
+            };
+            let tr = Pt {
+                x: tl.x + b1,
+                y: tl.y,
+            };
+            let ctr = Pt {
+                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
+                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
+            };
+            apply(vec![bl, br, tr, tl], ctr)
+        }
+        Shape::Parallelogram {
+            base: b,
+            offset_top: off,
+            height: h,
+        } => {
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let bl = Pt { x: at[0], y: at[1] };
+            let br = Pt {
+                x: bl.x + b,
+                y: bl.y,
+            };
+            let tl = Pt {
+                x: bl.x + off,
+                y: bl.y + h,
+            };
+            let tr = Pt {
+                x: tl.x + b,
+                y: tl.y,
+            };
+            let ctr = Pt {
+                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
+                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
+            };
+            apply(vec![bl, br, tr, tl], ctr)
+        }
+        Shape::Polygon { ref points } => {
+            let at = p.at.unwrap_or([0.0, 0.0]);
+            let pts = points
+                .iter()
+                .map(|v| Pt {
+                    x: v[0] + at[0],
+                    y: v[1] + at[1],
+                })
+                .collect::<Vec<_>>();
+            let n = pts.len().max(1) as f64;
+            let ctr = pts.iter().fold(Pt { x: 0.0, y: 0.0 }, |acc, q| Pt {
+                x: acc.x + q.x,
+                y: acc.y + q.y,
+            });
+            let ctr = Pt {
+                x: ctr.x / n,
+                y: ctr.y / n,
+            };
+            apply(pts, ctr)
+        }
+    }
+}
+
+// Move a piece by (dx, dy). Every shape is placed by `at` (polygons add it to
+// their points), so only `at` changes; a missing `at` counts as the origin.
+pub fn translate_piece(p: &mut Piece, dx: f64, dy: f64) {
+    let at = p.at.unwrap_or([0.0, 0.0]);
+    p.at = Some([at[0] + dx, at[1] + dy]);
+}
+
+// Approximate a circle by a polyline with maximum sagitta error `max_err_mm`.
+// Returns vertices in CCW order.
+pub fn tessellate_circle_polyline(center: Pt, r: f64, max_err_mm: f64) -> Vec<Pt> {
+    let r = r.max(0.0);
+    if r <= 0.0 {
+        return vec![center];
+    }
+    let e = max_err_mm.max(0.05).min(r);
+    // dtheta based on sagitta formula; clamp to reasonable range
+    let mut dtheta = 2.0 * (1.0 - e / r).clamp(-1.0, 1.0).acos();
+    if !dtheta.is_finite() || dtheta <= 0.0 {
+        dtheta = std::f64::consts::PI / 16.0;
+    }
+    let mut n = (2.0 * std::f64::consts::PI / dtheta).ceil() as usize;
+    n = n.clamp(12, 128);
+    let mut pts = Vec::with_capacity(n);
+    for i in 0..n {
+        let a = (i as f64) * 2.0 * std::f64::consts::PI / (n as f64);
+        pts.push(Pt {
+            x: center.x + r * a.cos(),
+            y: center.y + r * a.sin(),
+        });
+    }
+    pts
+}
+
+pub fn convex_hull(mut pts: Vec<Pt>) -> Vec<Pt> {
+    // Monotone chain in f64
+    if pts.len() <= 3 {
+        return pts;
+    }
+    pts.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    let cross = |o: &Pt, a: &Pt, b: &Pt| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    let mut lower: Vec<Pt> = Vec::new();
+    for p in &pts {
+        while lower.len() >= 2 && cross(&lower[lower.len() - 2], &lower[lower.len() - 1], p) <= 0.0
+        {
+            lower.pop();
+        }
+        lower.push(*p);
+    }
+    let mut upper: Vec<Pt> = Vec::new();
+    for p in pts.iter().rev() {
+        while upper.len() >= 2 && cross(&upper[upper.len() - 2], &upper[upper.len() - 1], p) <= 0.0
+        {
+            upper.pop();
+        }
+        upper.push(*p);
+    }
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+pub fn triangulate_polygon(points: &[Pt]) -> Vec<[Pt; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    // Flattened [x0, y0, x1, y1, ...]
+    let mut flat: Vec<f64> = Vec::with_capacity(points.len() * 2);
+    for p in points {
+        flat.push(p.x);
+        flat.push(p.y);
+    }
+    let idx = earcut(&flat, &[], 2).unwrap_or_default();
+    let mut tris = Vec::with_capacity(idx.len() / 3);
+    for t in (0..idx.len()).step_by(3) {
+        let a = idx[t] as usize;
+        let b = idx[t + 1] as usize;
+        let c = idx[t + 2] as usize;
+        if a < points.len() && b < points.len() && c < points.len() {
+            tris.push([points[a], points[b], points[c]]);
+        }
+    }
+    tris
+}
+
+pub fn normalize(p: Pt) -> Pt {
+    let len = (p.x * p.x + p.y * p.y).sqrt();
+    if len == 0.0 {
+        Pt { x: 0.0, y: 0.0 }
+    } else {
+        Pt {
+            x: p.x / len,
+            y: p.y / len,
+        }
+    }
+}
+
+pub fn poly_to_points(poly: &[PolygonPoint]) -> Vec<Pt> {
+    let mut out: Vec<Pt> = Vec::new();
+    let n = poly.len();
+    let mut i = 0;
+    while i < n {
+        match &poly[i] {
+            PolygonPoint::Point([x, y]) => {
+                out.push(Pt { x: *x, y: *y });
+                i += 1;
+            }
+            PolygonPoint::Rounded([x, y, r]) => {
+                if out.is_empty() || i + 1 >= n {
+                    i += 1;
+                    continue;
+                }
+                let prev = *out.last().unwrap();
+                let next_xy = match &poly[i + 1] {
+                    PolygonPoint::Point([nx, ny]) => Pt { x: *nx, y: *ny },
+                    PolygonPoint::Rounded([nx, ny, _]) => Pt { x: *nx, y: *ny },
+                };
+                let corner = Pt { x: *x, y: *y };
+                let radius = *r;
+                let v1 = normalize(Pt {
+                    x: prev.x - corner.x,
+                    y: prev.y - corner.y,
+                });
+                let v2 = normalize(Pt {
+                    x: next_xy.x - corner.x,
+                    y: next_xy.y - corner.y,
+                });
+                let start = Pt {
+                    x: corner.x + v1.x * radius,
+                    y: corner.y + v1.y * radius,
+                };
+                let end = Pt {
+                    x: corner.x + v2.x * radius,
+                    y: corner.y + v2.y * radius,
+                };
+                out.push(start);
+                let center = Pt {
+                    x: corner.x + (v1.x + v2.x) * radius,
+                    y: corner.y + (v1.y + v2.y) * radius,
+                };
+                let start_ang = (start.y - center.y).atan2(start.x - center.x);
+                let end_ang = (end.y - center.y).atan2(end.x - center.x);
+                let steps = 24;
+                for j in 1..=steps {
+                    let t = j as f64 / steps as f64;
+                    let ang = start_ang + (end_ang - start_ang) * t;
+                    out.push(Pt {
+                        x: center.x + radius * ang.cos(),
+                        y: center.y + radius * ang.sin(),
+                    });
+                }
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+pub fn board_to_geom(board: &Board) -> Option<Vec<Vec<Pt>>> {
+    match board.type_.as_deref() {
+        Some("rect") => {
+            let w = board.w.unwrap_or(0.0);
+            let h = board.h.unwrap_or(0.0);
+            Some(vec![vec![
+                Pt { x: 0.0, y: 0.0 },
+                Pt { x: w, y: 0.0 },
+                Pt { x: w, y: h },
+                Pt { x: 0.0, y: h },
+            ]])
+        }
+        Some("polygon") => {
+            if let Some(polys) = &board.polygons {
+                let geoms = polys
+                    .iter()
+                    .map(|poly| poly_to_points(poly))
+                    .collect::<Vec<_>>();
+                if geoms.is_empty() { None } else { Some(geoms) }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Outlines of the board's obstacles; entries with fewer than three points
+/// are skipped.
+pub fn board_obstacles_geom(board: &Board) -> Vec<Vec<Pt>> {
+    board
+        .obstacles
+        .iter()
+        .flatten()
+        .map(|poly| poly_to_points(poly))
+        .filter(|pts| pts.len() >= 3)
+        .collect()
+}
+
+pub fn board_outer_geom(board: &Board, ring: f64) -> Option<Vec<Vec<Pt>>> {
+    match board.type_.as_deref() {
+        Some("rect") => {
+            let w = board.w.unwrap_or(0.0);
+            let h = board.h.unwrap_or(0.0);
+            Some(vec![vec![
+                Pt { x: -ring, y: -ring },
+                Pt {
+                    x: w + ring,
+                    y: -ring,
+                },
+                Pt {
+                    x: w + ring,
+                    y: h + ring,
+                },
+                Pt {
+                    x: -ring,
+                    y: h + ring,
+                },
+            ]])
+        }
+        Some("polygon") => {
+            if let Some(polys) = &board.polygons {
+                let mut outs: Vec<Vec<Pt>> = Vec::new();
+                for poly in polys {
+                    let inner = poly_to_points(poly);
+                    if inner.len() >= 3 {
+                        outs.push(polygon_offset_rounded(&inner, ring, 8));
+                    }
+                }
+                if outs.is_empty() { None } else { Some(outs) }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn polygon_offset_rounded(inner: &[Pt], r: f64, _arc_samples: usize) -> Vec<Pt> {
+    use geo::algorithm::buffer::Buffer;
+    use geo::{Coord, LineString, Polygon};
+
+    let r = r.max(0.0);
+    if inner.len() < 3 || r <= 0.0 {
+        return inner.to_vec();
+    }
+
+    let coords: Vec<Coord> = inner.iter().map(|p| Coord { x: p.x, y: p.y }).collect();
+    let poly = Polygon::new(LineString::from(coords), vec![]);
+    let mp = poly.buffer(r);
+    let poly = mp.0.into_iter().next().unwrap();
+    let mut out: Vec<Pt> = poly
+        .exterior()
+        .0
+        .iter()
+        .map(|c| Pt { x: c.x, y: c.y })
+        .collect();
+    if out.len() > 1 {
+        let first = out.first().unwrap();
+        let last = out.last().unwrap();
+        if (first.x - last.x).abs() < 1e-9 && (first.y - last.y).abs() < 1e-9 {
+            out.pop();
+        }
+    }
+    out
+}
+
+pub fn poly_contains_point(poly: &[Pt], p: Pt) -> bool {
+    let (x, y) = (p.x, p.y);
+    let mut inside = false;
+    let n = poly.len();
+    let mut j = n - 1;
+    for i in 0..n {
+        let xi = poly[i].x;
+        let yi = poly[i].y;
+        let xj = poly[j].x;
+        let yj = poly[j].y;
+        let intersect =
+            ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi + 1e-12) + xi);
+        if intersect {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn segments_intersect(a1: Pt, a2: Pt, b1: Pt, b2: Pt) -> bool {
+    fn cross(a: Pt, b: Pt, c: Pt) -> f64 {
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    }
+    // Near-zero orientations count as touching, so flush edges and shared
+    // corners of neighbouring pieces are not reported as crossings.
+    let tol = 1e-6;
+    let d1 = cross(a1, a2, b1);
+    let d2 = cross(a1, a2, b2);
+    let d3 = cross(b1, b2, a1);
+    let d4 = cross(b1, b2, a2);
+    if ((d1 > tol && d2 < -tol) || (d1 < -tol && d2 > tol))
+        && ((d3 > tol && d4 < -tol) || (d3 < -tol && d4 > tol))
+    {
+        return true;
+    }
+    false
+}
+
+pub fn polygons_intersect(a: &[Pt], b: &[Pt]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    let an = a.len();
+    let bn = b.len();
+    for i in 0..an {
+        let a1 = a[i];
+        let a2 = a[(i + 1) % an];
+        for j in 0..bn {
+            let b1 = b[j];
+            let b2 = b[(j + 1) % bn];
+            if segments_intersect(a1, a2, b1, b2) {
+                return true;
+            }
+        }
+    }
+    // Containment: ignore vertices lying on the other outline (touching)
+    let strictly_inside = |poly: &[Pt], p: Pt| {
+        poly_contains_point(poly, p) && dist_to_outline(poly, p) > VALIDATION_EPS_MM
+    };
+    if strictly_inside(a, b[0]) || strictly_inside(b, a[0]) {
+        return true;
+    }
+    false
+}
+
+/// Penetration depth of two convex polygons along their best separating
+/// axis (negative when they are apart).
+pub fn convex_penetration(a: &[Pt], b: &[Pt]) -> f64 {
+    convex_depth_above(a, b, f64::NEG_INFINITY).unwrap_or(f64::NEG_INFINITY)
+}
+
+/// Whether two convex polygons overlap by more than `eps`; cheaper than
+/// `convex_penetration` for pairs that are apart.
+pub fn convex_overlap(a: &[Pt], b: &[Pt], eps: f64) -> bool {
+    convex_depth_above(a, b, eps).is_some()
+}
+
+// Separating-axis depth of two convex polygons, or `None` as soon as an axis
+// shows it is at most `floor`.
+fn convex_depth_above(a: &[Pt], b: &[Pt], floor: f64) -> Option<f64> {
+    fn project(poly: &[Pt], nx: f64, ny: f64) -> (f64, f64) {
+        poly.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                let v = p.x * nx + p.y * ny;
+                (lo.min(v), hi.max(v))
+            })
+    }
+    let mut depth = f64::INFINITY;
+    for poly in [a, b] {
+        let n = poly.len();
+        for i in 0..n {
+            let (p, q) = (poly[i], poly[(i + 1) % n]);
+            let (ex, ey) = (q.x - p.x, q.y - p.y);
+            let len = (ex * ex + ey * ey).sqrt();
+            if len < 1e-9 {
+                continue;
+            }
+            let (nx, ny) = (-ey / len, ex / len);
+            let (amin, amax) = project(a, nx, ny);
+            let (bmin, bmax) = project(b, nx, ny);
+            depth = depth.min((amax - bmin).min(bmax - amin));
+            if depth <= floor {
+                return None;
+            }
+        }
+    }
+    Some(depth)
+}
+
+pub fn dist_to_outline(poly: &[Pt], p: Pt) -> f64 {
+    let n = poly.len();
+    let mut best = f64::INFINITY;
+    for i in 0..n {
+        let a = poly[i];
+        let b = poly[(i + 1) % n];
+        let (vx, vy) = (b.x - a.x, b.y - a.y);
+        let len2 = vx * vx + vy * vy;
+        let t = if len2 > 0.0 {
+            (((p.x - a.x) * vx + (p.y - a.y) * vy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (dx, dy) = (a.x + vx * t - p.x, a.y + vy * t - p.y);
+        best = best.min((dx * dx + dy * dy).sqrt());
+    }
+    best
+}
+
+pub fn bounds_of_points(pts: &[Pt]) -> (f64, f64, f64, f64) {
+    let mut minx = f64::INFINITY;
+    let mut miny = f64::INFINITY;
+    let mut maxx = f64::NEG_INFINITY;
+    let mut maxy = f64::NEG_INFINITY;
+    for p in pts {
+        minx = minx.min(p.x);
+        miny = miny.min(p.y);
+        maxx = maxx.max(p.x);
+        maxy = maxy.max(p.y);
+    }
+    (minx, miny, maxx, maxy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(w: f64, h: f64, at: [f64; 2]) -> Piece {
+        let mut p = Piece::new(Shape::Rect { w, h });
+        p.at = Some(at);
+        p
+    }
+
+    fn close(a: Pt, b: (f64, f64)) -> bool {
+        (a.x - b.0).abs() < 1e-9 && (a.y - b.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn rect_outline_from_bottom_left_or_center() {
+        let (g, c) = piece_geom(&rect(30.0, 60.0, [10.0, 20.0]));
+        let want = [(10.0, 20.0), (40.0, 20.0), (40.0, 80.0), (10.0, 80.0)];
+        assert!(g.iter().zip(want).all(|(p, w)| close(*p, w)));
+        assert!(close(c, (25.0, 50.0)));
+
+        let mut p = rect(30.0, 60.0, [25.0, 50.0]);
+        p.anchor = Some("center".into());
+        assert!(close(piece_geom(&p).0[0], (10.0, 20.0)));
+    }
+
+    #[test]
+    fn rotation_turns_about_the_center() {
+        let mut p = rect(30.0, 60.0, [10.0, 20.0]);
+        p.rotation = Some(90.0);
+        let (g, c) = piece_geom(&p);
+        assert!(close(c, (25.0, 50.0)));
+        assert!(close(g[0], (55.0, 35.0)));
+        assert!(close(g[2], (-5.0, 65.0)));
+    }
+
+    #[test]
+    fn flip_mirrors_across_the_center() {
+        let mut p = Piece::new(Shape::RightTriangle { a: 30.0, b: 60.0 });
+        p.at = Some([0.0, 0.0]);
+        p.flip = Some(true);
+        let (g, c) = piece_geom(&p);
+        assert!(close(c, (10.0, 20.0)));
+        // The right angle moves from the left to the right end of leg `a`
+        assert!(close(g[0], (20.0, 0.0)));
+        assert!(close(g[1], (-10.0, 0.0)));
+        assert!(close(g[2], (20.0, 60.0)));
+    }
+
+    #[test]
+    fn circle_outline_stays_within_the_radius() {
+        let mut p = Piece::new(Shape::Circle { d: 30.0 });
+        p.at = Some([50.0, 50.0]);
+        let (g, c) = piece_geom(&p);
+        assert!(close(c, (50.0, 50.0)));
+        assert!(g.len() >= 12);
+        for q in g {
+            let r = (q.x - 50.0).hypot(q.y - 50.0);
+            assert!((r - 15.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn translate_moves_at() {
+        let mut p = rect(30.0, 30.0, [0.0, 0.0]);
+        translate_piece(&mut p, 5.0, -2.0);
+        assert_eq!(p.at, Some([5.0, -2.0]));
+
+        let mut poly = Piece::new(Shape::Polygon {
+            points: vec![[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]],
+        });
+        translate_piece(&mut poly, 1.0, 2.0);
+        translate_piece(&mut poly, 1.0, 0.0);
+        assert_eq!(poly.at, Some([2.0, 2.0]));
+        let (g, _) = piece_geom(&poly);
+        assert!(close(g[1], (12.0, 2.0)));
+    }
+
+    #[test]
+    fn rect_board_outline() {
+        let board = Board {
+            type_: Some("rect".into()),
+            w: Some(100.0),
+            h: Some(50.0),
+            ..Default::default()
+        };
+        let g = board_to_geom(&board).unwrap();
+        assert_eq!(g.len(), 1);
+        let want = [(0.0, 0.0), (100.0, 0.0), (100.0, 50.0), (0.0, 50.0)];
+        assert!(g[0].iter().zip(want).all(|(p, w)| close(*p, w)));
+    }
+
+    #[test]
+    fn polygon_board_regions_and_rounded_corners() {
+        let board = Board {
+            type_: Some("polygon".into()),
+            polygons: Some(vec![
+                vec![
+                    PolygonPoint::Point([0.0, 0.0]),
+                    PolygonPoint::Point([40.0, 0.0]),
+                    PolygonPoint::Rounded([40.0, 40.0, 10.0]),
+                    PolygonPoint::Point([0.0, 40.0]),
+                ],
+                vec![
+                    PolygonPoint::Point([50.0, 0.0]),
+                    PolygonPoint::Point([60.0, 0.0]),
+                    PolygonPoint::Point([60.0, 10.0]),
+                ],
+            ]),
+            ..Default::default()
+        };
+        let g = board_to_geom(&board).unwrap();
+        assert_eq!(g.len(), 2);
+        assert_eq!(g[1].len(), 3);
+        // The rounded corner is replaced by an arc from (40, 30) to (30, 40)
+        assert!(g[0].iter().all(|p| !close(*p, (40.0, 40.0))));
+        assert!(g[0].iter().any(|p| close(*p, (40.0, 30.0))));
+        assert!(g[0].iter().any(|p| close(*p, (30.0, 40.0))));
+        for p in &g[0] {
+            assert!(p.x <= 40.0 + 1e-9 && p.y <= 40.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn board_without_outline() {
+        assert!(board_to_geom(&Board::default()).is_none());
+        let empty = Board {
+            type_: Some("polygon".into()),
+            polygons: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(board_to_geom(&empty).is_none());
+    }
+}
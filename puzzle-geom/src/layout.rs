@@ -0,0 +1,276 @@
+// Building playable puzzles from counts specs, and the staging tray that
+// holds pieces not yet placed on the board.
+
+use crate::constants::{RING_WIDTH_MM, TRAY_GAP_MM, TRAY_SPACING_MM};
+use crate::geom::{board_outer_geom, board_to_geom, bounds_of_points, piece_geom, translate_piece};
+use crate::models::{Board, CountsSpec, Piece, Puzzle, Shape, ShapeDef, ShapesCatalog};
+
+type Bounds = (f64, f64, f64, f64);
+
+/// Shelf width of the tray when there is no board to size it by (mm).
+const DEFAULT_TRAY_W_MM: f64 = 200.0;
+/// Scrambled rotations are multiples of this, so the default rotation snap
+/// can bring every piece back upright.
+const SCRAMBLE_STEP_DEG: f64 = 15.0;
+
+/// Staging area to the right of the board. Pieces are packed in rows from
+/// `top` downwards; a piece lying within the area, give or take the spacing
+/// between pieces, counts as in the tray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tray {
+    pub x: f64,
+    pub top: f64,
+    /// Row width used when packing (mm).
+    pub width: f64,
+    /// How far the tray reaches below `top` (mm).
+    pub depth: f64,
+}
+
+impl Tray {
+    /// Whether a piece with these bounds sits in the tray.
+    pub fn contains(&self, bounds: (f64, f64, f64, f64)) -> bool {
+        let pad = TRAY_SPACING_MM;
+        bounds.0 >= self.x - pad
+            && bounds.2 <= self.x + self.width + pad
+            && bounds.1 >= self.top - self.depth - pad
+            && bounds.3 <= self.top + pad
+    }
+}
+
+/// The tray beside `board` for `pieces`: past the board's outer ring
+/// (whatever its coordinates), as wide as the board itself or the largest
+/// piece, and deep enough to give every piece a row of its own.
+pub fn tray_for(board: Option<&Board>, pieces: &[Piece]) -> Tray {
+    // No rotation makes a piece wider or taller than its diagonal
+    let sizes: Vec<f64> = pieces
+        .iter()
+        .map(|p| piece_geom(p).0)
+        .filter(|g| !g.is_empty())
+        .map(|g| {
+            let b = bounds_of_points(&g);
+            (b.2 - b.0).hypot(b.3 - b.1)
+        })
+        .collect();
+    let largest = sizes.iter().copied().fold(0.0, f64::max);
+    let depth = sizes.iter().map(|s| s + TRAY_SPACING_MM).sum();
+    let geoms = board.and_then(|b| board_outer_geom(b, RING_WIDTH_MM).or_else(|| board_to_geom(b)));
+    match geoms {
+        Some(g) if !g.is_empty() => {
+            let (minx, _miny, maxx, maxy) = bounds_of_points(&g.concat());
+            Tray {
+                x: maxx + TRAY_GAP_MM,
+                top: maxy,
+                width: (maxx - minx).max(largest),
+                depth,
+            }
+        }
+        _ => Tray {
+            x: 0.0,
+            top: 0.0,
+            width: DEFAULT_TRAY_W_MM.max(largest),
+            depth,
+        },
+    }
+}
+
+/// Put every piece back into the tray, upright and unflipped, keeping the
+/// order of `pieces`. Shelf packing: tallest pieces first, rows left to
+/// right no wider than the tray, each row below the previous one.
+pub fn pack_into_tray(pieces: &mut [Piece], tray: &Tray) {
+    for p in pieces.iter_mut() {
+        p.rotation = Some(0.0);
+        p.flip = Some(false);
+    }
+    let mut sized = sized_pieces(pieces);
+    let height = |b: &(f64, f64, f64, f64)| b.3 - b.1;
+    sized.sort_by(|a, b| height(&b.1).total_cmp(&height(&a.1)).then(a.0.cmp(&b.0)));
+    shelf_pack(pieces, tray, &sized);
+}
+
+/// Scatter the pieces over the tray in a random order with random rotations
+/// (multiples of `SCRAMBLE_STEP_DEG`) and flips. The same seed always gives
+/// the same layout, on every platform.
+pub fn scramble_into_tray(pieces: &mut [Piece], tray: &Tray, seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for p in pieces.iter_mut() {
+        let steps = (360.0 / SCRAMBLE_STEP_DEG) as u64;
+        p.rotation = Some((rng.next() % steps) as f64 * SCRAMBLE_STEP_DEG);
+        // A flipped circle looks the same; keep it unflipped
+        p.flip = Some(p.shape.circle_radius().is_none() && rng.next() % 2 == 1);
+    }
+    let mut sized = sized_pieces(pieces);
+    // Fisher-Yates shuffle of the packing order
+    for i in (1..sized.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        sized.swap(i, j);
+    }
+    shelf_pack(pieces, tray, &sized);
+}
+
+// Indices and current bounds of the pieces that have an outline. Pieces
+// without an anchor get one so that re-posing them is predictable.
+fn sized_pieces(pieces: &mut [Piece]) -> Vec<(usize, Bounds)> {
+    let mut sized = Vec::new();
+    for (i, p) in pieces.iter_mut().enumerate() {
+        // Circles and regular polygons are easier to handle by their center
+        if p.anchor.is_none() {
+            p.anchor = Some(
+                match p.shape {
+                    Shape::Circle { .. } | Shape::RegularPolygon { .. } => "center",
+                    _ => "bottomleft",
+                }
+                .to_string(),
+            );
+        }
+        let (geom, _ctr) = piece_geom(p);
+        if !geom.is_empty() {
+            sized.push((i, bounds_of_points(&geom)));
+        }
+    }
+    sized
+}
+
+// Place the pieces in `order` in rows across the tray, top row first.
+fn shelf_pack(pieces: &mut [Piece], tray: &Tray, order: &[(usize, Bounds)]) {
+    let (mut x, mut top, mut row_h) = (tray.x, tray.top, 0.0f64);
+    for &(i, b) in order {
+        let (w, h) = (b.2 - b.0, b.3 - b.1);
+        if x > tray.x && x + w > tray.x + tray.width {
+            x = tray.x;
+            top -= row_h + TRAY_SPACING_MM;
+            row_h = 0.0;
+        }
+        translate_piece(&mut pieces[i], x - b.0, top - h - b.1);
+        x += w + TRAY_SPACING_MM;
+        row_h = row_h.max(h);
+    }
+}
+
+/// Seed from user text (e.g. a URL parameter): numbers are used as they
+/// are, anything else is hashed, so `seed=monday` works too.
+pub fn parse_seed(text: &str) -> u64 {
+    text.trim().parse().unwrap_or_else(|_| {
+        // FNV-1a
+        text.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    })
+}
+
+// Small deterministic generator; no platform RNG so a shared seed gives
+// every child the same scramble.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+pub fn build_puzzle_from_counts(spec: &CountsSpec, catalog: &ShapesCatalog) -> Puzzle {
+    use std::collections::HashMap;
+    let mut by_id: HashMap<&str, &ShapeDef> = HashMap::new();
+    for s in &catalog.shapes {
+        by_id.insert(s.id.as_str(), s);
+    }
+    // Pieces follow the counts order (or the explicit `order`), so labels and
+    // colours are the same on every load.
+    let mut pieces: Vec<Piece> = Vec::new();
+    for (id, ct) in spec.counts.ordered(spec.order.as_deref()) {
+        if let Some(sd) = by_id.get(id) {
+            for _ in 0..ct {
+                let mut p = Piece::new(sd.shape.clone());
+                p.id = Some(sd.id.clone());
+                pieces.push(p);
+            }
+        }
+    }
+
+    let tray = tray_for(spec.board.as_ref(), &pieces);
+    pack_into_tray(&mut pieces, &tray);
+
+    Puzzle {
+        units: spec.units.clone().or(Some("mm".to_string())),
+        board: spec.board.clone(),
+        pieces,
+        goal: spec.goal,
+        note_en: spec.note_en.clone(),
+        note_zh: spec.note_zh.clone(),
+        solutions: spec.solutions.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collide::{piece_proxy, proxies_penetration};
+
+    // The k12 puzzle, scrambled with `seed`.
+    fn scrambled(seed: u64) -> (Vec<Piece>, Tray) {
+        let catalog = serde_json::from_str(include_str!("../../shapes.json")).unwrap();
+        let spec = serde_json::from_str(include_str!("../../puzzle/k12.json")).unwrap();
+        let mut pieces = build_puzzle_from_counts(&spec, &catalog).pieces;
+        let tray = tray_for(spec.board.as_ref(), &pieces);
+        scramble_into_tray(&mut pieces, &tray, seed);
+        (pieces, tray)
+    }
+
+    fn poses(pieces: &[Piece]) -> Vec<serde_json::Value> {
+        pieces
+            .iter()
+            .map(|p| serde_json::to_value(p).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn tray_contains_checks_every_side() {
+        let tray = Tray {
+            x: 100.0,
+            top: 50.0,
+            width: 80.0,
+            depth: 120.0,
+        };
+        assert!(tray.contains((100.0, -60.0, 180.0, 50.0)));
+        assert!(!tray.contains((80.0, 0.0, 110.0, 30.0)));
+        assert!(!tray.contains((160.0, 0.0, 200.0, 30.0)));
+        assert!(!tray.contains((120.0, 40.0, 150.0, 70.0)));
+        assert!(!tray.contains((120.0, -90.0, 150.0, -60.0)));
+    }
+
+    #[test]
+    fn scramble_depends_only_on_the_seed() {
+        assert_eq!(poses(&scrambled(7).0), poses(&scrambled(7).0));
+        assert_ne!(poses(&scrambled(7).0), poses(&scrambled(8).0));
+    }
+
+    #[test]
+    fn scrambled_pieces_lie_apart_in_the_tray() {
+        for seed in [0, 1, 42, parse_seed("monday")] {
+            let (pieces, tray) = scrambled(seed);
+            let proxies: Vec<_> = pieces.iter().map(piece_proxy).collect();
+            for (i, a) in proxies.iter().enumerate() {
+                assert!(
+                    tray.contains(a.bounds),
+                    "seed {seed}: piece {i} outside the tray"
+                );
+                for (j, b) in proxies.iter().enumerate().skip(i + 1) {
+                    assert!(
+                        proxies_penetration(a, b).is_none(),
+                        "seed {seed}: pieces {i} and {j} overlap"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_parse_from_numbers_or_text() {
+        assert_eq!(parse_seed(" 1234 "), 1234);
+        assert_eq!(parse_seed("monday"), parse_seed("monday"));
+        assert_ne!(parse_seed("monday"), parse_seed("tuesday"));
+    }
+}
@@ -0,0 +1,19 @@
+//! Platform-independent puzzle core: piece/board models, geometry, the
+//! overlap and containment rules, the validation report, and the solver.
+//! Builds for native targets and wasm alike, and is shared by `puzzle-wasm`
+//! and `blueprint-core`.
+
+pub mod catalog;
+pub mod collide;
+pub mod constants;
+pub mod geom;
+pub mod layout;
+pub mod models;
+pub mod solution;
+pub mod solver;
+pub mod validate;
+
+pub use models::{
+    Board, Counts, CountsSpec, Goal, Piece, Point, PolygonPoint, Pose, Puzzle, Shape, ShapeDef,
+    ShapesCatalog, Solution, SolutionPiece, SolutionsFile,
+};
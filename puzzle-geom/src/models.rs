@@ -0,0 +1,466 @@
+use std::fmt;
+
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Basic two dimensional point used for geometry operations.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl From<(f64, f64)> for Point {
+    fn from(v: (f64, f64)) -> Self {
+        Point { x: v.0, y: v.1 }
+    }
+}
+
+/// Board outline vertex, optionally with a rounded corner.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PolygonPoint {
+    Point([f64; 2]),
+    /// Corner with rounding radius: [x, y, r]
+    Rounded([f64; 3]),
+}
+
+/// Board configuration describing available polygons and size.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Board {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub w: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polygons: Option<Vec<Vec<PolygonPoint>>>,
+    /// Areas inside the board that pieces may not cover, such as a hole in
+    /// the frame or a fixed block. Same point format as `polygons`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obstacles: Option<Vec<Vec<PolygonPoint>>>,
+}
+
+/// Piece outline parameters, tagged by `type` in JSON (e.g.
+/// `{"type": "rect", "w": 30, "h": 60}`). Every parameter is required, so an
+/// unknown type, a missing/misspelled field or an extra one is a
+/// deserialization error. Circles may give their radius `r` instead of the
+/// diameter `d`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Shape {
+    Rect {
+        w: f64,
+        h: f64,
+    },
+    EquilateralTriangle {
+        side: f64,
+    },
+    /// Legs `a` (along x) and `b` (along y) meeting at the right angle.
+    RightTriangle {
+        a: f64,
+        b: f64,
+    },
+    RegularPolygon {
+        n: u32,
+        side: f64,
+    },
+    Circle {
+        #[serde(flatten, with = "circle_diameter")]
+        d: f64,
+    },
+    IsoscelesTrapezoid {
+        base_bottom: f64,
+        base_top: f64,
+        height: f64,
+    },
+    Parallelogram {
+        base: f64,
+        offset_top: f64,
+        height: f64,
+    },
+    /// Free-form outline, shifted by the piece's `at` when set.
+    Polygon {
+        points: Vec<[f64; 2]>,
+    },
+}
+
+impl Shape {
+    /// Radius for circles, `None` for polygonal shapes.
+    pub fn circle_radius(&self) -> Option<f64> {
+        match self {
+            Shape::Circle { d } => Some(d / 2.0),
+            _ => None,
+        }
+    }
+}
+
+/// A placed piece: its shape plus pose.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Piece {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub shape: Shape,
+    // common fields
+    pub at: Option<[f64; 2]>,
+    pub rotation: Option<f64>,
+    pub anchor: Option<String>,
+    pub flip: Option<bool>,
+    // cached runtime fields (not serialized)
+    #[serde(skip)]
+    pub __ctr: Option<Point>,
+    #[serde(skip)]
+    pub __geom: Option<Vec<Point>>, // for hit-testing
+    #[serde(skip)]
+    pub __geom_pl: Option<String>, // encoded polyline for debug/interop
+    #[serde(skip)]
+    pub __color_idx: Option<usize>, // stable color assignment
+    #[serde(skip)]
+    pub __label_idx: Option<usize>, // stable numeric label (0-based)
+    #[serde(skip)]
+    pub __pose: Option<Pose>, // pose `__geom`/`__ctr` were built for
+}
+
+/// The inputs of `piece_geom`. Cached geometry stays valid while the piece
+/// still has the pose it was built for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub shape: Shape,
+    pub at: Option<[f64; 2]>,
+    pub rotation: Option<f64>,
+    pub anchor: Option<String>,
+    pub flip: Option<bool>,
+}
+
+impl Piece {
+    /// Unposed piece of the given shape.
+    pub fn new(shape: Shape) -> Self {
+        Piece {
+            id: None,
+            shape,
+            at: None,
+            rotation: None,
+            anchor: None,
+            flip: None,
+            __ctr: None,
+            __geom: None,
+            __geom_pl: None,
+            __color_idx: None,
+            __label_idx: None,
+            __pose: None,
+        }
+    }
+
+    /// Snapshot of the pose fields.
+    pub fn pose(&self) -> Pose {
+        Pose {
+            shape: self.shape.clone(),
+            at: self.at,
+            rotation: self.rotation,
+            anchor: self.anchor.clone(),
+            flip: self.flip,
+        }
+    }
+
+    /// Whether the piece is still in pose `pose`.
+    pub fn has_pose(&self, pose: &Pose) -> bool {
+        self.at == pose.at
+            && self.rotation == pose.rotation
+            && self.flip == pose.flip
+            && self.anchor == pose.anchor
+            && self.shape == pose.shape
+    }
+
+    /// Cached outline and center, if they still match the current pose.
+    pub fn cached_geom(&self) -> Option<(&[Point], Point)> {
+        match (&self.__geom, self.__ctr, &self.__pose) {
+            (Some(g), Some(c), Some(pose)) if self.has_pose(pose) => Some((g, c)),
+            _ => None,
+        }
+    }
+}
+
+/// Full puzzle specification including board, pieces and optional notes.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub units: Option<String>,
+    pub board: Option<Board>,
+    #[serde(default)]
+    pub pieces: Vec<Piece>,
+    #[serde(default, skip_serializing_if = "Goal::is_contain")]
+    pub goal: Goal,
+    // Optional per-puzzle notes in two languages
+    pub note_en: Option<String>,
+    pub note_zh: Option<String>,
+    /// Known solutions, shown on request and used to recognise a solved board.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub solutions: Vec<Solution>,
+}
+
+/// What counts as solved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    /// Every piece inside the border without overlaps; gaps are fine.
+    #[default]
+    Contain,
+    /// As `Contain`, and the pieces must cover the whole board.
+    Fill,
+    /// The board is a target outline rather than a frame: the pieces must
+    /// reproduce its shape exactly, anywhere on the table.
+    Silhouette,
+}
+
+impl Goal {
+    pub fn is_contain(&self) -> bool {
+        *self == Goal::Contain
+    }
+}
+
+impl Puzzle {
+    /// The board when it is a frame the pieces must stay inside; `None` for
+    /// silhouettes, which have no ring to collide with.
+    pub fn frame(&self) -> Option<&Board> {
+        self.board
+            .as_ref()
+            .filter(|_| self.goal != Goal::Silhouette)
+    }
+}
+
+/// A known arrangement of a puzzle's pieces.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Solution {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub pieces: Vec<SolutionPiece>,
+}
+
+/// Pose of one piece in a solution. `id` is the catalog shape id; identical
+/// pieces are interchangeable, so the entry applies to any piece of that id.
+/// For a polygon piece `at` is the translation of its points.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolutionPiece {
+    pub id: String,
+    pub at: Option<[f64; 2]>,
+    pub rotation: Option<f64>,
+    pub anchor: Option<String>,
+    pub flip: Option<bool>,
+}
+
+/// Sidecar file `puzzle/<id>.solution.json` for puzzles that do not carry
+/// their solutions inline.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SolutionsFile {
+    pub solutions: Vec<Solution>,
+}
+
+/// Shape metadata used when building puzzles from counts specs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShapeDef {
+    pub id: String,
+    #[serde(flatten)]
+    pub shape: Shape,
+    // Optional human labels (bilingual)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_en: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_zh: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ShapesCatalog {
+    pub shapes: Vec<ShapeDef>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CountsSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board: Option<Board>,
+    pub counts: Counts,
+    /// Optional numbering: shape ids whose pieces come first, in this order.
+    /// Ids not listed follow in `counts` order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shapes_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Goal::is_contain")]
+    pub goal: Goal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_en: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_zh: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub solutions: Vec<Solution>,
+}
+
+/// Piece counts keyed by shape id, kept in the order of the JSON object so
+/// pieces are numbered the same way on every load.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Counts(pub Vec<(String, u32)>);
+
+impl Counts {
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.0.iter().map(|(id, n)| (id.as_str(), *n))
+    }
+
+    /// Shape ids in numbering order: those in `order` first, then the rest
+    /// in file order. Ids in `order` without a count are skipped.
+    pub fn ordered(&self, order: Option<&[String]>) -> Vec<(&str, u32)> {
+        let order = order.unwrap_or_default();
+        let mut out: Vec<(&str, u32)> = Vec::new();
+        for id in order {
+            if let Some(e) = self.iter().find(|(k, _)| k == id)
+                && !out.contains(&e)
+            {
+                out.push(e);
+            }
+        }
+        for (id, n) in self.iter() {
+            if !out.iter().any(|(k, _)| *k == id) {
+                out.push((id, n));
+            }
+        }
+        out
+    }
+}
+
+// Circle size as `d`, or as the radius `r` older files use; always written
+// back as `d`.
+mod circle_diameter {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    struct Size {
+        d: Option<f64>,
+        r: Option<f64>,
+    }
+
+    #[derive(Serialize)]
+    struct Diameter {
+        d: f64,
+    }
+
+    pub fn serialize<S: Serializer>(d: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        Diameter { d: *d }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        let size = Size::deserialize(deserializer)?;
+        size.d
+            .or(size.r.map(|r| r * 2.0))
+            .ok_or_else(|| D::Error::missing_field("d"))
+    }
+}
+
+impl Serialize for Counts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Counts {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CountsVisitor;
+
+        impl<'de> Visitor<'de> for CountsVisitor {
+            type Value = Counts;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of shape id to count")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Counts, A::Error> {
+                let mut out: Vec<(String, u32)> = Vec::new();
+                while let Some((id, n)) = map.next_entry::<String, u32>()? {
+                    // A repeated key overrides the earlier count in place
+                    match out.iter_mut().find(|(k, _)| *k == id) {
+                        Some(e) => e.1 = n,
+                        None => out.push((id, n)),
+                    }
+                }
+                Ok(Counts(out))
+            }
+        }
+
+        deserializer.deserialize_map(CountsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(json: &str) -> Result<Shape, serde_json::Error> {
+        serde_json::from_str::<Piece>(json).map(|p| p.shape)
+    }
+
+    #[test]
+    fn shape_fields_are_checked() {
+        assert_eq!(
+            shape(r#"{"type": "rect", "w": 30, "h": 60, "id": "r", "at": [1, 2]}"#).unwrap(),
+            Shape::Rect { w: 30.0, h: 60.0 }
+        );
+        assert!(shape(r#"{"type": "rect", "w": 30, "h": 60, "d": 5}"#).is_err());
+        assert!(shape(r#"{"type": "rect", "w": 30}"#).is_err());
+        assert!(shape(r#"{"type": "circle", "r": 15, "side": 5}"#).is_err());
+    }
+
+    #[test]
+    fn circle_takes_diameter_or_radius() {
+        assert_eq!(
+            shape(r#"{"type": "circle", "r": 15}"#).unwrap(),
+            Shape::Circle { d: 30.0 }
+        );
+        assert_eq!(
+            shape(r#"{"type": "circle", "d": 30}"#).unwrap(),
+            Shape::Circle { d: 30.0 }
+        );
+    }
+
+    #[test]
+    fn catalog_entries_keep_their_labels() {
+        let def: ShapeDef = serde_json::from_str(
+            r#"{"id": "s", "type": "rect", "w": 30, "h": 30, "label_en": "Square"}"#,
+        )
+        .unwrap();
+        assert_eq!(def.label_en.as_deref(), Some("Square"));
+        assert!(
+            serde_json::from_str::<ShapeDef>(
+                r#"{"id": "s", "type": "rect", "w": 30, "h": 30, "lable": "x"}"#
+            )
+            .is_err()
+        );
+    }
+
+    fn spec(json: &str) -> CountsSpec {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn order_numbers_listed_ids_first() {
+        let spec = spec(r#"{"counts": {"a": 1, "b": 2, "c": 3}, "order": ["c", "a"]}"#);
+        assert_eq!(
+            spec.counts.ordered(spec.order.as_deref()),
+            vec![("c", 3), ("a", 1), ("b", 2)]
+        );
+        assert_eq!(
+            spec.counts.ordered(None),
+            vec![("a", 1), ("b", 2), ("c", 3)]
+        );
+    }
+
+    #[test]
+    fn order_skips_ids_without_a_count() {
+        let spec = spec(r#"{"counts": {"a": 1, "b": 2}, "order": ["x", "b", "b"]}"#);
+        assert_eq!(
+            spec.counts.ordered(spec.order.as_deref()),
+            vec![("b", 2), ("a", 1)]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+// Known solutions: posing a puzzle's pieces as a stored solution,
+// recognising when the current arrangement is one of them, and picking the
+// next piece to move towards one. Identical pieces (same catalog id) are
+// interchangeable throughout.
+
+use std::borrow::Cow;
+
+use crate::constants::SOLUTION_TOL_MM;
+use crate::geom::piece_geom;
+use crate::models::{Piece, Point as Pt, Solution, SolutionPiece};
+
+/// The pieces posed as in `sol`, keeping their labels and colours. Each
+/// entry takes the next unused piece with its id; `None` when the solution
+/// does not account for exactly these pieces.
+pub fn apply_solution(pieces: &[Piece], sol: &Solution) -> Option<Vec<Piece>> {
+    if sol.pieces.len() != pieces.len() {
+        return None;
+    }
+    let mut out = pieces.to_vec();
+    let mut used = vec![false; pieces.len()];
+    for sp in &sol.pieces {
+        let i = (0..pieces.len()).find(|&i| !used[i] && pieces[i].id.as_deref() == Some(&sp.id))?;
+        used[i] = true;
+        let p = &mut out[i];
+        p.at = sp.at;
+        p.rotation = sp.rotation;
+        p.anchor = sp.anchor.clone();
+        p.flip = sp.flip;
+    }
+    Some(out)
+}
+
+/// Solution holding the poses of `pieces`, e.g. a solver result. `None`
+/// when a piece has no catalog id to key it by.
+pub fn solution_from_pieces(pieces: &[Piece]) -> Option<Solution> {
+    let pieces = pieces
+        .iter()
+        .map(|p| {
+            Some(SolutionPiece {
+                id: p.id.clone()?,
+                at: p.at,
+                rotation: p.rotation,
+                anchor: p.anchor.clone(),
+                flip: p.flip,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Solution { name: None, pieces })
+}
+
+/// Index of the first solution the current arrangement matches.
+pub fn matching_solution(pieces: &[Piece], solutions: &[Solution]) -> Option<usize> {
+    solutions.iter().position(|s| matches_solution(pieces, s))
+}
+
+/// Whether every piece lies where some piece of the same id lies in `sol`.
+/// Poses are compared by outline, so e.g. a flipped symmetric piece or a
+/// rotation by a full turn still matches.
+pub fn matches_solution(pieces: &[Piece], sol: &Solution) -> bool {
+    apply_solution(pieces, sol).is_some_and(|target| claim_slots(pieces, &target).1.is_empty())
+}
+
+/// A step towards a solution: piece `piece` (an index into the pieces)
+/// belongs at `target`, which is that piece in its solution pose.
+#[derive(Clone, Debug)]
+pub struct Hint {
+    pub piece: usize,
+    pub target: Piece,
+}
+
+/// Next piece to move, following the solution with the most pieces already
+/// in place. Open places are taken in solution order, each by the closest
+/// misplaced piece of its id. `None` when the layout is solved or no
+/// solution fits the pieces.
+pub fn next_hint(pieces: &[Piece], solutions: &[Solution]) -> Option<Hint> {
+    let (target, claimed, misplaced) = solutions
+        .iter()
+        .filter_map(|s| {
+            let target = apply_solution(pieces, s)?;
+            let (claimed, misplaced) = claim_slots(pieces, &target);
+            Some((target, claimed, misplaced))
+        })
+        .min_by_key(|(_, _, misplaced)| misplaced.len())?;
+    let slot = (0..target.len()).find(|&j| !claimed[j])?;
+    let goal = piece_geom(&target[slot]).1;
+    let dist = |i: usize| {
+        let c = piece_geom(&pieces[i]).1;
+        (c.x - goal.x).hypot(c.y - goal.y)
+    };
+    let piece = misplaced
+        .into_iter()
+        .filter(|&i| pieces[i].id == target[slot].id)
+        .min_by(|&a, &b| dist(a).total_cmp(&dist(b)))?;
+    let mut to = pieces[piece].clone();
+    let pose = &target[slot];
+    to.at = pose.at;
+    to.rotation = pose.rotation;
+    to.anchor = pose.anchor.clone();
+    to.flip = pose.flip;
+    Some(Hint { piece, target: to })
+}
+
+// Pieces lying in a place of `target` with their id claim it. Returns which
+// places are claimed and the indices of the pieces left over.
+fn claim_slots(pieces: &[Piece], target: &[Piece]) -> (Vec<bool>, Vec<usize>) {
+    let mut claimed = vec![false; target.len()];
+    let mut misplaced = Vec::new();
+    for (i, p) in pieces.iter().enumerate() {
+        let found = (0..target.len())
+            .find(|&j| !claimed[j] && target[j].id == p.id && in_place(p, &target[j]));
+        match found {
+            Some(j) => claimed[j] = true,
+            None => misplaced.push(i),
+        }
+    }
+    (claimed, misplaced)
+}
+
+/// Whether `a` covers the same outline as `b`, within tolerance. Circles
+/// only compare centers, since their outline is the same at any rotation.
+pub fn in_place(a: &Piece, b: &Piece) -> bool {
+    let (ga, ca) = outline(a);
+    let (gb, cb) = outline(b);
+    if a.shape.circle_radius().is_some() {
+        return near(ca, cb);
+    }
+    ga.len() == gb.len()
+        && ga.iter().all(|&p| gb.iter().any(|&q| near(p, q)))
+        && gb.iter().all(|&q| ga.iter().any(|&p| near(p, q)))
+}
+
+// Outline and center of `p`, from its cache while that is still valid.
+fn outline(p: &Piece) -> (Cow<'_, [Pt]>, Pt) {
+    match p.cached_geom() {
+        Some((g, c)) => (Cow::Borrowed(g), c),
+        None => {
+            let (g, c) = piece_geom(p);
+            (Cow::Owned(g), c)
+        }
+    }
+}
+
+fn near(a: Pt, b: Pt) -> bool {
+    (a.x - b.x).hypot(a.y - b.y) <= SOLUTION_TOL_MM
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::translate_piece;
+    use crate::models::Shape;
+
+    fn piece(id: &str, shape: Shape) -> Piece {
+        let mut p = Piece::new(shape);
+        p.id = Some(id.into());
+        p
+    }
+
+    fn pieces() -> Vec<Piece> {
+        let ell = Shape::Polygon {
+            points: vec![
+                [0.0, 0.0],
+                [20.0, 0.0],
+                [20.0, 10.0],
+                [10.0, 10.0],
+                [10.0, 20.0],
+                [0.0, 20.0],
+            ],
+        };
+        vec![
+            piece("ell", ell),
+            piece("sq", Shape::Rect { w: 10.0, h: 10.0 }),
+            piece("disc", Shape::Circle { d: 10.0 }),
+        ]
+    }
+
+    #[test]
+    fn solution_round_trip_places_polygon_pieces() {
+        let mut posed = pieces();
+        translate_piece(&mut posed[0], 40.0, 15.0);
+        posed[0].rotation = Some(90.0);
+        posed[0].flip = Some(true);
+        translate_piece(&mut posed[1], 5.0, 5.0);
+        translate_piece(&mut posed[2], 70.0, 0.0);
+        let sol = solution_from_pieces(&posed).unwrap();
+        let json = serde_json::to_string(&sol).unwrap();
+        let sol: Solution = serde_json::from_str(&json).unwrap();
+
+        let fresh = pieces();
+        assert!(!matches_solution(&fresh, &sol));
+        let applied = apply_solution(&fresh, &sol).unwrap();
+        assert!(matches_solution(&applied, &sol));
+        for (a, b) in applied.iter().zip(&posed) {
+            assert!(in_place(a, b));
+        }
+    }
+
+    #[test]
+    fn hint_moves_a_polygon_piece_into_place() {
+        let mut posed = pieces();
+        translate_piece(&mut posed[0], 40.0, 15.0);
+        let sol = solution_from_pieces(&posed).unwrap();
+        let mut layout = posed.clone();
+        translate_piece(&mut layout[0], -100.0, 0.0);
+        let hint = next_hint(&layout, &[sol]).unwrap();
+        assert_eq!(hint.piece, 0);
+        assert!(in_place(&hint.target, &posed[0]));
+    }
+
+    #[test]
+    fn identical_pieces_are_interchangeable() {
+        let sq = || piece("sq", Shape::Rect { w: 10.0, h: 10.0 });
+        let mut posed = vec![sq(), sq()];
+        translate_piece(&mut posed[0], 0.0, 0.0);
+        translate_piece(&mut posed[1], 20.0, 0.0);
+        let sol = solution_from_pieces(&posed).unwrap();
+        posed.swap(0, 1);
+        assert!(matches_solution(&posed, &sol));
+        assert!(next_hint(&posed, &[sol]).is_none());
+    }
+}
@@ -0,0 +1,1004 @@
+// Deterministic placement search for counts-format puzzles.
+//
+// Pieces are placed largest first. Candidate poses either put a piece vertex
+// on a board or placed-piece corner with one of its edges aligned to an edge
+// at that corner, or lay a piece edge flush against the border or a placed
+// piece and slide it along until it comes to rest against something (a
+// circle rests against two things at once). Candidates that share the most
+// edge length with the border and placed pieces are tried first (ties in
+// bottom-left order), and every accepted pose passes the same overlap and
+// containment rules as the validation panel. Board obstacles count as pieces
+// placed before the search starts. The search uses no randomness, so the same
+// input always yields the same solution, and it can run a slice at a time
+// (see `Solver::run`) so that a UI stays responsive.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::collide::{
+    PieceProxy, bounds_overlap, circle_depth, convex_parts, obstacle_proxies, proxies_overlap,
+    proxy_outside_border,
+};
+use crate::constants::{RING_WIDTH_MM, VALIDATION_EPS_MM};
+use crate::geom::{
+    board_outer_geom, board_to_geom, bounds_of_points, convex_overlap, dist_to_outline, piece_geom,
+    poly_contains_point, translate_piece,
+};
+use crate::layout::build_puzzle_from_counts;
+use crate::models::{CountsSpec, Piece, Point as Pt, Puzzle, ShapesCatalog};
+
+/// Upper bound on full placement checks over a whole search.
+const MAX_CHECKS: usize = 200_000;
+/// Valid placements explored per piece before backtracking further up; one
+/// search pass per entry, narrow first. The last pass is exhaustive.
+const BRANCHES: [usize; 3] = [4, 8, usize::MAX];
+/// Edges closer than this count as flush when scoring contact (mm).
+const FLUSH_TOL_MM: f64 = 0.2;
+/// Edges shorter than this (e.g. arc facets) are not used for alignment (mm).
+const MIN_ALIGN_EDGE_MM: f64 = 3.0;
+/// Overlap allowed where a sliding piece comes to rest against another (mm).
+const SLIDE_EPS_MM: f64 = 1e-6;
+
+/// Why a search ended without a solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// The puzzle has no usable board.
+    NoBoard,
+    /// Every candidate placement was tried.
+    NoSolution,
+    /// The search gave up after `MAX_CHECKS` placement checks; a solution
+    /// may still exist.
+    LimitReached,
+}
+
+/// Build the pieces listed in `spec` and search for a valid placement.
+pub fn solve(spec: &CountsSpec, catalog: &ShapesCatalog) -> Result<Puzzle, SolveError> {
+    solve_puzzle(&build_puzzle_from_counts(spec, catalog))
+}
+
+/// Search for poses of all pieces in `puzzle` so that none overlap and all
+/// lie inside the board, in one go.
+pub fn solve_puzzle(puzzle: &Puzzle) -> Result<Puzzle, SolveError> {
+    let mut solver = Solver::new(puzzle)?;
+    loop {
+        if let Some(done) = solver.run(usize::MAX) {
+            return done;
+        }
+    }
+}
+
+/// A placement search that runs a slice at a time.
+pub struct Solver {
+    puzzle: Puzzle,
+    // Pieces without a pose (candidates start from these), and the poses
+    // chosen so far.
+    templates: Vec<Piece>,
+    pieces: Vec<Piece>,
+    order: Vec<usize>,
+    inner: Vec<Vec<Pt>>,
+    outer: Option<Vec<Vec<Pt>>>,
+    bounds: (f64, f64, f64, f64),
+    board_anchors: Vec<Anchor>,
+    board_walls: Vec<Wall>,
+    // Turned and flipped outlines of each piece, built as needed.
+    poses: Vec<Poses>,
+    // The obstacles, then one entry per piece placed so far.
+    placed: Vec<Placed>,
+    // Untried candidates of each piece placed so far and of the next one.
+    frames: Vec<Frame>,
+    pass: usize,
+    checks: usize,
+    max_checks: usize,
+}
+
+struct Frame {
+    // Placement key of the candidate placed last, if any.
+    key: Vec<(i64, i64)>,
+    // Best last, so the next one is popped.
+    candidates: Vec<Candidate>,
+    accepted: usize,
+}
+
+impl Solver {
+    /// Set up a search for `puzzle`; fails when it has no usable board.
+    pub fn new(puzzle: &Puzzle) -> Result<Self, SolveError> {
+        let board = puzzle.board.as_ref().ok_or(SolveError::NoBoard)?;
+        let inner = board_to_geom(board).ok_or(SolveError::NoBoard)?;
+        let outer = board_outer_geom(board, RING_WIDTH_MM);
+
+        let templates: Vec<Piece> = puzzle.pieces.iter().map(template).collect();
+        let areas: Vec<f64> = templates
+            .iter()
+            .map(|p| signed_area(&piece_geom(p).0).abs())
+            .collect();
+        let mut order: Vec<usize> = (0..templates.len()).collect();
+        order.sort_by(|&a, &b| {
+            areas[b]
+                .total_cmp(&areas[a])
+                .then_with(|| templates[a].id.cmp(&templates[b].id))
+                .then(a.cmp(&b))
+        });
+
+        Ok(Solver {
+            puzzle: puzzle.clone(),
+            pieces: templates.clone(),
+            poses: templates.iter().map(|_| Poses::default()).collect(),
+            templates,
+            order,
+            board_anchors: inner.iter().flat_map(|g| corner_anchors(g)).collect(),
+            board_walls: inner.iter().flat_map(|g| walls(g, true)).collect(),
+            bounds: bounds_of_points(&inner.concat()),
+            inner,
+            outer,
+            placed: obstacle_proxies(board)
+                .into_iter()
+                .map(Placed::new)
+                .collect(),
+            frames: Vec::new(),
+            pass: 0,
+            checks: 0,
+            max_checks: MAX_CHECKS,
+        })
+    }
+
+    // A search that gives up after `max_checks` placement checks.
+    #[cfg(test)]
+    fn with_max_checks(puzzle: &Puzzle, max_checks: usize) -> Result<Self, SolveError> {
+        Ok(Solver {
+            max_checks,
+            ..Solver::new(puzzle)?
+        })
+    }
+
+    /// Placement checks made so far.
+    pub fn checks(&self) -> usize {
+        self.checks
+    }
+
+    /// Continue the search for about `budget` more placement checks. Returns
+    /// `None` when the slice ends with the search still going, and the
+    /// outcome once it is over.
+    pub fn run(&mut self, budget: usize) -> Option<Result<Puzzle, SolveError>> {
+        let stop = self.checks.saturating_add(budget);
+        loop {
+            if self.frames.is_empty() {
+                let Some(&first) = self.order.first() else {
+                    return Some(Ok(self.puzzle.clone()));
+                };
+                if self.pass == BRANCHES.len() {
+                    return Some(Err(SolveError::NoSolution));
+                }
+                let frame = self.frame(first);
+                self.frames.push(frame);
+            }
+            if self.checks >= self.max_checks {
+                return Some(Err(SolveError::LimitReached));
+            }
+            if self.checks >= stop {
+                return None;
+            }
+            let depth = self.frames.len() - 1;
+            let frame = &mut self.frames[depth];
+            let next = if frame.accepted < BRANCHES[self.pass] {
+                frame.candidates.pop()
+            } else {
+                None
+            };
+            let Some(c) = next else {
+                // Out of options: take back the previous piece
+                self.frames.pop();
+                match self.frames.last_mut() {
+                    Some(prev) => {
+                        prev.accepted += 1;
+                        self.placed.pop();
+                    }
+                    None => self.pass += 1,
+                }
+                continue;
+            };
+            self.checks += 1;
+            let pose = &self.poses[self.order[depth]].list[c.pose];
+            let Some(proxy) = self.fits(pose, c.at) else {
+                continue;
+            };
+            let mut piece = pose.piece.clone();
+            translate_piece(&mut piece, c.at.x, c.at.y);
+            self.frames[depth].key = c.key;
+            self.pieces[self.order[depth]] = piece;
+            self.placed.push(Placed::new(proxy));
+            let Some(&idx) = self.order.get(depth + 1) else {
+                return Some(Ok(Puzzle {
+                    pieces: self.pieces.clone(),
+                    ..self.puzzle.clone()
+                }));
+            };
+            let frame = self.frame(idx);
+            self.frames.push(frame);
+        }
+    }
+
+    // Candidate poses for piece `idx`, deduplicated, screened with cheap
+    // tests and sorted best last.
+    fn frame(&mut self, idx: usize) -> Frame {
+        let mut poses = std::mem::take(&mut self.poses[idx]);
+        let frame = self.frame_with(idx, &mut poses);
+        self.poses[idx] = poses;
+        frame
+    }
+
+    fn frame_with(&self, idx: usize, poses: &mut Poses) -> Frame {
+        let t = &self.templates[idx];
+        // (pose, translation)
+        let mut spots: Vec<(usize, Pt)> = Vec::new();
+
+        if let Some(r) = t.shape.circle_radius() {
+            // Resting against two things: walls, corners or placed circles
+            let pose = poses.get(t, false, 0.0);
+            let ctr = poses.list[pose].ctr;
+            let rests = self.rests(r);
+            for (i, a) in rests.iter().enumerate() {
+                for b in &rests[i + 1..] {
+                    for c in meet(a, b) {
+                        spots.push((pose, sub(c, ctr)));
+                    }
+                }
+            }
+        } else {
+            let anchors: Vec<&Anchor> = self
+                .board_anchors
+                .iter()
+                .chain(self.placed.iter().flat_map(|pl| pl.anchors.iter()))
+                .collect();
+            let edges: Vec<&Wall> = self
+                .board_walls
+                .iter()
+                .chain(self.placed.iter().flat_map(|pl| pl.walls.iter()))
+                .collect();
+            for flip in [false, true] {
+                let base = poses.get(t, flip, 0.0);
+                let own = corner_anchors(&poses.list[base].geom);
+                let own_walls = walls(&poses.list[base].geom, false);
+                // A piece corner on a corner, one edge along one of its edges
+                for a in &anchors {
+                    for alpha in &a.dirs {
+                        for v in &own {
+                            for beta in &v.dirs {
+                                for turn in [0.0, 180.0] {
+                                    let pose = poses.get(t, flip, alpha - beta + turn);
+                                    let vk = poses.list[pose].geom[v.index];
+                                    spots.push((pose, sub(a.at, vk)));
+                                }
+                            }
+                        }
+                    }
+                }
+                // A piece edge flush on a wall, slid along it
+                let mut lines = HashSet::new();
+                for w in &edges {
+                    let want = (-w.normal.y).atan2(-w.normal.x).to_degrees();
+                    for m in &own_walls {
+                        let turn = want - m.normal.y.atan2(m.normal.x).to_degrees();
+                        let pose = poses.get(t, flip, turn);
+                        let start = sub(w.at, poses.list[pose].geom[m.index]);
+                        // Collinear walls give the same slide
+                        let min = add(poses.list[pose].min, start);
+                        let line = (
+                            poses.list[pose].outline,
+                            round_mm(w.dir.x),
+                            round_mm(w.dir.y),
+                            round_mm(min.y * w.dir.x - min.x * w.dir.y),
+                        );
+                        if !lines.insert(line) {
+                            continue;
+                        }
+                        for s in self.slide_stops(&poses.list[pose], start, w.dir) {
+                            let at = Pt {
+                                x: start.x + w.dir.x * s,
+                                y: start.y + w.dir.y * s,
+                            };
+                            spots.push((pose, at));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Turns of a symmetric piece can give the same outline, so poses are
+        // told apart by outline and position.
+        // Identical pieces are placed in increasing key order, so that
+        // swapping them is not searched again.
+        let depth = self.frames.len();
+        let after = depth
+            .checked_sub(1)
+            .filter(|&d| {
+                let prev = &self.templates[self.order[d]];
+                prev.shape == t.shape && prev.anchor == t.anchor
+            })
+            .map(|d| &self.frames[d].key);
+        let mut seen: HashSet<(usize, i64, i64)> = HashSet::new();
+        // (contact, bottom, left, candidate)
+        let mut scored: Vec<(f64, f64, f64, Candidate)> = Vec::new();
+        let (minx, miny, maxx, maxy) = self.bounds;
+        let e = VALIDATION_EPS_MM;
+        for (i, at) in spots {
+            let pose = &poses.list[i];
+            let (min, max) = (add(pose.min, at), add(pose.max, at));
+            if min.x < minx - e || min.y < miny - e || max.x > maxx + e || max.y > maxy + e {
+                continue;
+            }
+            let key = (pose.outline, round_mm(min.x), round_mm(min.y));
+            if !seen.insert(key) {
+                continue;
+            }
+            let geom: Vec<Pt> = pose.geom.iter().map(|&q| add(q, at)).collect();
+            let bounds = (min.x, min.y, max.x, max.y);
+            if !self.may_fit(pose, &geom, bounds, at) {
+                continue;
+            }
+            let key = outline_key(&geom);
+            if after.is_some_and(|prev| key <= *prev) {
+                continue;
+            }
+            let contact = self.contact(&geom);
+            scored.push((contact, bounds.1, bounds.0, Candidate { pose: i, at, key }));
+        }
+        scored.sort_by(|a, b| {
+            a.0.total_cmp(&b.0)
+                .then(b.1.total_cmp(&a.1))
+                .then(b.2.total_cmp(&a.2))
+        });
+        Frame {
+            key: Vec::new(),
+            candidates: scored.into_iter().map(|(.., c)| c).collect(),
+            accepted: 0,
+        }
+    }
+
+    // Curves the centre of a circle of radius `r` follows while it touches
+    // the border or a placed piece.
+    fn rests(&self, r: f64) -> Vec<Rest> {
+        let walls = self
+            .board_walls
+            .iter()
+            .chain(self.placed.iter().flat_map(|pl| pl.walls.iter()))
+            .map(|w| Rest::Line {
+                at: add(
+                    w.at,
+                    Pt {
+                        x: w.normal.x * r,
+                        y: w.normal.y * r,
+                    },
+                ),
+                dir: w.dir,
+                len: w.len,
+            });
+        let corners = self
+            .inner
+            .iter()
+            .flatten()
+            .chain(
+                self.placed
+                    .iter()
+                    .filter(|pl| pl.proxy.radius.is_none())
+                    .flat_map(|pl| pl.proxy.geom.iter()),
+            )
+            .map(|&ctr| Rest::Round { ctr, r });
+        let circles = self.placed.iter().filter_map(|pl| {
+            pl.proxy.radius.map(|pr| Rest::Round {
+                ctr: pl.proxy.ctr,
+                r: pr + r,
+            })
+        });
+        walls.chain(corners).chain(circles).collect()
+    }
+
+    // Offsets along `dir` where `pose` moved by `start` comes to rest
+    // against the border or a placed piece when slid along `dir`.
+    fn slide_stops(&self, pose: &Pose, start: Pt, dir: Pt) -> Vec<f64> {
+        // Placed pieces whose bounds miss the band the pose sweeps through
+        // cannot stop it
+        let across = |q: Pt| q.y * dir.x - q.x * dir.y;
+        let (lo, hi) = pose
+            .geom
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), &q| {
+                let d = across(add(q, start));
+                (l.min(d), h.max(d))
+            });
+        let in_band = |b: (f64, f64, f64, f64)| {
+            let ends =
+                [(b.0, b.1), (b.0, b.3), (b.2, b.1), (b.2, b.3)].map(|(x, y)| across(Pt { x, y }));
+            let (l, h) = ends
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), &d| {
+                    (l.min(d), h.max(d))
+                });
+            h > lo + SLIDE_EPS_MM && l < hi - SLIDE_EPS_MM
+        };
+        let placed: Vec<&Placed> = self.placed.iter().filter(|pl| in_band(pl.reach)).collect();
+        let mut blocked: Vec<(f64, f64)> = Vec::new();
+        for part in &pose.parts {
+            let part: Vec<Pt> = part.iter().map(|&q| add(q, start)).collect();
+            for g in &self.inner {
+                let n = g.len();
+                for j in 0..n {
+                    let seg = [g[j], g[(j + 1) % n]];
+                    blocked.extend(blocked_range(&part, dir, &seg));
+                }
+            }
+            for pl in &placed {
+                for q in &pl.parts {
+                    blocked.extend(blocked_range(&part, dir, q));
+                }
+            }
+        }
+        // Stops are the ends of the merged blocked ranges
+        blocked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut stops = Vec::new();
+        let mut runs = blocked.into_iter();
+        let Some((mut lo, mut hi)) = runs.next() else {
+            return stops;
+        };
+        for (l, h) in runs {
+            if l < hi - 1e-9 {
+                hi = hi.max(h);
+                continue;
+            }
+            stops.extend([lo, hi]);
+            (lo, hi) = (l, h);
+        }
+        stops.extend([lo, hi]);
+        stops.retain(|s| s.is_finite());
+        stops
+    }
+
+    // Necessary conditions for a pose within the board's bounds, much
+    // cheaper than `fits`: every vertex inside the board or within tolerance
+    // of its border, and no placed polygon overlapped beyond tolerance.
+    fn may_fit(&self, pose: &Pose, geom: &[Pt], bounds: (f64, f64, f64, f64), at: Pt) -> bool {
+        let e = VALIDATION_EPS_MM;
+        let near_board = |q: &Pt| {
+            self.inner
+                .iter()
+                .any(|g| poly_contains_point(g, *q) || dist_to_outline(g, *q) <= e)
+        };
+        if !geom.iter().all(near_board) {
+            return false;
+        }
+        if let Some(r) = pose.piece.shape.circle_radius() {
+            let c = add(pose.ctr, at);
+            return !self.placed.iter().any(|pl| {
+                pl.proxy.radius.is_none()
+                    && bounds_overlap(pl.proxy.bounds, bounds)
+                    && circle_depth(c, r, &pl.proxy.geom) > e
+            });
+        }
+        let parts: Vec<Vec<Pt>> = pose
+            .parts
+            .iter()
+            .map(|part| part.iter().map(|&q| add(q, at)).collect())
+            .collect();
+        !self.placed.iter().any(|pl| {
+            pl.proxy.radius.is_none()
+                && bounds_overlap(pl.proxy.bounds, bounds)
+                && parts
+                    .iter()
+                    .any(|a| pl.proxy.parts.iter().any(|b| convex_overlap(a, b, e)))
+        })
+    }
+
+    // Edge length a pose shares with the border and placed polygon pieces.
+    fn contact(&self, geom: &[Pt]) -> f64 {
+        let placed = self
+            .placed
+            .iter()
+            .filter(|pl| pl.proxy.radius.is_none())
+            .map(|pl| &pl.proxy.geom);
+        self.inner
+            .iter()
+            .chain(placed)
+            .map(|g| shared_edge_len(geom, g))
+            .sum()
+    }
+
+    // The validation rules for `pose` moved by `at`; its proxy when it
+    // passes them.
+    fn fits(&self, pose: &Pose, at: Pt) -> Option<PieceProxy> {
+        let geom = pose.geom.iter().map(|&q| add(q, at)).collect();
+        let radius = pose.piece.shape.circle_radius();
+        let proxy = PieceProxy::new(geom, add(pose.ctr, at), radius);
+        let clear = self.placed.iter().all(|pl| {
+            !bounds_overlap(pl.proxy.bounds, proxy.bounds) || !proxies_overlap(&pl.proxy, &proxy)
+        });
+        (clear && !proxy_outside_border(&proxy, &self.inner, self.outer.as_deref()))
+            .then_some(proxy)
+    }
+}
+
+// Strip the pose and cached geometry, keeping labels and colors.
+fn template(p: &Piece) -> Piece {
+    let mut t = p.clone();
+    t.rotation = Some(0.0);
+    t.flip = Some(false);
+    t.at = Some([0.0, 0.0]);
+    t.__geom = None;
+    t.__geom_pl = None;
+    t.__ctr = None;
+    t.__pose = None;
+    t
+}
+
+// Sorted, rounded vertices: equal for the same outline in the same place.
+fn outline_key(geom: &[Pt]) -> Vec<(i64, i64)> {
+    let mut key: Vec<(i64, i64)> = geom
+        .iter()
+        .map(|q| (round_mm(q.x), round_mm(q.y)))
+        .collect();
+    key.sort_unstable();
+    key
+}
+
+fn add(a: Pt, b: Pt) -> Pt {
+    Pt {
+        x: a.x + b.x,
+        y: a.y + b.y,
+    }
+}
+
+fn sub(a: Pt, b: Pt) -> Pt {
+    Pt {
+        x: a.x - b.x,
+        y: a.y - b.y,
+    }
+}
+
+// Position key with micrometre resolution.
+fn round_mm(v: f64) -> i64 {
+    (v * 1e3).round() as i64
+}
+
+/// A corner usable for alignment: its index in the outline, its position and
+/// the directions (degrees) of the edges leaving it.
+struct Anchor {
+    index: usize,
+    at: Pt,
+    dirs: Vec<f64>,
+}
+
+fn edge_dir(a: Pt, b: Pt) -> Option<f64> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    if (dx * dx + dy * dy).sqrt() < MIN_ALIGN_EDGE_MM {
+        None
+    } else {
+        Some(dy.atan2(dx).to_degrees())
+    }
+}
+
+fn corner_anchors(poly: &[Pt]) -> Vec<Anchor> {
+    let n = poly.len();
+    let mut out = Vec::new();
+    if n < 3 {
+        return out;
+    }
+    for i in 0..n {
+        let v = poly[i];
+        let dirs: Vec<f64> = [poly[(i + 1) % n], poly[(i + n - 1) % n]]
+            .into_iter()
+            .filter_map(|w| edge_dir(v, w))
+            .collect();
+        if !dirs.is_empty() {
+            out.push(Anchor {
+                index: i,
+                at: v,
+                dirs,
+            });
+        }
+    }
+    out
+}
+
+/// A straight edge a piece can lie flush against: the index of its start in
+/// the outline, that start, its unit direction and length, and the unit
+/// normal pointing to the free side.
+struct Wall {
+    index: usize,
+    at: Pt,
+    dir: Pt,
+    len: f64,
+    normal: Pt,
+}
+
+// Walls along the edges of `poly`; the free side is the inside for a board
+// region and the outside for a piece or obstacle.
+fn walls(poly: &[Pt], inside: bool) -> Vec<Wall> {
+    let n = poly.len();
+    let ccw = signed_area(poly) > 0.0;
+    let mut out = Vec::new();
+    for i in 0..n {
+        let (a, b) = (poly[i], poly[(i + 1) % n]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len < MIN_ALIGN_EDGE_MM {
+            continue;
+        }
+        let dir = Pt {
+            x: dx / len,
+            y: dy / len,
+        };
+        // Left of the edge is inside a counter-clockwise outline
+        let left = Pt {
+            x: -dir.y,
+            y: dir.x,
+        };
+        let normal = if ccw == inside {
+            left
+        } else {
+            Pt {
+                x: -left.x,
+                y: -left.y,
+            }
+        };
+        out.push(Wall {
+            index: i,
+            at: a,
+            dir,
+            len,
+            normal,
+        });
+    }
+    out
+}
+
+fn signed_area(pts: &[Pt]) -> f64 {
+    let n = pts.len();
+    (0..n)
+        .map(|i| {
+            let (p, q) = (pts[i], pts[(i + 1) % n]);
+            p.x * q.y - q.x * p.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// A path a circle's centre follows while touching something: a segment
+/// (from `at`, `len` along unit `dir`) or a circle.
+enum Rest {
+    Line { at: Pt, dir: Pt, len: f64 },
+    Round { ctr: Pt, r: f64 },
+}
+
+// Where two rest paths cross.
+fn meet(a: &Rest, b: &Rest) -> Vec<Pt> {
+    const TOL: f64 = 1e-6;
+    let cross = |u: Pt, v: Pt| u.x * v.y - u.y * v.x;
+    let along = |at: Pt, dir: Pt, s: f64| Pt {
+        x: at.x + dir.x * s,
+        y: at.y + dir.y * s,
+    };
+    match (a, b) {
+        (
+            &Rest::Line { at, dir, len },
+            &Rest::Line {
+                at: at2,
+                dir: dir2,
+                len: len2,
+            },
+        ) => {
+            let d = cross(dir, dir2);
+            if d.abs() < 1e-9 {
+                return Vec::new();
+            }
+            let w = sub(at2, at);
+            let (s, t) = (cross(w, dir2) / d, cross(w, dir) / d);
+            let on = |s: f64, len: f64| (-TOL..=len + TOL).contains(&s);
+            if on(s, len) && on(t, len2) {
+                vec![along(at, dir, s)]
+            } else {
+                Vec::new()
+            }
+        }
+        (&Rest::Line { at, dir, len }, &Rest::Round { ctr, r })
+        | (&Rest::Round { ctr, r }, &Rest::Line { at, dir, len }) => {
+            let f = sub(at, ctr);
+            let b = f.x * dir.x + f.y * dir.y;
+            let disc = b * b - (f.x * f.x + f.y * f.y - r * r);
+            if disc < 0.0 {
+                return Vec::new();
+            }
+            let root = disc.sqrt();
+            [-b - root, -b + root]
+                .into_iter()
+                .filter(|s| (-TOL..=len + TOL).contains(s))
+                .map(|s| along(at, dir, s))
+                .collect()
+        }
+        (&Rest::Round { ctr, r }, &Rest::Round { ctr: ctr2, r: r2 }) => {
+            let w = sub(ctr2, ctr);
+            let d = w.x.hypot(w.y);
+            if d < 1e-9 || d > r + r2 || d < (r - r2).abs() {
+                return Vec::new();
+            }
+            let s = (d * d + r * r - r2 * r2) / (2.0 * d);
+            let h = (r * r - s * s).max(0.0).sqrt();
+            let (ux, uy) = (w.x / d, w.y / d);
+            let mid = along(ctr, Pt { x: ux, y: uy }, s);
+            vec![
+                Pt {
+                    x: mid.x - uy * h,
+                    y: mid.y + ux * h,
+                },
+                Pt {
+                    x: mid.x + uy * h,
+                    y: mid.y - ux * h,
+                },
+            ]
+        }
+    }
+}
+
+// A circle's polyline pushed out so that it contains the true circle.
+fn circumscribed(poly: &[Pt], ctr: Pt) -> Vec<Pt> {
+    let k = 1.0 / (std::f64::consts::PI / poly.len().max(3) as f64).cos();
+    poly.iter()
+        .map(|q| Pt {
+            x: ctr.x + (q.x - ctr.x) * k,
+            y: ctr.y + (q.y - ctr.y) * k,
+        })
+        .collect()
+}
+
+// Range of `s` for which convex `a` moved by `s * dir` overlaps convex `b`
+// by more than `SLIDE_EPS_MM`, if any (separating axes).
+fn blocked_range(a: &[Pt], dir: Pt, b: &[Pt]) -> Option<(f64, f64)> {
+    // Apart across the slide direction: they never meet
+    let across = |poly: &[Pt]| {
+        poly.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), v| {
+                let d = v.y * dir.x - v.x * dir.y;
+                (l.min(d), h.max(d))
+            })
+    };
+    let ((amin, amax), (bmin, bmax)) = (across(a), across(b));
+    if amax <= bmin + SLIDE_EPS_MM || bmax <= amin + SLIDE_EPS_MM {
+        return None;
+    }
+    let (mut lo, mut hi) = (f64::NEG_INFINITY, f64::INFINITY);
+    for poly in [a, b] {
+        let n = poly.len();
+        for i in 0..n {
+            let (p, q) = (poly[i], poly[(i + 1) % n]);
+            let (ex, ey) = (q.x - p.x, q.y - p.y);
+            let len = (ex * ex + ey * ey).sqrt();
+            if len < 1e-9 {
+                continue;
+            }
+            let (nx, ny) = (-ey / len, ex / len);
+            let proj = |poly: &[Pt]| {
+                poly.iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), v| {
+                        let d = v.x * nx + v.y * ny;
+                        (l.min(d), h.max(d))
+                    })
+            };
+            let (amin, amax) = proj(a);
+            let (bmin, bmax) = proj(b);
+            let k = dir.x * nx + dir.y * ny;
+            // Overlap on this axis needs amax + s k > bmin + eps and
+            // amin + s k < bmax - eps
+            let (l1, l2) = (bmin + SLIDE_EPS_MM - amax, bmax - SLIDE_EPS_MM - amin);
+            if k.abs() < 1e-12 {
+                if l1 >= 0.0 || l2 <= 0.0 {
+                    return None;
+                }
+            } else if k > 0.0 {
+                lo = lo.max(l1 / k);
+                hi = hi.min(l2 / k);
+            } else {
+                lo = lo.max(l2 / k);
+                hi = hi.min(l1 / k);
+            }
+            if lo >= hi {
+                return None;
+            }
+        }
+    }
+    Some((lo, hi))
+}
+
+// Total length of collinear, overlapping edge pairs between two outlines.
+fn shared_edge_len(a: &[Pt], b: &[Pt]) -> f64 {
+    let mut total = 0.0;
+    let (an, bn) = (a.len(), b.len());
+    for i in 0..an {
+        let (p, q) = (a[i], a[(i + 1) % an]);
+        let len = ((q.x - p.x).powi(2) + (q.y - p.y).powi(2)).sqrt();
+        if len < 1e-9 {
+            continue;
+        }
+        let (ux, uy) = ((q.x - p.x) / len, (q.y - p.y) / len);
+        for j in 0..bn {
+            let (r, s) = (b[j], b[(j + 1) % bn]);
+            let off = |w: Pt| (w.x - p.x) * uy - (w.y - p.y) * ux;
+            if off(r).abs() > FLUSH_TOL_MM || off(s).abs() > FLUSH_TOL_MM {
+                continue;
+            }
+            let along = |w: Pt| (w.x - p.x) * ux + (w.y - p.y) * uy;
+            let (t1, t2) = (along(r), along(s));
+            let lo = t1.min(t2).max(0.0);
+            let hi = t1.max(t2).min(len);
+            if hi > lo {
+                total += hi - lo;
+            }
+        }
+    }
+    total
+}
+
+/// One flip and turn of the piece being placed, before it is moved.
+struct Pose {
+    piece: Piece,
+    geom: Vec<Pt>,
+    ctr: Pt,
+    // Convex parts as validation splits them; the outline for a circle.
+    parts: Vec<Vec<Pt>>,
+    // Poses with the same outline up to position share this id.
+    outline: usize,
+    min: Pt,
+    max: Pt,
+}
+
+// Poses of one piece built while collecting its candidates.
+#[derive(Default)]
+struct Poses {
+    list: Vec<Pose>,
+    by_turn: HashMap<(bool, i64), usize>,
+    outlines: HashMap<Vec<(i64, i64)>, usize>,
+}
+
+impl Poses {
+    fn get(&mut self, t: &Piece, flip: bool, deg: f64) -> usize {
+        let deg = deg.rem_euclid(360.0);
+        let key = (flip, round_mm(deg) % 360_000);
+        if let Some(&i) = self.by_turn.get(&key) {
+            return i;
+        }
+        let mut piece = t.clone();
+        piece.flip = Some(flip);
+        piece.rotation = Some(deg);
+        let (geom, ctr) = piece_geom(&piece);
+        let (minx, miny, maxx, maxy) = bounds_of_points(&geom);
+        let mut shape: Vec<(i64, i64)> = geom
+            .iter()
+            .map(|q| (round_mm(q.x - minx), round_mm(q.y - miny)))
+            .collect();
+        shape.sort_unstable();
+        let next = self.outlines.len();
+        let outline = *self.outlines.entry(shape).or_insert(next);
+        let parts = if piece.shape.circle_radius().is_some() {
+            vec![geom.clone()]
+        } else {
+            convex_parts(&geom)
+        };
+        self.list.push(Pose {
+            piece,
+            geom,
+            ctr,
+            parts,
+            outline,
+            min: Pt { x: minx, y: miny },
+            max: Pt { x: maxx, y: maxy },
+        });
+        self.by_turn.insert(key, self.list.len() - 1);
+        self.list.len() - 1
+    }
+}
+
+// A pose in the frame's list and where it is moved.
+struct Candidate {
+    pose: usize,
+    at: Pt,
+    key: Vec<(i64, i64)>,
+}
+
+struct Placed {
+    proxy: PieceProxy,
+    anchors: Vec<Anchor>,
+    walls: Vec<Wall>,
+    // Convex parts that sliding pieces stop against, and their bounds.
+    parts: Vec<Vec<Pt>>,
+    reach: (f64, f64, f64, f64),
+}
+
+impl Placed {
+    fn new(proxy: PieceProxy) -> Self {
+        let (anchors, walls, parts) = match proxy.radius {
+            Some(_) => (
+                Vec::new(),
+                Vec::new(),
+                vec![circumscribed(&proxy.geom, proxy.ctr)],
+            ),
+            None => (
+                corner_anchors(&proxy.geom),
+                walls(&proxy.geom, false),
+                proxy.parts.clone(),
+            ),
+        };
+        Placed {
+            reach: bounds_of_points(&parts.concat()),
+            proxy,
+            anchors,
+            walls,
+            parts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+
+    fn catalog() -> ShapesCatalog {
+        serde_json::from_str(include_str!("../../shapes.json")).unwrap()
+    }
+
+    fn spec(json: &str) -> CountsSpec {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn assert_solves(spec: &CountsSpec) {
+        let solved = solve(spec, &catalog()).unwrap();
+        let report = validate(&solved);
+        assert!(report.ok, "{:?}", report.violations);
+    }
+
+    #[test]
+    fn solves_k6() {
+        assert_solves(&spec(include_str!("../../puzzle/k6.json")));
+    }
+
+    #[test]
+    fn solves_k_double() {
+        assert_solves(&spec(include_str!("../../puzzle/k-double.json")));
+    }
+
+    #[test]
+    fn rests_circles_against_walls_and_each_other() {
+        assert_solves(&spec(
+            r#"{"board": {"type": "rect", "w": 61, "h": 61},
+                "counts": {"circle_d30": 3, "tri_eq_30": 1}}"#,
+        ));
+    }
+
+    #[test]
+    fn places_pieces_around_obstacles() {
+        assert_solves(&spec(
+            r#"{"board": {"type": "polygon",
+                          "polygons": [[[0, 0], [91, 0], [91, 31], [0, 31]]],
+                          "obstacles": [[[30.5, 0], [60.5, 0], [60.5, 31], [30.5, 31]]]},
+                "counts": {"square_30": 2}}"#,
+        ));
+    }
+
+    #[test]
+    fn reports_an_impossible_puzzle() {
+        let spec = spec(
+            r#"{"board": {"type": "rect", "w": 50, "h": 50},
+                "counts": {"rect_30x60": 1}}"#,
+        );
+        assert_eq!(solve(&spec, &catalog()).err(), Some(SolveError::NoSolution));
+    }
+
+    #[test]
+    fn gives_up_at_the_check_limit() {
+        let spec = spec(include_str!("../../puzzle/k10.json"));
+        let puzzle = build_puzzle_from_counts(&spec, &catalog());
+        let mut solver = Solver::with_max_checks(&puzzle, 50).unwrap();
+        let done = loop {
+            if let Some(done) = solver.run(16) {
+                break done;
+            }
+        };
+        assert_eq!(done.err(), Some(SolveError::LimitReached));
+        assert_eq!(solver.checks(), 50);
+    }
+}
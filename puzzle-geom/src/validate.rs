@@ -0,0 +1,441 @@
+// Structured validation of a posed puzzle. The in-app panel, the CLI and the
+// JS `validate()` export all render this report.
+
+use geo::{Area, BooleanOps, Centroid, Coord, LineString, MultiPolygon, Polygon, Translate};
+use serde::Serialize;
+
+use crate::collide::{
+    bounds_overlap, obstacle_proxies, piece_proxy, proxies_penetration, proxy_border_depth,
+};
+use crate::constants::{FILL_SLIVER_MM, RING_WIDTH_MM, VALIDATION_EPS_MM};
+use crate::geom::{board_obstacles_geom, board_outer_geom, board_to_geom, piece_geom};
+use crate::layout::tray_for;
+use crate::models::{Goal, Point as Pt, Puzzle};
+
+/// A single rule violation. Piece numbers are indices into `Puzzle::pieces`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// Two pieces overlap by more than the tolerance.
+    Overlap {
+        a: usize,
+        b: usize,
+        penetration_mm: f64,
+    },
+    /// A piece is not contained by the inner border.
+    OutsideBorder { piece: usize, depth_mm: f64 },
+    /// A piece covers one of the board's obstacles (an index into
+    /// `Board::obstacles`) by more than the tolerance.
+    OnObstacle {
+        piece: usize,
+        obstacle: usize,
+        depth_mm: f64,
+    },
+    /// A piece is still in the staging tray beside the board.
+    Unplaced { piece: usize },
+    /// Fill goal: part of the board is left uncovered.
+    Uncovered { area_mm2: f64 },
+    /// Silhouette goal: the pieces' outline differs from the target by the
+    /// target area they miss and the area they add.
+    Silhouette { missing_mm2: f64, excess_mm2: f64 },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ValidationReport {
+    /// True when there are no violations.
+    pub ok: bool,
+    pub violations: Vec<Violation>,
+    /// Share of the board area covered by pieces (0-100); 0 without a board.
+    /// For a silhouette, the share of the target the pieces reproduce.
+    pub coverage_pct: f64,
+    /// Board (or silhouette) area not covered by any piece (mm²); 0 without
+    /// a board.
+    pub uncovered_mm2: f64,
+}
+
+/// Check overlaps and containment for every piece of `puzzle`, that no piece
+/// covers a board obstacle, and for a fill goal that no more of the board
+/// than a thin sliver is left bare. A silhouette has no containment; the
+/// placed pieces must match its outline (obstacles being holes in it) up to
+/// translation instead.
+pub fn validate(puzzle: &Puzzle) -> ValidationReport {
+    let proxies: Vec<_> = puzzle.pieces.iter().map(piece_proxy).collect();
+    let mut violations = Vec::new();
+
+    // 0) Pieces waiting in the tray are reported once and otherwise ignored
+    let board_geom = puzzle.board.as_ref().and_then(board_to_geom);
+    let tray = board_geom
+        .as_ref()
+        .map(|_| tray_for(puzzle.board.as_ref(), &puzzle.pieces));
+    let unplaced: Vec<bool> = proxies
+        .iter()
+        .map(|pr| tray.is_some_and(|t| t.contains(pr.bounds)))
+        .collect();
+    for (piece, _) in unplaced.iter().enumerate().filter(|(_, u)| **u) {
+        violations.push(Violation::Unplaced { piece });
+    }
+
+    // 1) Piece-piece overlaps (with tolerance). Sweep and prune along x so
+    // only pairs with overlapping bounds reach the exact test.
+    let mut by_x: Vec<usize> = (0..proxies.len()).filter(|&i| !unplaced[i]).collect();
+    by_x.sort_by(|&a, &b| proxies[a].bounds.0.total_cmp(&proxies[b].bounds.0));
+    let mut pairs = Vec::new();
+    for (k, &a) in by_x.iter().enumerate() {
+        for &b in &by_x[k + 1..] {
+            if proxies[b].bounds.0 >= proxies[a].bounds.2 - VALIDATION_EPS_MM {
+                break;
+            }
+            if bounds_overlap(proxies[a].bounds, proxies[b].bounds) {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    for (a, b) in pairs {
+        if let Some(depth) = proxies_penetration(&proxies[a], &proxies[b]) {
+            violations.push(Violation::Overlap {
+                a,
+                b,
+                penetration_mm: depth,
+            });
+        }
+    }
+
+    // 2) Containment against the board (three-layer logic)
+    if let Some(bg) = puzzle.frame().and(board_geom.as_ref()) {
+        let outer = puzzle
+            .board
+            .as_ref()
+            .and_then(|b| board_outer_geom(b, RING_WIDTH_MM));
+        for (i, pr) in proxies.iter().enumerate().filter(|(i, _)| !unplaced[*i]) {
+            if let Some(depth) = proxy_border_depth(pr, bg, outer.as_deref()) {
+                violations.push(Violation::OutsideBorder {
+                    piece: i,
+                    depth_mm: depth,
+                });
+            }
+        }
+    }
+
+    // 3) Obstacles inside the board are kept clear like fixed pieces
+    if puzzle.frame().is_some()
+        && let Some(board) = &puzzle.board
+    {
+        let obstacles = obstacle_proxies(board);
+        for (i, pr) in proxies.iter().enumerate().filter(|(i, _)| !unplaced[*i]) {
+            for (o, ob) in obstacles.iter().enumerate() {
+                if !bounds_overlap(pr.bounds, ob.bounds) {
+                    continue;
+                }
+                if let Some(depth) = proxies_penetration(pr, ob) {
+                    violations.push(Violation::OnObstacle {
+                        piece: i,
+                        obstacle: o,
+                        depth_mm: depth,
+                    });
+                }
+            }
+        }
+    }
+
+    // 4) Coverage, and for a fill goal the bare area left
+    let placed: Vec<usize> = (0..proxies.len()).filter(|&i| !unplaced[i]).collect();
+    let obstacles = puzzle
+        .board
+        .as_ref()
+        .map(board_obstacles_geom)
+        .unwrap_or_default();
+    let coverage = board_geom
+        .as_ref()
+        .map(|bg| coverage(bg, &obstacles, puzzle, &placed));
+    let (coverage_pct, uncovered_mm2) = coverage.map_or((0.0, 0.0), |c| (c.pct, c.uncovered_mm2));
+    if let Some(c) = coverage {
+        let slack = c.perimeter * FILL_SLIVER_MM;
+        match puzzle.goal {
+            Goal::Fill if c.uncovered_mm2 > slack => {
+                violations.push(Violation::Uncovered {
+                    area_mm2: c.uncovered_mm2,
+                });
+            }
+            Goal::Silhouette if c.uncovered_mm2 + c.excess_mm2 > slack => {
+                violations.push(Violation::Silhouette {
+                    missing_mm2: c.uncovered_mm2,
+                    excess_mm2: c.excess_mm2,
+                });
+            }
+            _ => {}
+        }
+    }
+    ValidationReport {
+        ok: violations.is_empty(),
+        violations,
+        coverage_pct,
+        uncovered_mm2,
+    }
+}
+
+/// For a fill or silhouette goal whose pieces cannot cover the board
+/// exactly: the total piece area and the board area (mm²), obstacles
+/// excluded.
+pub fn fill_area_mismatch(puzzle: &Puzzle) -> Option<(f64, f64)> {
+    if puzzle.goal == Goal::Contain {
+        return None;
+    }
+    let b = puzzle.board.as_ref()?;
+    let board = board_to_geom(b)?;
+    let obstacles = board_obstacles_geom(b);
+    let board_area = free_area(&board, &obstacles).unsigned_area();
+    let pieces_area: f64 = puzzle
+        .pieces
+        .iter()
+        .map(|p| to_geo(&piece_geom(p).0).unsigned_area())
+        .sum();
+    let perimeter = perimeter(&board) + perimeter(&obstacles);
+    ((pieces_area - board_area).abs() > perimeter * FILL_SLIVER_MM)
+        .then_some((pieces_area, board_area))
+}
+
+fn perimeter(polys: &[Vec<Pt>]) -> f64 {
+    polys
+        .iter()
+        .flat_map(|g| g.iter().zip(g.iter().cycle().skip(1)))
+        .map(|(a, b)| (b.x - a.x).hypot(b.y - a.y))
+        .sum()
+}
+
+fn to_geo(poly: &[Pt]) -> Polygon {
+    let coords: Vec<Coord> = poly.iter().map(|p| Coord { x: p.x, y: p.y }).collect();
+    Polygon::new(LineString::from(coords), vec![])
+}
+
+// The board regions with the obstacles cut out.
+fn free_area(board: &[Vec<Pt>], obstacles: &[Vec<Pt>]) -> MultiPolygon {
+    let board = MultiPolygon::new(board.iter().map(|g| to_geo(g)).collect());
+    if obstacles.is_empty() {
+        return board;
+    }
+    board.difference(&MultiPolygon::new(
+        obstacles.iter().map(|g| to_geo(g)).collect(),
+    ))
+}
+
+#[derive(Clone, Copy, Default)]
+struct Coverage {
+    pct: f64,
+    uncovered_mm2: f64,
+    /// Piece area outside the board (only computed for silhouettes).
+    excess_mm2: f64,
+    perimeter: f64,
+}
+
+// Area of the union of the placed pieces clipped to the board, relative to
+// the board, and the board area left over. A silhouette is first moved so
+// its centroid meets that of the pieces, so only the shape is compared.
+fn coverage(
+    board: &[Vec<Pt>],
+    obstacles: &[Vec<Pt>],
+    puzzle: &Puzzle,
+    placed: &[usize],
+) -> Coverage {
+    let perimeter = perimeter(board) + perimeter(obstacles);
+    let mut board = free_area(board, obstacles);
+    let board_area = board.unsigned_area();
+    if board_area <= 0.0 {
+        return Coverage {
+            perimeter,
+            ..Default::default()
+        };
+    }
+    let pieces = placed
+        .iter()
+        .map(|&i| piece_geom(&puzzle.pieces[i]).0)
+        .filter(|g| g.len() >= 3)
+        .fold(MultiPolygon::new(Vec::new()), |acc, g| {
+            acc.union(&MultiPolygon::new(vec![to_geo(&g)]))
+        });
+    let silhouette = puzzle.goal == Goal::Silhouette;
+    if silhouette && let (Some(target), Some(actual)) = (board.centroid(), pieces.centroid()) {
+        board.translate_mut(actual.x() - target.x(), actual.y() - target.y());
+    }
+    let uncovered_mm2 = board.difference(&pieces).unsigned_area();
+    let excess_mm2 = if silhouette {
+        pieces.difference(&board).unsigned_area()
+    } else {
+        0.0
+    };
+    Coverage {
+        pct: ((board_area - uncovered_mm2) / board_area * 100.0).clamp(0.0, 100.0),
+        uncovered_mm2,
+        excess_mm2,
+        perimeter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Board, Piece, PolygonPoint, Shape};
+
+    fn puzzle(pieces: &[(f64, f64)]) -> Puzzle {
+        Puzzle {
+            board: Some(Board {
+                type_: Some("rect".into()),
+                w: Some(100.0),
+                h: Some(100.0),
+                ..Default::default()
+            }),
+            pieces: pieces
+                .iter()
+                .map(|&(x, y)| {
+                    let mut p = Piece::new(Shape::Rect { w: 30.0, h: 30.0 });
+                    p.at = Some([x, y]);
+                    p
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clean_layout_passes() {
+        let report = validate(&puzzle(&[(0.0, 0.0), (30.0, 0.0), (0.0, 30.0)]));
+        assert!(report.ok);
+        assert!(report.violations.is_empty());
+        assert!((report.coverage_pct - 27.0).abs() < 1e-6);
+        assert!((report.uncovered_mm2 - 7300.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn overlap_names_both_pieces() {
+        let report = validate(&puzzle(&[(0.0, 0.0), (50.0, 50.0), (20.0, 0.0)]));
+        assert!(!report.ok);
+        assert_eq!(report.violations.len(), 1);
+        match report.violations[0] {
+            Violation::Overlap {
+                a,
+                b,
+                penetration_mm,
+            } => {
+                assert_eq!((a, b), (0, 2));
+                assert!(penetration_mm > VALIDATION_EPS_MM);
+            }
+            ref v => panic!("unexpected {v:?}"),
+        }
+    }
+
+    #[test]
+    fn piece_across_the_border_is_outside() {
+        let report = validate(&puzzle(&[(10.0, 10.0), (80.0, 10.0)]));
+        assert_eq!(report.violations.len(), 1);
+        match report.violations[0] {
+            Violation::OutsideBorder { piece, depth_mm } => {
+                assert_eq!(piece, 1);
+                assert!((depth_mm - 10.0).abs() < 1e-6);
+            }
+            ref v => panic!("unexpected {v:?}"),
+        }
+    }
+
+    #[test]
+    fn pieces_in_the_tray_are_unplaced_only() {
+        // Two stacked pieces in the tray are neither overlapping nor outside
+        let report = validate(&puzzle(&[(0.0, 0.0), (200.0, 0.0), (200.0, 0.0)]));
+        assert!(!report.ok);
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::Unplaced { piece: 1 },
+                Violation::Unplaced { piece: 2 },
+            ]
+        );
+        assert!((report.coverage_pct - 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fill_goal_reports_the_bare_area() {
+        let mut p = puzzle(&[(0.0, 0.0)]);
+        p.goal = Goal::Fill;
+        let report = validate(&p);
+        assert_eq!(
+            report.violations,
+            vec![Violation::Uncovered { area_mm2: 9100.0 }]
+        );
+    }
+
+    #[test]
+    fn piece_over_an_obstacle_is_reported() {
+        let mut p = puzzle(&[(0.0, 0.0), (35.0, 35.0)]);
+        let corner = |x, y| PolygonPoint::Point([x, y]);
+        p.board.as_mut().unwrap().obstacles = Some(vec![vec![
+            corner(60.0, 60.0),
+            corner(80.0, 60.0),
+            corner(80.0, 80.0),
+            corner(60.0, 80.0),
+        ]]);
+        let report = validate(&p);
+        match report.violations[..] {
+            [
+                Violation::OnObstacle {
+                    piece,
+                    obstacle,
+                    depth_mm,
+                },
+            ] => {
+                assert_eq!((piece, obstacle), (1, 0));
+                assert!((depth_mm - 5.0).abs() < 1e-6);
+            }
+            ref v => panic!("unexpected {v:?}"),
+        }
+    }
+
+    // A 60×30 silhouette and two 30×30 squares with their lower-left corners
+    // at `pieces`.
+    fn silhouette(pieces: &[(f64, f64)]) -> Puzzle {
+        let mut p = puzzle(pieces);
+        p.board.as_mut().unwrap().w = Some(60.0);
+        p.board.as_mut().unwrap().h = Some(30.0);
+        p.goal = Goal::Silhouette;
+        p
+    }
+
+    #[test]
+    fn silhouette_matches_anywhere() {
+        let report = validate(&silhouette(&[(-40.0, 50.0), (-10.0, 50.0)]));
+        assert_eq!(report.violations, vec![]);
+        assert!((report.coverage_pct - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn silhouette_reports_a_gap() {
+        let report = validate(&silhouette(&[(-40.0, 50.0), (-5.0, 50.0)]));
+        match report.violations[..] {
+            [
+                Violation::Silhouette {
+                    missing_mm2,
+                    excess_mm2,
+                },
+            ] => {
+                assert!((missing_mm2 - 150.0).abs() < 1e-6);
+                assert!((excess_mm2 - 150.0).abs() < 1e-6);
+            }
+            ref v => panic!("unexpected {v:?}"),
+        }
+    }
+
+    #[test]
+    fn silhouette_reports_an_overhang() {
+        let report = validate(&silhouette(&[(-40.0, 50.0), (-10.0, 60.0)]));
+        match report.violations[..] {
+            [
+                Violation::Silhouette {
+                    missing_mm2,
+                    excess_mm2,
+                },
+            ] => {
+                assert!(missing_mm2 > 0.0);
+                assert!((missing_mm2 - excess_mm2).abs() < 1e-6);
+            }
+            ref v => panic!("unexpected {v:?}"),
+        }
+    }
+}
@@ -3,7 +3,43 @@
 pub const DEFAULT_MM2PX: f64 = 3.0;
 /// Thickness of the virtual frame used for edge contact in lock mode (mm).
 pub const EDGE_RADIUS_MM: f64 = 0.05;
-/// Ring width for the board middle layer (mm).
-pub const RING_WIDTH_MM: f64 = 8.0;
-/// Unified radius for circle pieces (mm).
-pub const CIRCLE_R_MM: f64 = 15.0;
+/// Two taps on the same piece within this time (ms) flip it.
+pub const DOUBLE_TAP_MS: f64 = 350.0;
+/// Maximum distance between the two taps of a double tap (canvas px).
+pub const DOUBLE_TAP_PX: f64 = 40.0;
+/// A second touch off the held piece but within this distance of its
+/// outline still twists it (canvas px).
+pub const TWIST_REACH_PX: f64 = 48.0;
+/// Outline color of selected pieces.
+pub const SELECTION_COLOR: &str = "#1e88e5";
+/// Magnet mode reach around edges and vertices (screen px).
+pub const MAGNET_PX: f64 = 12.0;
+/// Magnet mode aligns edges that are within this angle of parallel (degrees).
+pub const MAGNET_ANGLE_DEG: f64 = 10.0;
+/// Color of magnet snap guides.
+pub const GUIDE_COLOR: &str = "#e91e63";
+/// Hinted piece and the ghost of its place.
+pub const HINT_COLOR: &str = "#ff9800";
+/// Outlines of a known solution drawn over the board.
+pub const SOLUTION_COLOR: &str = "#2e7d32";
+/// Fill of a silhouette target.
+pub const SILHOUETTE_COLOR: &str = "#455a64";
+/// Board outlines and vertex handles in the editor.
+pub const EDITOR_COLOR: &str = "#6a1b9a";
+/// Editor vertices snap to this grid (mm).
+pub const EDITOR_GRID_MM: f64 = 1.0;
+/// Reach of editor vertex and edge handles (screen px).
+pub const EDITOR_HANDLE_PX: f64 = 8.0;
+/// Radius given to a corner when it is first rounded (mm).
+pub const EDITOR_CORNER_R_MM: f64 = 10.0;
+/// Canvas px per mm at 100% zoom (CSS reference pixel, 96 per inch),
+/// used for the shape editor's real-size preview.
+pub const CSS_PX_PER_MM: f64 = 96.0 / 25.4;
+/// Default snap grid of the shape editor (mm).
+pub const SHAPE_EDITOR_GRID_MM: f64 = 5.0;
+/// Default polygon edge angle step of the shape editor (degrees).
+pub const SHAPE_EDITOR_ANGLE_DEG: f64 = 15.0;
+/// Background of the staging tray beside the board.
+pub const TRAY_COLOR: &str = "#f3efe6";
+/// Solver placement checks between yields to the browser.
+pub const SEARCH_SLICE_CHECKS: usize = 16;
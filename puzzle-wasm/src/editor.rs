@@ -0,0 +1,580 @@
+// Puzzle editor. Board regions are drawn and reshaped vertex by vertex on
+// the canvas, piece counts come from the shapes catalog, and the result is
+// exported as a counts-format JSON. After every change the edited spec is
+// rebuilt into `state.data`, so the board, the tray and the validation panel
+// preview exactly what the exported file loads as. The side panel (React)
+// reads `editor_state()` whenever an `editor-change` event fires.
+
+use wasm_bindgen::prelude::*;
+
+use puzzle_geom::geom::board_to_geom;
+use puzzle_geom::layout::build_puzzle_from_counts;
+use puzzle_geom::{Board, Counts, CountsSpec, Goal, Point as Pt, PolygonPoint, ShapesCatalog};
+
+use crate::canvas::{set_fill_style, set_stroke_style};
+use crate::constants::{EDITOR_COLOR, EDITOR_CORNER_R_MM, EDITOR_GRID_MM, EDITOR_HANDLE_PX};
+use crate::state::{State, with_state};
+use crate::utils::{dispatch_later, from_screen, to_screen};
+use crate::{draw, puzzle_loaded, save_text_as_file, update_note_dom};
+
+pub struct Editor {
+    pub spec: CountsSpec,
+    /// Region (index into the board polygons) that new vertices go to.
+    pub region: usize,
+    /// Selected vertex of `region`.
+    pub vertex: Option<usize>,
+    /// Pointer dragging the selected vertex.
+    pub drag: Option<i32>,
+}
+
+impl Editor {
+    /// Board regions as they are; empty when there is no polygon board yet.
+    pub fn polygons(&self) -> &[Vec<PolygonPoint>] {
+        let polys = self.spec.board.as_ref().and_then(|b| b.polygons.as_ref());
+        polys.map_or(&[], Vec::as_slice)
+    }
+
+    // Board regions for an edit, making the board a polygon one first.
+    fn regions(&mut self) -> &mut Vec<Vec<PolygonPoint>> {
+        let board = self.spec.board.get_or_insert_with(Board::default);
+        board.type_ = Some("polygon".to_string());
+        board.polygons.get_or_insert_with(Vec::new)
+    }
+
+    /// Vertex positions of every region, including unfinished ones.
+    pub fn outlines(&self) -> Vec<Vec<Pt>> {
+        self.polygons()
+            .iter()
+            .map(|p| p.iter().map(position).collect())
+            .collect()
+    }
+}
+
+fn position(p: &PolygonPoint) -> Pt {
+    match *p {
+        PolygonPoint::Point([x, y]) | PolygonPoint::Rounded([x, y, _]) => Pt { x, y },
+    }
+}
+
+fn radius(p: &PolygonPoint) -> Option<f64> {
+    match *p {
+        PolygonPoint::Point(_) => None,
+        PolygonPoint::Rounded([_, _, r]) => Some(r),
+    }
+}
+
+fn with_position(p: &PolygonPoint, at: Pt) -> PolygonPoint {
+    match radius(p) {
+        Some(r) => PolygonPoint::Rounded([at.x, at.y, r]),
+        None => PolygonPoint::Point([at.x, at.y]),
+    }
+}
+
+/// Enter the editor with the loaded puzzle's spec. The layout, undo history
+/// and hints stay as they are until the first edit.
+pub fn start(state: &mut State) {
+    state.shape_editor = None;
+    let catalog = catalog(state);
+    let loaded = state
+        .spec
+        .clone()
+        .unwrap_or_else(|| spec_from_pieces(state, &catalog));
+    let mut spec = editable(&loaded);
+    if spec.solutions.is_empty() {
+        // Solutions from a sidecar file travel with the export
+        spec.solutions = state.data.solutions.clone();
+    }
+    state.shapes_catalog = Some(catalog);
+    state.editor = Some(Editor {
+        spec,
+        region: 0,
+        vertex: None,
+        drag: None,
+    });
+    draw(state);
+    dispatch_later(&state.window, "editor-change");
+}
+
+// A counts spec for a puzzle loaded with posed pieces: one count per catalog
+// shape among its pieces.
+fn spec_from_pieces(state: &State, catalog: &ShapesCatalog) -> CountsSpec {
+    let mut counts: Vec<(String, u32)> = Vec::new();
+    for id in state.data.pieces.iter().filter_map(|p| p.id.as_deref()) {
+        if !catalog.shapes.iter().any(|s| s.id == id) {
+            continue;
+        }
+        match counts.iter_mut().find(|(k, _)| k == id) {
+            Some(e) => e.1 += 1,
+            None => counts.push((id.to_string(), 1)),
+        }
+    }
+    CountsSpec {
+        units: state.data.units.clone(),
+        board: state.data.board.clone(),
+        counts: Counts(counts),
+        goal: state.data.goal,
+        note_en: state.data.note_en.clone(),
+        note_zh: state.data.note_zh.clone(),
+        ..Default::default()
+    }
+}
+
+// `spec` with a rect board turned into a polygon so every corner can be
+// edited; everything else is kept.
+fn editable(spec: &CountsSpec) -> CountsSpec {
+    let board = spec.board.as_ref().and_then(|b| match &b.polygons {
+        Some(_) if b.type_.as_deref() == Some("polygon") => Some(b.clone()),
+        _ => board_to_geom(b).map(|geoms| Board {
+            type_: Some("polygon".to_string()),
+            polygons: Some(
+                geoms
+                    .iter()
+                    .map(|g| g.iter().map(|p| PolygonPoint::Point([p.x, p.y])).collect())
+                    .collect(),
+            ),
+            obstacles: b.obstacles.clone(),
+            ..Default::default()
+        }),
+    });
+    CountsSpec {
+        board,
+        ..spec.clone()
+    }
+}
+
+// Catalog for the count list: the loaded one, else the bundled copy.
+fn catalog(state: &State) -> ShapesCatalog {
+    state.shapes_catalog.clone().unwrap_or_else(|| {
+        serde_json::from_str(include_str!("../../shapes.json")).unwrap_or_default()
+    })
+}
+
+/// Leave the editor; the edited puzzle stays loaded.
+pub fn stop(state: &mut State) {
+    state.editor = None;
+    draw(state);
+}
+
+// Rebuild the preview puzzle, redraw and tell the panel.
+fn changed(state: &mut State) {
+    let Some(ed) = &state.editor else {
+        return;
+    };
+    let spec = export_spec(&ed.spec);
+    let catalog = catalog(state);
+    state.data = build_puzzle_from_counts(&spec, &catalog);
+    state.spec = Some(spec);
+    puzzle_loaded(state);
+    update_note_dom(state);
+    draw(state);
+    dispatch_later(&state.window, "editor-change");
+}
+
+// The spec as exported: unfinished regions (fewer than three vertices) are
+// left out, and so is an empty board.
+fn export_spec(spec: &CountsSpec) -> CountsSpec {
+    let mut out = spec.clone();
+    if let Some(b) = &mut out.board {
+        let polys: Vec<Vec<PolygonPoint>> = b
+            .polygons
+            .iter()
+            .flatten()
+            .filter(|p| p.len() >= 3)
+            .cloned()
+            .collect();
+        b.polygons = Some(polys);
+    }
+    if out
+        .board
+        .as_ref()
+        .is_some_and(|b| b.polygons.as_ref().is_none_or(Vec::is_empty))
+    {
+        out.board = None;
+    }
+    out
+}
+
+pub fn pointer_down(state: &mut State, id: i32, pt: (f64, f64)) {
+    let h = state.canvas.height() as f64;
+    let at = snap(from_screen(pt.0, pt.1, h, state.scale, state.offset));
+    let tol = EDITOR_HANDLE_PX / state.scale;
+    let raw = from_screen(pt.0, pt.1, h, state.scale, state.offset);
+    let Some(ed) = state.editor.as_mut() else {
+        return;
+    };
+    let outlines = ed.outlines();
+    // 1) Grab an existing vertex of any region
+    let hit = outlines.iter().enumerate().find_map(|(r, g)| {
+        g.iter()
+            .position(|&v| (v.x - raw.x).hypot(v.y - raw.y) <= tol)
+            .map(|v| (r, v))
+    });
+    if let Some((r, v)) = hit {
+        ed.region = r;
+        ed.vertex = Some(v);
+    } else if let Some(i) = outlines.get(ed.region).and_then(|g| edge_near(g, raw, tol)) {
+        // 2) Split an edge of the current region
+        let region = ed.region;
+        ed.regions()[region].insert(i + 1, PolygonPoint::Point([at.x, at.y]));
+        ed.vertex = Some(i + 1);
+    } else {
+        // 3) Add a vertex after the selected one (or at the end)
+        if ed.regions().is_empty() {
+            ed.regions().push(Vec::new());
+            ed.region = 0;
+        }
+        let region = ed.region;
+        let pos = ed.vertex.map_or(ed.regions()[region].len(), |v| v + 1);
+        ed.regions()[region].insert(pos, PolygonPoint::Point([at.x, at.y]));
+        ed.vertex = Some(pos);
+    }
+    ed.drag = Some(id);
+    changed(state);
+}
+
+pub fn pointer_move(state: &mut State, id: i32, pt: (f64, f64)) {
+    let h = state.canvas.height() as f64;
+    let at = snap(from_screen(pt.0, pt.1, h, state.scale, state.offset));
+    let Some(ed) = state.editor.as_mut() else {
+        return;
+    };
+    let (Some(v), Some(drag)) = (ed.vertex, ed.drag) else {
+        return;
+    };
+    let region = ed.region;
+    if drag != id {
+        return;
+    }
+    let p = &mut ed.regions()[region][v];
+    if position(p).x == at.x && position(p).y == at.y {
+        return;
+    }
+    *p = with_position(p, at);
+    changed(state);
+}
+
+pub fn pointer_up(state: &mut State, id: i32) {
+    if let Some(ed) = state.editor.as_mut()
+        && ed.drag == Some(id)
+    {
+        ed.drag = None;
+        draw(state);
+    }
+}
+
+/// Editor keys: Delete/Backspace removes the selected vertex, R toggles its
+/// rounding and Escape clears the selection.
+pub fn key(state: &mut State, key: &str) {
+    match key {
+        "delete" | "backspace" => delete_vertex(state),
+        "r" => {
+            let r = selected(state).map(|p| radius(&p));
+            if let Some(r) = r {
+                set_radius(state, if r.is_some() { 0.0 } else { EDITOR_CORNER_R_MM });
+            }
+        }
+        "escape" => {
+            if let Some(ed) = state.editor.as_mut() {
+                ed.vertex = None;
+            }
+            changed(state);
+        }
+        _ => {}
+    }
+}
+
+fn selected(state: &State) -> Option<PolygonPoint> {
+    let ed = state.editor.as_ref()?;
+    let (r, v) = (ed.region, ed.vertex?);
+    ed.polygons().get(r)?.get(v).cloned()
+}
+
+fn delete_vertex(state: &mut State) {
+    let Some(ed) = state.editor.as_mut() else {
+        return;
+    };
+    let (r, Some(v)) = (ed.region, ed.vertex) else {
+        return;
+    };
+    if let Some(region) = ed.regions().get_mut(r)
+        && v < region.len()
+    {
+        region.remove(v);
+        ed.vertex = v.checked_sub(1).or((!region.is_empty()).then_some(0));
+    }
+    changed(state);
+}
+
+// Round the selected corner with radius `r`; 0 makes it sharp again.
+fn set_radius(state: &mut State, r: f64) {
+    let Some(ed) = state.editor.as_mut() else {
+        return;
+    };
+    let (region, Some(v)) = (ed.region, ed.vertex) else {
+        return;
+    };
+    if let Some(p) = ed.regions().get_mut(region).and_then(|g| g.get_mut(v)) {
+        let at = position(p);
+        *p = if r > 0.0 {
+            PolygonPoint::Rounded([at.x, at.y, r])
+        } else {
+            PolygonPoint::Point([at.x, at.y])
+        };
+    }
+    changed(state);
+}
+
+fn snap(p: Pt) -> Pt {
+    Pt {
+        x: (p.x / EDITOR_GRID_MM).round() * EDITOR_GRID_MM,
+        y: (p.y / EDITOR_GRID_MM).round() * EDITOR_GRID_MM,
+    }
+}
+
+// Index of the first vertex of the closed outline's edge within `tol` of `p`.
+fn edge_near(poly: &[Pt], p: Pt, tol: f64) -> Option<usize> {
+    if poly.len() < 2 {
+        return None;
+    }
+    let n = poly.len();
+    (0..n).find(|&i| {
+        let (a, b) = (poly[i], poly[(i + 1) % n]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len2 = dx * dx + dy * dy;
+        if len2 <= 1e-12 {
+            return false;
+        }
+        let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+        (a.x + t * dx - p.x).hypot(a.y + t * dy - p.y) <= tol
+    })
+}
+
+/// Region outlines and vertex handles over the preview. Rounded corners get
+/// a ring, the selected vertex is filled.
+pub fn draw_handles(state: &State) {
+    let Some(ed) = &state.editor else {
+        return;
+    };
+    let h = state.canvas.height() as f64;
+    let ctx = &state.ctx;
+    for (r, poly) in ed.polygons().iter().enumerate() {
+        let active = r == ed.region;
+        let pts: Vec<(f64, f64)> = poly
+            .iter()
+            .map(|p| to_screen(position(p), h, state.scale, state.offset))
+            .collect();
+        set_stroke_style(ctx, EDITOR_COLOR);
+        ctx.set_line_width(if active { 2.0 } else { 1.0 });
+        ctx.begin_path();
+        for (i, &(x, y)) in pts.iter().enumerate() {
+            if i == 0 {
+                ctx.move_to(x, y);
+            } else {
+                ctx.line_to(x, y);
+            }
+        }
+        if pts.len() >= 3 {
+            ctx.close_path();
+        }
+        ctx.stroke();
+        for (i, (&(x, y), p)) in pts.iter().zip(poly).enumerate() {
+            let size = if active { 5.0 } else { 3.5 };
+            ctx.begin_path();
+            ctx.rect(x - size, y - size, size * 2.0, size * 2.0);
+            let chosen = active && ed.vertex == Some(i);
+            set_fill_style(ctx, if chosen { EDITOR_COLOR } else { "#fff" });
+            ctx.fill();
+            ctx.stroke();
+            if radius(p).is_some() {
+                ctx.begin_path();
+                let _ = ctx.arc(x, y, size + 4.0, 0.0, 2.0 * std::f64::consts::PI);
+                ctx.stroke();
+            }
+        }
+    }
+}
+
+// ---- JS API used by the editor panel ----
+
+fn with_editor(f: impl FnOnce(&mut Editor)) {
+    with_state(|s| {
+        if let Some(ed) = s.editor.as_mut() {
+            f(ed);
+            changed(s);
+        }
+    });
+}
+
+/// Enter the editor with the loaded puzzle.
+#[wasm_bindgen]
+pub fn editor_start() {
+    with_state(start);
+}
+
+/// Leave the editor, keeping the edited puzzle loaded.
+#[wasm_bindgen]
+pub fn editor_stop() {
+    with_state(stop);
+}
+
+/// Editor state for the panel as JSON: `{ regions, region, vertex: { x, y,
+/// r } | null, counts, goal, note_en, note_zh, shapes: [{ id, label_en,
+/// label_zh }] }`.
+#[wasm_bindgen]
+pub fn editor_state() -> String {
+    with_state(|s| {
+        let catalog = catalog(s);
+        let Some(ed) = s.editor.as_ref() else {
+            return "null".to_string();
+        };
+        let (region, vertex) = (ed.region, ed.vertex);
+        let regions = ed.polygons().len();
+        let vertex = vertex
+            .and_then(|v| ed.polygons().get(region)?.get(v).cloned())
+            .map(|p| {
+                let at = position(&p);
+                serde_json::json!({ "x": at.x, "y": at.y, "r": radius(&p) })
+            });
+        let shapes: Vec<_> = catalog
+            .shapes
+            .iter()
+            .map(|s| serde_json::json!({ "id": s.id, "label_en": s.label_en, "label_zh": s.label_zh }))
+            .collect();
+        serde_json::json!({
+            "regions": regions,
+            "region": region,
+            "vertex": vertex,
+            "counts": ed.spec.counts,
+            "goal": ed.spec.goal,
+            "note_en": ed.spec.note_en,
+            "note_zh": ed.spec.note_zh,
+            "shapes": shapes,
+        })
+        .to_string()
+    })
+    .unwrap_or_else(|| "null".to_string())
+}
+
+/// Set how many pieces of catalog shape `id` the puzzle has.
+#[wasm_bindgen]
+pub fn editor_set_count(id: String, n: u32) {
+    with_editor(|ed| {
+        let counts = &mut ed.spec.counts.0;
+        match counts.iter().position(|(k, _)| *k == id) {
+            Some(i) if n == 0 => {
+                counts.remove(i);
+            }
+            Some(i) => counts[i].1 = n,
+            None if n > 0 => counts.push((id, n)),
+            None => {}
+        }
+    });
+}
+
+/// Set the note for `lang` (`"en"` or `"zh"`); empty text removes it.
+#[wasm_bindgen]
+pub fn editor_set_note(lang: String, text: String) {
+    with_editor(|ed| {
+        let note = (!text.trim().is_empty()).then_some(text);
+        if lang == "zh" {
+            ed.spec.note_zh = note;
+        } else {
+            ed.spec.note_en = note;
+        }
+    });
+}
+
+/// Set the goal: `"contain"`, `"fill"` or `"silhouette"`.
+#[wasm_bindgen]
+pub fn editor_set_goal(goal: String) {
+    with_editor(|ed| {
+        if let Ok(g) = serde_json::from_value::<Goal>(serde_json::Value::String(goal)) {
+            ed.spec.goal = g;
+        }
+    });
+}
+
+/// Round the selected corner with radius `r` (mm); 0 makes it sharp.
+#[wasm_bindgen]
+pub fn editor_set_radius(r: f64) {
+    with_state(|s| set_radius(s, r));
+}
+
+/// Remove the selected vertex.
+#[wasm_bindgen]
+pub fn editor_delete_vertex() {
+    with_state(delete_vertex);
+}
+
+/// Start a new board region; the next clicks add its vertices.
+#[wasm_bindgen]
+pub fn editor_add_region() {
+    with_editor(|ed| {
+        ed.regions().push(Vec::new());
+        ed.region = ed.regions().len() - 1;
+        ed.vertex = None;
+    });
+}
+
+/// Remove the current board region.
+#[wasm_bindgen]
+pub fn editor_remove_region() {
+    with_editor(|ed| {
+        let region = ed.region;
+        let regions = ed.regions();
+        if region < regions.len() {
+            regions.remove(region);
+        }
+        ed.region = region.min(regions.len().saturating_sub(1));
+        ed.vertex = None;
+    });
+}
+
+/// Download the edited puzzle as counts-format JSON and return the text.
+#[wasm_bindgen]
+pub fn editor_export() -> Result<String, JsValue> {
+    with_state(|s| {
+        let ed = s
+            .editor
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("Editor is not open"))?;
+        let text = serde_json::to_string_pretty(&export_spec(&ed.spec))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let name = format!("{}.json", s.puzzle_name);
+        save_text_as_file(&s.document, &name, &text)?;
+        Ok(text)
+    })
+    .unwrap_or_else(|| Err(JsValue::from_str("State not initialized")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use puzzle_geom::{Puzzle, SolutionsFile};
+
+    #[test]
+    fn export_loads_back_as_the_same_puzzle() {
+        let catalog: ShapesCatalog =
+            serde_json::from_str(include_str!("../../shapes.json")).unwrap();
+        let mut loaded: CountsSpec =
+            serde_json::from_str(include_str!("../../puzzle/k10.json")).unwrap();
+        let sidecar: SolutionsFile =
+            serde_json::from_str(include_str!("../../puzzle/k10.solution.json")).unwrap();
+        loaded.order = Some(vec!["rt_30x60".to_string(), "square_30".to_string()]);
+        loaded.shapes_file = Some("shapes.json".to_string());
+        loaded.solutions = sidecar.solutions;
+
+        let text = serde_json::to_string(&export_spec(&editable(&loaded))).unwrap();
+        let reloaded: CountsSpec = serde_json::from_str(&text).unwrap();
+        assert_eq!(reloaded.order, loaded.order);
+        assert_eq!(reloaded.shapes_file, loaded.shapes_file);
+
+        let before = build_puzzle_from_counts(&loaded, &catalog);
+        let after = build_puzzle_from_counts(&reloaded, &catalog);
+        let json = |p: &Puzzle| {
+            let board = board_to_geom(p.board.as_ref().unwrap());
+            serde_json::to_value((board, &p.pieces, &p.solutions)).unwrap()
+        };
+        assert_eq!(json(&after), json(&before));
+    }
+}
@@ -0,0 +1,115 @@
+// Progressive hints for a stuck player. Asking again about the same piece
+// goes one step further: first the misplaced piece is highlighted, then a
+// ghost outline shows where it belongs, then it is snapped into place.
+// Hints follow the known solution closest to the current layout, or one
+// found by the solver when the puzzle has none.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use puzzle_geom::Piece;
+use puzzle_geom::geom::refresh_geom_cache;
+use puzzle_geom::solution::{Hint as Step, in_place, next_hint, solution_from_pieces};
+use puzzle_geom::solver::SolveError;
+
+use crate::history::Command;
+use crate::pointer::piece_index;
+use crate::search::{failure_message, search};
+use crate::state::State;
+use crate::{commit_gesture, update_status_dom};
+
+/// Highlight the misplaced piece.
+pub const HINT_PIECE: u8 = 1;
+/// Also show a ghost outline of its place.
+pub const HINT_GHOST: u8 = 2;
+/// Snap the piece into place.
+pub const HINT_SNAP: u8 = 3;
+
+/// The hint currently shown.
+#[derive(Clone, Debug)]
+pub struct Hint {
+    /// `__label_idx` of the piece the hint is about.
+    pub label: Option<usize>,
+    /// The piece in its solution pose, with its outline cached.
+    pub target: Piece,
+    pub level: u8,
+}
+
+/// Give the next hint and count it. Returns the level given, or `None` when
+/// the layout is solved or no solution is known or can be found; the user
+/// is told about the latter. Without known solutions the solver searches
+/// in the background first (see `search`).
+pub async fn request_hint(st: &Rc<RefCell<State>>) -> Option<u8> {
+    let known = !st.borrow().data.solutions.is_empty();
+    let found = if known {
+        Vec::new()
+    } else {
+        let solved = search(st)
+            .await?
+            .and_then(|p| solution_from_pieces(&p.pieces).ok_or(SolveError::NoSolution));
+        match solved {
+            Ok(sol) => vec![sol],
+            Err(e) => {
+                tell_no_hint(&st.borrow(), e);
+                return None;
+            }
+        }
+    };
+    let mut s = st.borrow_mut();
+    let state = &mut *s;
+    let solutions = if known { &state.data.solutions } else { &found };
+    let Step { piece, mut target } = next_hint(&state.data.pieces, solutions)?;
+    let label = state.data.pieces[piece].__label_idx;
+    let level = match &state.hint {
+        Some(h) if h.label == label => (h.level + 1).min(HINT_SNAP),
+        _ => HINT_PIECE,
+    };
+    state.hints_used += 1;
+    update_status_dom(state);
+    if level == HINT_SNAP {
+        commit_gesture(state);
+        let before = state.data.pieces.clone();
+        let p = &mut state.data.pieces[piece];
+        p.at = target.at;
+        p.rotation = target.rotation;
+        p.anchor = target.anchor.clone();
+        p.flip = target.flip;
+        let cmd = Command::Replace {
+            before,
+            after: state.data.pieces.clone(),
+        };
+        state.history.push(cmd);
+        state.drags.clear();
+        state.rot_vel = 0.0;
+        state.hint = None;
+    } else {
+        refresh_geom_cache(&mut target);
+        state.hint = Some(Hint {
+            label,
+            target,
+            level,
+        });
+    }
+    Some(level)
+}
+
+/// Drop the hint once its piece is in place (or gone).
+pub fn refresh_hint(state: &mut State) {
+    let done = state.hint.as_ref().is_none_or(|h| {
+        piece_index(state, h.label).is_none_or(|i| in_place(&state.data.pieces[i], &h.target))
+    });
+    if done {
+        state.hint = None;
+    }
+}
+
+// No solution to follow: say so, and why.
+fn tell_no_hint(state: &State, err: SolveError) {
+    let lead = if state.lang == "zh" {
+        "暂无提示。"
+    } else {
+        "No hint available. "
+    };
+    let msg = format!("{lead}{}", failure_message(err, &state.lang));
+    let _ = state.window.alert_with_message(&msg);
+}
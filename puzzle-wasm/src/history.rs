@@ -0,0 +1,187 @@
+// Undo/redo history for board edits. Every edit is recorded as a command
+// holding both sides of the change so it can be applied in either
+// direction. Continuous gestures (a drag, a held Q/E) are coalesced into a
+// single command between `begin` and `commit`; several pieces can have a
+// gesture open at once (multi-touch).
+
+use puzzle_geom::Piece;
+
+/// Oldest commands are dropped beyond this depth.
+const MAX_UNDO: usize = 200;
+
+#[derive(Clone, Debug)]
+pub enum Command {
+    /// Pose change of one piece (move, rotate, flip). The piece is found by
+    /// its stable label so raising it to the top does not invalidate the
+    /// command.
+    Transform {
+        before: Box<Piece>,
+        after: Box<Piece>,
+    },
+    /// Whole-layout change such as reset or show solution.
+    Replace {
+        before: Vec<Piece>,
+        after: Vec<Piece>,
+    },
+}
+
+impl Command {
+    fn apply(&self, pieces: &mut Vec<Piece>, forward: bool) {
+        match self {
+            Command::Transform { before, after } => {
+                let (from, to) = if forward {
+                    (before, after)
+                } else {
+                    (after, before)
+                };
+                if let Some(p) = pieces
+                    .iter_mut()
+                    .find(|p| p.__label_idx == from.__label_idx)
+                {
+                    *p = (**to).clone();
+                }
+            }
+            Command::Replace { before, after } => {
+                *pieces = if forward { after } else { before }.clone();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// Snapshots of the pieces being manipulated by open gestures.
+    pending: Vec<Piece>,
+    /// Bumped whenever a command changes the pieces.
+    revision: u64,
+}
+
+impl History {
+    /// Forget everything, e.g. after loading another puzzle.
+    pub fn clear(&mut self) {
+        *self = History::default();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Number of commands applied, undone or redone so far. Anything derived
+    /// from the layout stays valid while this stays the same.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Start a gesture on `piece`. Ignored while the piece already has one
+    /// open, so rotating during a drag folds into the drag.
+    pub fn begin(&mut self, piece: &Piece) {
+        if !self
+            .pending
+            .iter()
+            .any(|p| p.__label_idx == piece.__label_idx)
+        {
+            self.pending.push(piece.clone());
+        }
+    }
+
+    /// Close every open gesture.
+    pub fn commit(&mut self, pieces: &[Piece]) {
+        for before in std::mem::take(&mut self.pending) {
+            self.record(before, pieces);
+        }
+    }
+
+    /// Close the gesture open on the piece labelled `label`, if any.
+    pub fn commit_piece(&mut self, label: Option<usize>, pieces: &[Piece]) {
+        if let Some(i) = self.pending.iter().position(|p| p.__label_idx == label) {
+            let before = self.pending.remove(i);
+            self.record(before, pieces);
+        }
+    }
+
+    // Record a closed gesture if the piece actually changed.
+    fn record(&mut self, before: Piece, pieces: &[Piece]) {
+        if let Some(after) = pieces.iter().find(|p| p.__label_idx == before.__label_idx)
+            && !same_pose(&before, after)
+        {
+            self.push(Command::Transform {
+                before: Box::new(before),
+                after: Box::new(after.clone()),
+            });
+        }
+    }
+
+    /// Record a command that has already been applied.
+    pub fn push(&mut self, cmd: Command) {
+        self.undo.push(cmd);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.revision += 1;
+    }
+
+    /// Revert the latest command; returns false when there is nothing to undo.
+    pub fn undo(&mut self, pieces: &mut Vec<Piece>) -> bool {
+        self.commit(pieces);
+        match self.undo.pop() {
+            Some(cmd) => {
+                cmd.apply(pieces, false);
+                self.redo.push(cmd);
+                self.revision += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-apply the latest undone command; returns false when there is none.
+    pub fn redo(&mut self, pieces: &mut Vec<Piece>) -> bool {
+        self.commit(pieces);
+        match self.redo.pop() {
+            Some(cmd) => {
+                cmd.apply(pieces, true);
+                self.undo.push(cmd);
+                self.revision += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// True when both layouts hold the same pieces, in the same order and pose.
+pub fn same_layout(a: &[Piece], b: &[Piece]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(x, y)| x.__label_idx == y.__label_idx && same_pose(x, y))
+}
+
+fn same_pose(a: &Piece, b: &Piece) -> bool {
+    a.has_pose(&b.pose())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use puzzle_geom::Shape;
+
+    #[test]
+    fn records_an_anchor_change() {
+        let mut piece = Piece::new(Shape::Rect { w: 30.0, h: 30.0 });
+        piece.at = Some([0.0, 0.0]);
+        let mut pieces = vec![piece];
+        let mut history = History::default();
+        history.begin(&pieces[0]);
+        pieces[0].anchor = Some("center".to_string());
+        history.commit(&pieces);
+        assert!(history.can_undo());
+    }
+}
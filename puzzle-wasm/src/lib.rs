@@ -5,259 +5,85 @@ use js_sys::Array;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 use web_sys::{
-    Blob, CanvasRenderingContext2d, Document, Event, HtmlCanvasElement, HtmlElement, KeyboardEvent,
-    MouseEvent, Url, Window,
+    Blob, CanvasRenderingContext2d, CanvasWindingRule, Document, Event, HtmlCanvasElement,
+    HtmlElement, KeyboardEvent, MouseEvent, Url, Window,
 };
 
-use blueprint_core::PolygonPoint;
-use earcutr::earcut;
 use geo_types::Coord as GeoCoord;
 use polyline as polyline_codec;
-use rapier2d::na::{Isometry2, Point2};
-use rapier2d::prelude::*;
+use puzzle_geom::constants::RING_WIDTH_MM;
+use puzzle_geom::constants::TRAY_SPACING_MM;
+use puzzle_geom::geom::{
+    board_obstacles_geom, board_outer_geom, board_to_geom, bounds_of_points, refresh_geom_cache,
+};
+use puzzle_geom::layout::{
+    build_puzzle_from_counts, pack_into_tray, parse_seed, scramble_into_tray, tray_for,
+};
+use puzzle_geom::solution::{apply_solution, matching_solution};
+use puzzle_geom::solver::SolveError;
+use puzzle_geom::validate::Violation;
+use puzzle_geom::{
+    CountsSpec, Goal, Piece, Point as Pt, Puzzle, ShapesCatalog, Solution, SolutionsFile,
+};
 
 mod canvas;
 mod constants;
-mod models;
+mod editor;
+mod hint;
+mod history;
+mod lock;
+mod magnet;
+mod physics;
+mod pointer;
+mod search;
+mod selection;
+mod shape_editor;
+mod snap;
 mod state;
 mod upload;
 mod utils;
 
 use crate::canvas::{set_fill_style, set_stroke_style};
 use constants::*;
-use models::{Board, CountsSpec, Piece, Point as Pt, Puzzle, ShapeDef, ShapesCatalog};
-use state::{STATE, State};
+use history::{Command, same_layout};
+use pointer::is_held;
+use snap::SnapMode;
+use state::{STATE, State, Validation};
 use utils::{
-    asset_url, fetch_text_with_fallbacks, from_screen, get_query_param, log, sync_canvas_size,
-    to_screen,
+    asset_url, fetch_text_with_fallbacks, from_screen, get_query_param, log, set_query_param,
+    sync_canvas_size, to_screen,
 };
 
-fn rotate_point(p: Pt, c: Pt, ang: f64, flip: bool) -> Pt {
-    let mut dx = p.x - c.x;
-    let dy = p.y - c.y;
-    if flip {
-        dx = -dx;
-    }
-    let (s, ca) = ang.sin_cos();
-    Pt {
-        x: c.x + dx * ca - dy * s,
-        y: c.y + dx * s + dy * ca,
+fn draw(state: &mut State) {
+    sync_canvas_size(state);
+    if state.shape_editor.is_some() {
+        shape_editor::draw_preview(state);
+        return;
     }
-}
-
-fn piece_rotation(p: &Piece) -> f64 {
-    (p.rotation.unwrap_or(0.0)).to_radians()
-}
-fn piece_flip(p: &Piece) -> bool {
-    p.flip.unwrap_or(false)
-}
-
-fn piece_geom(p: &Piece) -> (Vec<Pt>, Pt) {
-    let rot = piece_rotation(p);
-    let flip = piece_flip(p);
-    let anchor = p.anchor.clone().unwrap_or_else(|| "bottomleft".to_string());
-    let apply = |pts: Vec<Pt>, ctr: Pt| -> (Vec<Pt>, Pt) {
-        let out = pts
-            .into_iter()
-            .map(|q| rotate_point(q, ctr, rot, flip))
-            .collect();
-        (out, ctr)
-    };
-    match p.type_.as_str() {
-        "rect" => {
-            let w = p.w.unwrap_or(0.0);
-            let h = p.h.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = if anchor == "center" {
-                Pt {
-                    x: at[0] - w / 2.0,
-                    y: at[1] - h / 2.0,
-                }
-            } else {
-                Pt { x: at[0], y: at[1] }
-            };
-            let tl = Pt {
-                x: bl.x,
-                y: bl.y + h,
-            };
-            let tr = Pt {
-                x: bl.x + w,
-                y: bl.y + h,
-            };
-            let br = Pt {
-                x: bl.x + w,
-                y: bl.y,
-            };
-            let ctr = Pt {
-                x: bl.x + w / 2.0,
-                y: bl.y + h / 2.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
+    for p in &mut state.data.pieces {
+        if refresh_geom_cache(p) {
+            // Maintain an encoded copy for potential interop/export
+            p.__geom_pl = p.__geom.as_deref().map(encode_polyline_mm);
         }
-        "equilateral_triangle" => {
-            let s = p.side.unwrap_or(0.0);
-            let h = s * 3.0_f64.sqrt() / 2.0;
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = if anchor == "center" {
-                Pt {
-                    x: at[0] - s / 2.0,
-                    y: at[1] - h / 3.0,
-                }
-            } else {
-                Pt { x: at[0], y: at[1] }
-            };
-            let a = Pt { x: bl.x, y: bl.y };
-            let b = Pt {
-                x: bl.x + s,
-                y: bl.y,
-            };
-            let c = Pt {
-                x: bl.x + s / 2.0,
-                y: bl.y + h,
-            };
-            let ctr = Pt {
-                x: (a.x + b.x + c.x) / 3.0,
-                y: (a.y + b.y + c.y) / 3.0,
-            };
-            apply(vec![a, b, c], ctr)
-        }
-        "right_triangle" => {
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let a = Pt { x: at[0], y: at[1] };
-            let b = Pt {
-                x: at[0] + p.a.unwrap_or(0.0),
-                y: at[1],
-            };
-            let c = Pt {
-                x: at[0],
-                y: at[1] + p.b.unwrap_or(0.0),
-            };
-            let ctr = Pt {
-                x: (a.x + b.x + c.x) / 3.0,
-                y: (a.y + b.y + c.y) / 3.0,
-            };
-            apply(vec![a, b, c], ctr)
-        }
-        "regular_polygon" => {
-            let n = p.n.unwrap_or(3) as i32;
-            let side = p.side.unwrap_or(0.0);
-            let r = side / (2.0 * (std::f64::consts::PI / n as f64).sin());
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let ctr = Pt { x: at[0], y: at[1] };
-            let base_ang = piece_rotation(p)
-                + if piece_flip(p) {
-                    std::f64::consts::PI
-                } else {
-                    0.0
-                };
-            let mut pts = Vec::new();
-            for i in 0..n {
-                let a = base_ang + (i as f64) * 2.0 * std::f64::consts::PI / (n as f64);
-                pts.push(Pt {
-                    x: ctr.x + r * a.cos(),
-                    y: ctr.y + r * a.sin(),
-                });
-            }
-            (pts, ctr)
-        }
-        "circle" => {
-            // Use a polyline for computation/hit-testing; render as a true circle.
-            let r = p.d.unwrap_or_else(|| p.r.unwrap_or(0.0) * 2.0) / 2.0;
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let ctr = Pt { x: at[0], y: at[1] };
-            let pts = tessellate_circle_polyline(ctr, r, 0.3);
-            (pts, ctr)
-        }
-        "isosceles_trapezoid" => {
-            let b0 = p.base_bottom.unwrap_or(0.0);
-            let b1 = p.base_top.unwrap_or(0.0);
-            let h = p.height.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = Pt { x: at[0], y: at[1] };
-            let br = Pt {
-                x: bl.x + b0,
-                y: bl.y,
-            };
-            let tl = Pt {
-                x: bl.x + (b0 - b1) / 2.0,
-                y: bl.y + h,
-            };
-            let tr = Pt {
-                x: tl.x + b1,
-                y: tl.y,
-            };
-            let ctr = Pt {
-                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
-                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
-        }
-        "parallelogram" => {
-            let b = p.base.unwrap_or(0.0);
-            let h = p.height.unwrap_or(0.0);
-            let off = p.offset_top.unwrap_or(0.0);
-            let at = p.at.unwrap_or([0.0, 0.0]);
-            let bl = Pt { x: at[0], y: at[1] };
-            let br = Pt {
-                x: bl.x + b,
-                y: bl.y,
-            };
-            let tl = Pt {
-                x: bl.x + off,
-                y: bl.y + h,
-            };
-            let tr = Pt {
-                x: tl.x + b,
-                y: tl.y,
-            };
-            let ctr = Pt {
-                x: (bl.x + br.x + tr.x + tl.x) / 4.0,
-                y: (bl.y + br.y + tr.y + tl.y) / 4.0,
-            };
-            apply(vec![bl, br, tr, tl], ctr)
-        }
-        "polygon" => {
-            let pts = p
-                .points
-                .clone()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|v| Pt { x: v[0], y: v[1] })
-                .collect::<Vec<_>>();
-            let n = pts.len().max(1) as f64;
-            let ctr = pts.iter().fold(Pt { x: 0.0, y: 0.0 }, |acc, q| Pt {
-                x: acc.x + q.x,
-                y: acc.y + q.y,
-            });
-            let ctr = Pt {
-                x: ctr.x / n,
-                y: ctr.y / n,
-            };
-            apply(pts, ctr)
-        }
-        _ => (Vec::new(), Pt { x: 0.0, y: 0.0 }),
     }
-}
-
-fn draw(state: &mut State) {
-    sync_canvas_size(state);
     update_viewport(state);
     let width = state.canvas.width() as f64;
     let height = state.canvas.height() as f64;
     state.ctx.clear_rect(0.0, 0.0, width, height);
     draw_board(state);
+    draw_tray(state);
+    hint::refresh_hint(state);
+    draw_hint_ghost(state);
 
-    for (i, p) in state.data.pieces.iter_mut().enumerate() {
-        let (geom, ctr) = piece_geom(p);
-        p.__geom = Some(geom.clone());
-        // Maintain an encoded copy for potential interop/export
-        p.__geom_pl = Some(encode_polyline_mm(&geom));
-        p.__ctr = Some(ctr);
+    for &i in &state.z_order {
+        let p = &state.data.pieces[i];
+        let Some((geom, ctr)) = p.cached_geom() else {
+            continue;
+        };
         let color_idx = p.__color_idx.unwrap_or(i);
-        let color = piece_color(color_idx);
-        if p.type_ == "circle" {
+        let color = blueprint_core::piece_color(color_idx);
+        if let Some(r) = p.shape.circle_radius() {
             // Render true circle while computations use polyline
-            let r = p.d.unwrap_or_else(|| p.r.unwrap_or(0.0) * 2.0) / 2.0;
             draw_colored_circle(
                 &state.ctx,
                 height,
@@ -271,13 +97,23 @@ fn draw(state: &mut State) {
             draw_colored_polygon(
                 &state.ctx,
                 height,
-                &geom,
+                geom,
                 false,
                 state.scale,
                 state.offset,
                 &color,
             );
         }
+        if state.selected.contains(&i) {
+            draw_selection_outline(
+                &state.ctx,
+                height,
+                geom,
+                p.shape.circle_radius().map(|r| (ctr, r)),
+                state.scale,
+                state.offset,
+            );
+        }
         // Draw center number label
         let (cx, cy) = to_screen(ctr, height, state.scale, state.offset);
         let size = (4.5 * state.scale).clamp(10.0, 28.0);
@@ -292,33 +128,157 @@ fn draw(state: &mut State) {
         set_fill_style(&state.ctx, "#111");
         let _ = state.ctx.fill_text(&num.to_string(), cx, cy);
     }
+    draw_guides(state);
+    draw_hint_piece(state);
+    draw_solution_overlay(state);
+    editor::draw_handles(state);
     update_validation_dom(state);
+    update_history_dom(state);
+}
+
+// Magnet snap targets: matched edges as lines, matched vertices as rings.
+fn draw_guides(state: &State) {
+    let h = state.canvas.height() as f64;
+    let ctx = &state.ctx;
+    set_stroke_style(ctx, GUIDE_COLOR);
+    ctx.set_line_width(2.5);
+    for g in &state.guides {
+        let (ax, ay) = to_screen(g.a, h, state.scale, state.offset);
+        let (bx, by) = to_screen(g.b, h, state.scale, state.offset);
+        ctx.begin_path();
+        if (ax - bx).abs() < 1e-9 && (ay - by).abs() < 1e-9 {
+            let _ = ctx.arc(ax, ay, 6.0, 0.0, 2.0 * std::f64::consts::PI);
+        } else {
+            ctx.move_to(ax, ay);
+            ctx.line_to(bx, by);
+        }
+        ctx.stroke();
+    }
+}
+
+// Where the hinted piece belongs: a faint filled outline under the pieces.
+fn draw_hint_ghost(state: &State) {
+    let Some(h) = state.hint.as_ref().filter(|h| h.level >= hint::HINT_GHOST) else {
+        return;
+    };
+    let Some((geom, ctr)) = h.target.cached_geom() else {
+        return;
+    };
+    let circle = h.target.shape.circle_radius().map(|r| (ctr, r));
+    let height = state.canvas.height() as f64;
+    let ctx = &state.ctx;
+    trace_outline(ctx, height, geom, circle, state.scale, state.offset);
+    ctx.set_global_alpha(0.25);
+    set_fill_style(ctx, HINT_COLOR);
+    ctx.fill();
+    ctx.set_global_alpha(1.0);
+    let _ = ctx.set_line_dash(&Array::of2(&6.0.into(), &4.0.into()));
+    ctx.set_line_width(2.0);
+    set_stroke_style(ctx, HINT_COLOR);
+    ctx.stroke();
+    let _ = ctx.set_line_dash(&Array::new());
+}
+
+// Outline the piece a hint is about.
+fn draw_hint_piece(state: &State) {
+    let Some(h) = &state.hint else {
+        return;
+    };
+    let Some((geom, ctr)) =
+        pointer::piece_index(state, h.label).and_then(|i| state.data.pieces[i].cached_geom())
+    else {
+        return;
+    };
+    let p = &h.target;
+    let circle = p.shape.circle_radius().map(|r| (ctr, r));
+    let height = state.canvas.height() as f64;
+    trace_outline(&state.ctx, height, geom, circle, state.scale, state.offset);
+    state.ctx.set_line_width(4.0);
+    set_stroke_style(&state.ctx, HINT_COLOR);
+    state.ctx.stroke();
 }
 
-// Approximate a circle by a polyline with maximum sagitta error `max_err_mm`.
-// Returns vertices in CCW order.
-fn tessellate_circle_polyline(center: Pt, r: f64, max_err_mm: f64) -> Vec<Pt> {
-    let r = r.max(0.0);
-    if r <= 0.0 {
-        return vec![center];
+// Known solution as dashed outlines with the piece numbers, drawn on top so
+// it stays visible while the pieces are moved into place.
+fn draw_solution_overlay(state: &State) {
+    let pieces = &state.overlay_pieces;
+    if pieces.is_empty() {
+        return;
     }
-    let e = max_err_mm.max(0.05).min(r);
-    // dtheta based on sagitta formula; clamp to reasonable range
-    let mut dtheta = 2.0 * (1.0 - e / r).clamp(-1.0, 1.0).acos();
-    if !dtheta.is_finite() || dtheta <= 0.0 {
-        dtheta = std::f64::consts::PI / 16.0;
+    let h = state.canvas.height() as f64;
+    let ctx = &state.ctx;
+    let _ = ctx.set_line_dash(&Array::of2(&6.0.into(), &4.0.into()));
+    ctx.set_line_width(2.0);
+    set_stroke_style(ctx, SOLUTION_COLOR);
+    set_fill_style(ctx, SOLUTION_COLOR);
+    let size = (3.5 * state.scale).clamp(9.0, 22.0);
+    ctx.set_font(&format!("{}px sans-serif", size));
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("middle");
+    for (i, p) in pieces.iter().enumerate() {
+        let Some((geom, ctr)) = p.cached_geom() else {
+            continue;
+        };
+        let circle = p.shape.circle_radius().map(|r| (ctr, r));
+        trace_outline(ctx, h, geom, circle, state.scale, state.offset);
+        ctx.stroke();
+        let (cx, cy) = to_screen(ctr, h, state.scale, state.offset);
+        let num = p.__label_idx.unwrap_or(i) + 1;
+        let _ = ctx.fill_text(&num.to_string(), cx, cy);
     }
-    let mut n = (2.0 * std::f64::consts::PI / dtheta).ceil() as usize;
-    n = n.clamp(12, 128);
-    let mut pts = Vec::with_capacity(n);
-    for i in 0..n {
-        let a = (i as f64) * 2.0 * std::f64::consts::PI / (n as f64);
-        pts.push(Pt {
-            x: center.x + r * a.cos(),
-            y: center.y + r * a.sin(),
-        });
+    let _ = ctx.set_line_dash(&Array::new());
+}
+
+// Show known solution `index` as the overlay, or hide it with `None`. The
+// solution is posed and its outlines built here rather than every frame.
+fn set_solution_overlay(state: &mut State, index: Option<usize>) {
+    state.solution_overlay = index;
+    state.overlay_pieces = index
+        .and_then(|i| state.data.solutions.get(i))
+        .and_then(|sol| apply_solution(&state.data.pieces, sol))
+        .unwrap_or_default();
+    for p in &mut state.overlay_pieces {
+        refresh_geom_cache(p);
     }
-    pts
+}
+
+// Highlight outline around a selected piece (true circle for circles).
+fn draw_selection_outline(
+    ctx: &CanvasRenderingContext2d,
+    canvas_h: f64,
+    geom: &[Pt],
+    circle: Option<(Pt, f64)>,
+    scale: f64,
+    offset: (f64, f64),
+) {
+    trace_outline(ctx, canvas_h, geom, circle, scale, offset);
+    ctx.set_line_width(3.5);
+    set_stroke_style(ctx, SELECTION_COLOR);
+    ctx.stroke();
+}
+
+// Closed path along a piece outline, or its true circle.
+fn trace_outline(
+    ctx: &CanvasRenderingContext2d,
+    canvas_h: f64,
+    geom: &[Pt],
+    circle: Option<(Pt, f64)>,
+    scale: f64,
+    offset: (f64, f64),
+) {
+    ctx.begin_path();
+    if let Some((ctr, r)) = circle {
+        let (cx, cy) = to_screen(ctr, canvas_h, scale, offset);
+        let _ = ctx.arc(cx, cy, r * scale, 0.0, 2.0 * std::f64::consts::PI);
+    } else if let Some(first) = geom.first() {
+        let (sx, sy) = to_screen(*first, canvas_h, scale, offset);
+        ctx.move_to(sx, sy);
+        for p in &geom[1..] {
+            let (x, y) = to_screen(*p, canvas_h, scale, offset);
+            ctx.line_to(x, y);
+        }
+    }
+    ctx.close_path();
 }
 
 fn draw_colored_circle(
@@ -363,20 +323,22 @@ fn encode_polyline_mm(pts: &[Pt]) -> String {
     polyline_codec::encode_coordinates(coords, 0).unwrap_or_default()
 }
 
-fn piece_color(i: usize) -> String {
-    // Fixed 8-color palette in the exact order: red, orange, yellow,
-    // green, cyan, blue, purple, pink. Colors are stable and cycle by index%8.
-    const PALETTE: [&str; 8] = [
-        "red",    // 红
-        "orange", // 橙
-        "yellow", // 黄
-        "green",  // 绿
-        "cyan",   // 青
-        "blue",   // 蓝
-        "purple", // 紫
-        "pink",   // 粉
-    ];
-    PALETTE[i % PALETTE.len()].to_string()
+// Per-puzzle session state after `state.data` was replaced by a new puzzle.
+fn puzzle_loaded(state: &mut State) {
+    assign_piece_colors(&mut state.data);
+    state.initial_data = state.data.clone();
+    state.history.clear();
+    state.selected.clear();
+    state.drags.clear();
+    state.z_order = (0..state.data.pieces.len()).collect();
+    state.lock_world.reset();
+    state.validation = None;
+    set_solution_overlay(state, None);
+    state.hint = None;
+    state.hints_used = 0;
+    state.searched = None;
+    state.searching = false;
+    state.loads += 1;
 }
 
 fn assign_piece_colors(p: &mut Puzzle) {
@@ -445,16 +407,86 @@ fn update_status_dom(state: &State) {
         } else {
             "速度：快"
         };
-        let txt = if state.lang == "zh" {
-            format!("{}  |  {}", lock_zh, speed_zh)
+        let (magnet_en, magnet_zh) = if state.magnet_mode {
+            ("Magnet: On", "磁吸：开")
         } else {
-            format!("{}  |  {}", lock_en, speed_en)
+            ("Magnet: Off", "磁吸：关")
         };
+        let mut txt = if state.lang == "zh" {
+            format!("{}  |  {}  |  {}", lock_zh, speed_zh, magnet_zh)
+        } else {
+            format!("{}  |  {}  |  {}", lock_en, speed_en, magnet_en)
+        };
+        if state.hints_used > 0 {
+            if state.lang == "zh" {
+                txt.push_str(&format!("  |  提示：{}", state.hints_used));
+            } else {
+                txt.push_str(&format!("  |  Hints: {}", state.hints_used));
+            }
+        }
         el.set_inner_text(&txt);
     }
 }
 
-fn update_validation_dom(state: &State) {
+// Enable or disable the toolbar undo/redo buttons.
+fn update_history_dom(state: &State) {
+    for (id, enabled) in [
+        ("undoBtn", state.history.can_undo()),
+        ("redoBtn", state.history.can_redo()),
+    ] {
+        if let Some(el) = state.document.get_element_by_id(id) {
+            if enabled {
+                let _ = el.remove_attribute("disabled");
+            } else {
+                let _ = el.set_attribute("disabled", "");
+            }
+        }
+    }
+}
+
+// Close every open drag/rotate gesture, recording them in the history.
+fn commit_gesture(state: &mut State) {
+    state.history.commit(&state.data.pieces);
+}
+
+// Close the gesture open on one piece (by `__label_idx`).
+fn commit_piece_gesture(state: &mut State, label: Option<usize>) {
+    state.history.commit_piece(label, &state.data.pieces);
+}
+
+// Undo (or redo) one command. Any drag or rotation in progress ends first.
+fn step_history(state: &mut State, redo: bool) -> bool {
+    state.drags.clear();
+    state.guides.clear();
+    state.rot_vel = 0.0;
+    let changed = if redo {
+        state.history.redo(&mut state.data.pieces)
+    } else {
+        state.history.undo(&mut state.data.pieces)
+    };
+    draw(state);
+    changed
+}
+
+// Validation of the current layout. Only recomputed once a command has
+// changed a piece since the last time, not on every frame of a gesture.
+fn validation(state: &mut State) -> &Validation {
+    let revision = state.history.revision();
+    if state
+        .validation
+        .as_ref()
+        .is_none_or(|v| v.revision != revision)
+    {
+        state.validation = Some(Validation {
+            revision,
+            report: puzzle_geom::validate::validate(&state.data),
+            solution: matching_solution(&state.data.pieces, &state.data.solutions),
+        });
+    }
+    state.validation.as_ref().unwrap()
+}
+
+fn update_validation_dom(state: &mut State) {
     let doc = &state.document;
     let el = match doc.get_element_by_id("validationContent") {
         Some(e) => match e.dyn_into::<HtmlElement>() {
@@ -467,207 +499,97 @@ fn update_validation_dom(state: &State) {
     // 始终执行校验（即使在锁定/临时锁定时）。
     // 约束模式由拖拽求解器负责“阻挡”，这里的校验仅负责展示状态，
     // 并在数值误差范围内给予容差。
-
-    // Gather board geometry
-    let board_geom = state.data.board.as_ref().and_then(board_to_geom);
-
-    // Gather piece geoms with label index, whether it's a circle, its radius (if circle), and its center.
-    // Circles use exact Ball(r) with translation for contact checks.
-    let mut geoms: Vec<(usize, Vec<Pt>, bool, f64, Pt)> = Vec::new();
-    for (i, p) in state.data.pieces.iter().enumerate() {
-        let label_idx = p.__label_idx.unwrap_or(i);
-        let is_circle = p.type_ == "circle";
-        let radius = if is_circle {
-            p.d.unwrap_or_else(|| p.r.unwrap_or(0.0) * 2.0) / 2.0
-        } else {
-            0.0
-        };
-        let (geom, ctr) = if let (Some(g), Some(c)) = (&p.__geom, p.__ctr) {
-            (g.clone(), c)
-        } else {
-            piece_geom(p)
-        };
-        geoms.push((label_idx, geom, is_circle, radius, ctr));
-    }
-
-    let mut errors_en: Vec<String> = Vec::new();
-    let mut errors_zh: Vec<String> = Vec::new();
-
-    // Tolerance in mm: allow touching or tiny overlaps
-    let eps_mm: f64 = 0.10;
-
-    // Helpers for Parry contact-based overlap with tolerance (handle concavity via earcut compound)
-    let make_shape = |poly: &Vec<Pt>, is_circle: bool, radius: f64| -> Option<SharedShape> {
-        if is_circle {
-            return Some(SharedShape::ball(radius as Real));
-        }
-        let tris = triangulate_polygon(poly);
-        if tris.is_empty() {
-            let hull = convex_hull(poly.clone());
-            let pts = to_na_points(&hull);
-            return SharedShape::convex_hull(&pts);
-        }
-        let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::with_capacity(tris.len());
-        for tri in tris {
-            let pts = vec![
-                Point2::new(tri[0].x as Real, tri[0].y as Real),
-                Point2::new(tri[1].x as Real, tri[1].y as Real),
-                Point2::new(tri[2].x as Real, tri[2].y as Real),
-            ];
-            if let Some(s) = SharedShape::convex_hull(&pts) {
-                parts.push((Isometry2::identity(), s));
-            }
-        }
-        if parts.is_empty() {
-            None
-        } else {
-            Some(SharedShape::compound(parts))
-        }
+    let Validation {
+        report, solution, ..
+    } = validation(state).clone();
+    let zh = state.lang == "zh";
+    // Violations index pieces; the UI shows their stable labels.
+    let label = |i: usize| {
+        state
+            .data
+            .pieces
+            .get(i)
+            .and_then(|p| p.__label_idx)
+            .unwrap_or(i)
+            + 1
     };
-    let deep_overlap =
-        |a: &Vec<Pt>, ac: bool, ra: f64, ca: Pt, b: &Vec<Pt>, bc: bool, rb: f64, cb: Pt| -> bool {
-            if let (Some(sa), Some(sb)) = (make_shape(a, ac, ra), make_shape(b, bc, rb)) {
-                let ia = if ac {
-                    Isometry2::new(vector![ca.x as Real, ca.y as Real], 0.0)
-                } else {
-                    Isometry2::identity()
-                };
-                let ib = if bc {
-                    Isometry2::new(vector![cb.x as Real, cb.y as Real], 0.0)
-                } else {
-                    Isometry2::identity()
-                };
-                // If contact exists and distance < -eps => significant penetration
-                if let Ok(Some(ct)) =
-                    parry2d::query::contact(&ia, sa.as_ref(), &ib, sb.as_ref(), eps_mm as Real)
-                {
-                    return (ct.dist as f64) < -eps_mm;
-                }
+    let mut errors: Vec<String> = Vec::new();
+    for v in &report.violations {
+        errors.push(match *v {
+            Violation::Overlap { a, b, .. } if zh => {
+                format!("拼图 {} 与拼图 {} 重叠", label(a), label(b))
             }
-            false
-        };
-
-    // 1) Piece-piece overlaps (with tolerance)
-    for a in 0..geoms.len() {
-        for b in (a + 1)..geoms.len() {
-            let overlap = deep_overlap(
-                &geoms[a].1,
-                geoms[a].2,
-                geoms[a].3,
-                geoms[a].4,
-                &geoms[b].1,
-                geoms[b].2,
-                geoms[b].3,
-                geoms[b].4,
-            );
-            // Fallback: polygon intersection test using current polylines
-            let poly_cross = !overlap && polygons_intersect(&geoms[a].1, &geoms[b].1);
-            if overlap || poly_cross {
-                let la = geoms[a].0 + 1;
-                let lb = geoms[b].0 + 1;
-                errors_en.push(format!("Piece {} overlaps piece {}", la, lb));
-                errors_zh.push(format!("拼图 {} 与拼图 {} 重叠", la, lb));
+            Violation::Overlap { a, b, .. } => {
+                format!("Piece {} overlaps piece {}", label(a), label(b))
             }
-        }
-    }
-
-    if let Some(bg) = &board_geom {
-        // helpers (containment check kept; distances via Parry)
-        let fully_inside = |poly: &Vec<Pt>| -> bool {
-            poly.iter()
-                .all(|p| bg.iter().any(|g| poly_contains_point(g, *p)))
-        };
-        // Compute min distance from piece to board edges using Parry contacts
-        let min_dist_to_board = |poly: &Vec<Pt>, is_circle: bool, radius: f64, ctr: Pt| -> f64 {
-            let mut best = f64::INFINITY;
-            if let Some(sp) = make_shape(poly, is_circle, radius) {
-                // Circle is defined at origin and needs translation; polygons are in world space.
-                let iso_shape = if is_circle {
-                    Isometry2::new(vector![ctr.x as Real, ctr.y as Real], 0.0)
-                } else {
-                    Isometry2::identity()
-                };
-                // Board segments live in world space; do NOT translate them.
-                let iso_seg = Isometry2::identity();
-                for g in bg {
-                    let n = g.len();
-                    for j in 0..n {
-                        let a = g[j];
-                        let b = g[(j + 1) % n];
-                        let seg = SharedShape::segment(
-                            Point2::new(a.x as Real, a.y as Real),
-                            Point2::new(b.x as Real, b.y as Real),
-                        );
-                        if let Ok(Some(ct)) = parry2d::query::contact(
-                            &iso_shape,
-                            sp.as_ref(),
-                            &iso_seg,
-                            seg.as_ref(),
-                            1.0e3,
-                        ) {
-                            best = best.min(ct.dist as f64);
-                        }
-                    }
-                }
+            Violation::OutsideBorder { piece, .. } if zh => {
+                format!("拼图 {} 在边框外部", label(piece))
             }
-            best
-        };
-
-        // Also compute outer geometry for three-layer logic
-        let outer = state
-            .data
-            .board
-            .as_ref()
-            .and_then(|b| board_outer_geom(b, RING_WIDTH_MM));
-
-        for (label_idx, pg, is_circle, radius, ctr) in &geoms {
-            let num = label_idx + 1;
-            // Rapier-based: use minimal Parry distance to inner border segments
-            // d < -eps_mm => inner border penetration (i.e., into middle ring)
-            let d = min_dist_to_board(pg, *is_circle, *radius, *ctr);
-            // Outer containment: if not fully inside outer, it's in the outer layer -> error
-            let outside_outer = if let Some(out) = &outer {
-                !pg.iter()
-                    .all(|p| out.iter().any(|poly| poly_contains_point(poly, *p)))
-            } else {
-                false
-            };
-            if outside_outer || (d.is_finite() && d < -eps_mm) {
-                errors_en.push(format!("Piece {} is outside the border", num));
-                errors_zh.push(format!("拼图 {} 在边框外部", num));
-            } else if !fully_inside(pg) {
-                // Inside outer but not fully inside inner => touching the ring (beyond tolerance)
-                if !(d.is_finite() && d <= eps_mm) {
-                    errors_en.push(format!("Piece {} is outside the border", num));
-                    errors_zh.push(format!("拼图 {} 在边框外部", num));
-                }
+            Violation::OutsideBorder { piece, .. } => {
+                format!("Piece {} is outside the border", label(piece))
             }
-        }
+            Violation::OnObstacle { piece, .. } if zh => {
+                format!("拼图 {} 压在障碍物上", label(piece))
+            }
+            Violation::OnObstacle { piece, .. } => {
+                format!("Piece {} covers an obstacle", label(piece))
+            }
+            Violation::Unplaced { piece } if zh => {
+                format!("拼图 {} 还在托盘中", label(piece))
+            }
+            Violation::Unplaced { piece } => {
+                format!("Piece {} is still in the tray", label(piece))
+            }
+            Violation::Uncovered { area_mm2 } if zh => {
+                format!("底板还有 {:.0} mm² 没有铺满", area_mm2)
+            }
+            Violation::Uncovered { area_mm2 } => {
+                format!("{:.0} mm² of the board is still uncovered", area_mm2)
+            }
+            Violation::Silhouette {
+                missing_mm2,
+                excess_mm2,
+            } if zh => format!(
+                "拼出的形状与剪影不符：缺少 {:.0} mm²，多出 {:.0} mm²",
+                missing_mm2, excess_mm2
+            ),
+            Violation::Silhouette {
+                missing_mm2,
+                excess_mm2,
+            } => format!(
+                "Shape does not match the silhouette: {:.0} mm² missing, {:.0} mm² extra",
+                missing_mm2, excess_mm2
+            ),
+        });
     }
 
-    if state.lang == "zh" {
-        if errors_zh.is_empty() {
-            el.set_inner_html("<div style=\"opacity:.7\">成功</div>");
-        } else {
-            let mut html = String::new();
-            html.push_str("<ul style=\"margin:0;padding-left:18px\">");
-            for e in errors_zh {
-                html.push_str(&format!("<li>{}</li>", e));
-            }
-            html.push_str("</ul>");
-            el.set_inner_html(&html);
+    let mut html = String::new();
+    if errors.is_empty() {
+        let ok = if zh { "成功" } else { "Success" };
+        html.push_str(&format!("<div style=\"opacity:.7\">{}</div>", ok));
+        if let Some(i) = solution {
+            let known = if zh {
+                format!("与已知解答 {} 一致", i + 1)
+            } else {
+                format!("Matches known solution {}", i + 1)
+            };
+            html.push_str(&format!("<div style=\"opacity:.7\">{}</div>", known));
         }
-    } else if errors_en.is_empty() {
-        el.set_inner_html("<div style=\"opacity:.7\">Success</div>");
     } else {
-        let mut html = String::new();
         html.push_str("<ul style=\"margin:0;padding-left:18px\">");
-        for e in errors_en {
+        for e in errors {
             html.push_str(&format!("<li>{}</li>", e));
         }
         html.push_str("</ul>");
-        el.set_inner_html(&html);
     }
+    if state.data.board.is_some() {
+        let cov = if zh { "覆盖率" } else { "Coverage" };
+        html.push_str(&format!(
+            "<div style=\"opacity:.7\">{}: {:.1}%</div>",
+            cov, report.coverage_pct
+        ));
+    }
+    el.set_inner_html(&html);
 }
 
 fn event_canvas_coords(e: &MouseEvent, cv: &HtmlCanvasElement) -> (f64, f64) {
@@ -720,260 +642,9 @@ fn draw_colored_polygon(
 
 // ---- Rapier/Parry helpers for smooth collision-aware translation ----
 
-fn convex_hull(mut pts: Vec<Pt>) -> Vec<Pt> {
-    // Monotone chain in f64
-    if pts.len() <= 3 {
-        return pts;
-    }
-    pts.sort_by(|a, b| {
-        a.x.partial_cmp(&b.x)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
-    });
-    let cross = |o: &Pt, a: &Pt, b: &Pt| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
-    let mut lower: Vec<Pt> = Vec::new();
-    for p in &pts {
-        while lower.len() >= 2 && cross(&lower[lower.len() - 2], &lower[lower.len() - 1], p) <= 0.0
-        {
-            lower.pop();
-        }
-        lower.push(*p);
-    }
-    let mut upper: Vec<Pt> = Vec::new();
-    for p in pts.iter().rev() {
-        while upper.len() >= 2 && cross(&upper[upper.len() - 2], &upper[upper.len() - 1], p) <= 0.0
-        {
-            upper.pop();
-        }
-        upper.push(*p);
-    }
-    lower.pop();
-    upper.pop();
-    lower.extend(upper);
-    lower
-}
-
-fn to_na_points(points: &[Pt]) -> Vec<Point2<Real>> {
-    points
-        .iter()
-        .map(|p| Point2::new(p.x as Real, p.y as Real))
-        .collect()
-}
-
-fn triangulate_polygon(points: &[Pt]) -> Vec<[Pt; 3]> {
-    if points.len() < 3 {
-        return Vec::new();
-    }
-    // Flattened [x0, y0, x1, y1, ...]
-    let mut flat: Vec<f64> = Vec::with_capacity(points.len() * 2);
-    for p in points {
-        flat.push(p.x);
-        flat.push(p.y);
-    }
-    let idx = earcut(&flat, &[], 2).unwrap_or_default();
-    let mut tris = Vec::with_capacity(idx.len() / 3);
-    for t in (0..idx.len()).step_by(3) {
-        let a = idx[t] as usize;
-        let b = idx[t + 1] as usize;
-        let c = idx[t + 2] as usize;
-        if a < points.len() && b < points.len() && c < points.len() {
-            tris.push([points[a], points[b], points[c]]);
-        }
-    }
-    tris
-}
-
-// Use Rapier physics pipeline (CCD + solver) to compute allowed delta under edge-edge collisions.
-fn locked_slide_delta_rapier(state: &State, moving_idx: usize, dx: f64, dy: f64) -> (f64, f64) {
-    use rapier2d::prelude::BroadPhaseBvh;
-    use rapier2d::prelude::*;
-
-    // Initialize a tiny world with zero gravity
-    let mut pipeline = PhysicsPipeline::new();
-    let gravity = vector![0.0, 0.0];
-    let mut islands = IslandManager::new();
-    let mut broad_phase = BroadPhaseBvh::new();
-    let mut narrow_phase = NarrowPhase::new();
-    let mut bodies = RigidBodySet::new();
-    let mut colliders = ColliderSet::new();
-    let mut impulse_joints = ImpulseJointSet::new();
-    let mut multibody_joints = MultibodyJointSet::new();
-    let mut ccd_solver = CCDSolver::new();
-    let params = IntegrationParameters {
-        dt: 1.0,
-        ..Default::default()
-    }; // one step, velocity encodes full displacement
-
-    // Obstacles: other pieces as solid shapes (circles use balls; others convex hulls);
-    // board as an inner/outer polyline barrier. Attach most to a shared ground body; create
-    // per-piece fixed bodies where we need a translated collider (e.g., balls).
-    let ground = bodies.insert(RigidBodyBuilder::fixed().build());
-    if let Some(b) = &state.data.board {
-        if let Some(inner_geoms) = board_to_geom(b) {
-            let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::new();
-            for inner in inner_geoms {
-                build_capsule_obstacles(&inner, &mut parts);
-            }
-            if !parts.is_empty() {
-                let col = ColliderBuilder::compound(parts)
-                    .friction(0.0)
-                    .restitution(0.0)
-                    .build();
-                colliders.insert_with_parent(col, ground, &mut bodies);
-            }
-        }
-        if let Some(outer_geoms) = board_outer_geom(b, RING_WIDTH_MM) {
-            let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::new();
-            for outer in outer_geoms {
-                build_capsule_obstacles(&outer, &mut parts);
-            }
-            if !parts.is_empty() {
-                let col = ColliderBuilder::compound(parts)
-                    .friction(0.0)
-                    .restitution(0.0)
-                    .build();
-                colliders.insert_with_parent(col, ground, &mut bodies);
-            }
-        }
-    }
-    for (j, pc) in state.data.pieces.iter().enumerate() {
-        if j == moving_idx {
-            continue;
-        }
-        if pc.type_ == "circle" {
-            let (_geom, ctr) = piece_geom(pc);
-            let body = bodies.insert(
-                RigidBodyBuilder::fixed()
-                    .translation(vector![ctr.x as Real, ctr.y as Real])
-                    .build(),
-            );
-            let col = ColliderBuilder::ball(CIRCLE_R_MM as Real)
-                .friction(0.0)
-                .restitution(0.0)
-                .build();
-            colliders.insert_with_parent(col, body, &mut bodies);
-        } else {
-            let (og, _c) = piece_geom(pc);
-            let hull = convex_hull(og);
-            let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::new();
-            build_capsule_obstacles(&hull, &mut parts);
-            if !parts.is_empty() {
-                let col = ColliderBuilder::compound(parts)
-                    .friction(0.0)
-                    .restitution(0.0)
-                    .build();
-                colliders.insert_with_parent(col, ground, &mut bodies);
-            }
-        }
-    }
-
-    // Moving piece as a dynamic body with local convex hull and CCD
-    let p = &state.data.pieces[moving_idx];
-    let (geom, ctr) = piece_geom(p);
-    if geom.is_empty() {
-        return (0.0, 0.0);
-    }
-    let start = vector![ctr.x as Real, ctr.y as Real];
-    let dyn_h = bodies.insert(
-        RigidBodyBuilder::dynamic()
-            .translation(start)
-            .lock_rotations()
-            .ccd_enabled(true)
-            .build(),
-    );
-    if p.type_ == "circle" {
-        let col = ColliderBuilder::ball(CIRCLE_R_MM as Real)
-            .friction(0.0)
-            .restitution(0.0)
-            .build();
-        colliders.insert_with_parent(col, dyn_h, &mut bodies);
-    } else {
-        let hull = convex_hull(geom);
-        if let Some(shape) = build_capsule_compound_local(&hull, ctr) {
-            let col = ColliderBuilder::new(shape)
-                .friction(0.0)
-                .restitution(0.0)
-                .build();
-            colliders.insert_with_parent(col, dyn_h, &mut bodies);
-        } else {
-            let local: Vec<Point2<Real>> = hull
-                .iter()
-                .map(|p| Point2::new((p.x - ctr.x) as Real, (p.y - ctr.y) as Real))
-                .collect();
-            if let Some(builder) = ColliderBuilder::convex_hull(&local) {
-                let col = builder.friction(0.0).restitution(0.0).build();
-                colliders.insert_with_parent(col, dyn_h, &mut bodies);
-            }
-        }
-    }
-    if let Some(rb) = bodies.get_mut(dyn_h) {
-        rb.set_linvel(vector![dx as Real, dy as Real], true);
-        rb.set_angvel(0.0, true);
-    }
-
-    // Run one step with CCD. No hooks / events.
-    pipeline.step(
-        &gravity,
-        &params,
-        &mut islands,
-        &mut broad_phase,
-        &mut narrow_phase,
-        &mut bodies,
-        &mut colliders,
-        &mut impulse_joints,
-        &mut multibody_joints,
-        &mut ccd_solver,
-        &(),
-        &(),
-    );
-
-    if let Some(rb) = bodies.get(dyn_h) {
-        let end = rb.translation();
-        return ((end.x - start.x) as f64, (end.y - start.y) as f64);
-    }
-    (0.0, 0.0)
-}
-
-fn build_capsule_obstacles(points: &[Pt], out: &mut Vec<(Isometry2<Real>, SharedShape)>) {
-    if points.len() < 2 {
-        return;
-    }
-    let r: Real = EDGE_RADIUS_MM as Real;
-    let n = points.len();
-    for i in 0..n {
-        let a = points[i];
-        let b = points[(i + 1) % n];
-        let cap = SharedShape::capsule(
-            Point2::new(a.x as Real, a.y as Real),
-            Point2::new(b.x as Real, b.y as Real),
-            r,
-        );
-        out.push((Isometry2::identity(), cap));
-    }
-}
-
-fn build_capsule_compound_local(points: &[Pt], ctr: Pt) -> Option<SharedShape> {
-    if points.len() < 2 {
-        return None;
-    }
-    let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::new();
-    let r: Real = EDGE_RADIUS_MM as Real;
-    let n = points.len();
-    for i in 0..n {
-        let a = points[i];
-        let b = points[(i + 1) % n];
-        let cap = SharedShape::capsule(
-            Point2::new((a.x - ctr.x) as Real, (a.y - ctr.y) as Real),
-            Point2::new((b.x - ctr.x) as Real, (b.y - ctr.y) as Real),
-            r,
-        );
-        parts.push((Isometry2::identity(), cap));
-    }
-    Some(SharedShape::compound(parts))
-}
-
+// Allowed delta under collisions, solved in the persistent Rapier world (see `physics`).
 fn rapier_allowed_delta(
-    state: &State,
+    state: &mut State,
     moving_idx: usize,
     dx: f64,
     dy: f64,
@@ -986,180 +657,22 @@ fn rapier_allowed_delta(
     if !enforce {
         return (dx, dy);
     }
-    locked_slide_delta_rapier(state, moving_idx, dx, dy)
-}
-
-fn normalize(p: Pt) -> Pt {
-    let len = (p.x * p.x + p.y * p.y).sqrt();
-    if len == 0.0 {
-        Pt { x: 0.0, y: 0.0 }
-    } else {
-        Pt {
-            x: p.x / len,
-            y: p.y / len,
-        }
-    }
-}
-
-fn poly_to_points(poly: &[PolygonPoint]) -> Vec<Pt> {
-    let mut out: Vec<Pt> = Vec::new();
-    let n = poly.len();
-    let mut i = 0;
-    while i < n {
-        match &poly[i] {
-            PolygonPoint::Point([x, y]) => {
-                out.push(Pt { x: *x, y: *y });
-                i += 1;
-            }
-            PolygonPoint::Rounded([x, y, r]) => {
-                if out.is_empty() || i + 1 >= n {
-                    i += 1;
-                    continue;
-                }
-                let prev = *out.last().unwrap();
-                let next_xy = match &poly[i + 1] {
-                    PolygonPoint::Point([nx, ny]) => Pt { x: *nx, y: *ny },
-                    PolygonPoint::Rounded([nx, ny, _]) => Pt { x: *nx, y: *ny },
-                };
-                let corner = Pt { x: *x, y: *y };
-                let radius = *r;
-                let v1 = normalize(Pt {
-                    x: prev.x - corner.x,
-                    y: prev.y - corner.y,
-                });
-                let v2 = normalize(Pt {
-                    x: next_xy.x - corner.x,
-                    y: next_xy.y - corner.y,
-                });
-                let start = Pt {
-                    x: corner.x + v1.x * radius,
-                    y: corner.y + v1.y * radius,
-                };
-                let end = Pt {
-                    x: corner.x + v2.x * radius,
-                    y: corner.y + v2.y * radius,
-                };
-                out.push(start);
-                let center = Pt {
-                    x: corner.x + (v1.x + v2.x) * radius,
-                    y: corner.y + (v1.y + v2.y) * radius,
-                };
-                let start_ang = (start.y - center.y).atan2(start.x - center.x);
-                let end_ang = (end.y - center.y).atan2(end.x - center.x);
-                let steps = 24;
-                for j in 1..=steps {
-                    let t = j as f64 / steps as f64;
-                    let ang = start_ang + (end_ang - start_ang) * t;
-                    out.push(Pt {
-                        x: center.x + radius * ang.cos(),
-                        y: center.y + radius * ang.sin(),
-                    });
-                }
-                i += 1;
-            }
-        }
-    }
-    out
-}
-
-fn board_to_geom(board: &Board) -> Option<Vec<Vec<Pt>>> {
-    match board.type_.as_deref() {
-        Some("rect") => {
-            let w = board.w.unwrap_or(0.0);
-            let h = board.h.unwrap_or(0.0);
-            Some(vec![vec![
-                Pt { x: 0.0, y: 0.0 },
-                Pt { x: w, y: 0.0 },
-                Pt { x: w, y: h },
-                Pt { x: 0.0, y: h },
-            ]])
-        }
-        Some("polygon") => {
-            if let Some(polys) = &board.polygons {
-                let geoms = polys
-                    .iter()
-                    .map(|poly| poly_to_points(poly))
-                    .collect::<Vec<_>>();
-                if geoms.is_empty() { None } else { Some(geoms) }
-            } else {
-                None
-            }
-        }
-        _ => None,
-    }
-}
-
-fn board_outer_geom(board: &Board, ring: f64) -> Option<Vec<Vec<Pt>>> {
-    match board.type_.as_deref() {
-        Some("rect") => {
-            let w = board.w.unwrap_or(0.0);
-            let h = board.h.unwrap_or(0.0);
-            Some(vec![vec![
-                Pt { x: -ring, y: -ring },
-                Pt {
-                    x: w + ring,
-                    y: -ring,
-                },
-                Pt {
-                    x: w + ring,
-                    y: h + ring,
-                },
-                Pt {
-                    x: -ring,
-                    y: h + ring,
-                },
-            ]])
-        }
-        Some("polygon") => {
-            if let Some(polys) = &board.polygons {
-                let mut outs: Vec<Vec<Pt>> = Vec::new();
-                for poly in polys {
-                    let inner = poly_to_points(poly);
-                    if inner.len() >= 3 {
-                        outs.push(polygon_offset_rounded(&inner, ring, 8));
-                    }
-                }
-                if outs.is_empty() { None } else { Some(outs) }
-            } else {
-                None
-            }
-        }
-        _ => None,
-    }
-}
-
-fn polygon_offset_rounded(inner: &[Pt], r: f64, _arc_samples: usize) -> Vec<Pt> {
-    use geo::algorithm::buffer::Buffer;
-    use geo::{Coord, LineString, Polygon};
-
-    let r = r.max(0.0);
-    if inner.len() < 3 || r <= 0.0 {
-        return inner.to_vec();
-    }
-
-    let coords: Vec<Coord> = inner.iter().map(|p| Coord { x: p.x, y: p.y }).collect();
-    let poly = Polygon::new(LineString::from(coords), vec![]);
-    let mp = poly.buffer(r);
-    let poly = mp.0.into_iter().next().unwrap();
-    let mut out: Vec<Pt> = poly
-        .exterior()
-        .0
-        .iter()
-        .map(|c| Pt { x: c.x, y: c.y })
-        .collect();
-    if out.len() > 1 {
-        let first = out.first().unwrap();
-        let last = out.last().unwrap();
-        if (first.x - last.x).abs() < 1e-9 && (first.y - last.y).abs() < 1e-9 {
-            out.pop();
-        }
-    }
-    out
+    state
+        .lock_world
+        .slide(&state.data.pieces, state.data.frame(), moving_idx, dx, dy)
 }
 
 fn draw_board(state: &mut State) {
     if let Some(b) = &state.data.board {
         let h = state.canvas.height() as f64;
+        let obstacles = board_obstacles_geom(b);
+        if state.data.goal == Goal::Silhouette {
+            if let Some(mut geoms) = board_to_geom(b) {
+                geoms.extend(obstacles);
+                draw_silhouette(&state.ctx, h, &geoms, state.scale, state.offset);
+            }
+            return;
+        }
         if let Some(inner_geoms) = board_to_geom(b) {
             let outer_geoms =
                 board_outer_geom(b, RING_WIDTH_MM).unwrap_or_else(|| inner_geoms.clone());
@@ -1183,10 +696,97 @@ fn draw_board(state: &mut State) {
                     "#ffffff",
                 );
             }
-            state.ctx.set_line_width(1.6);
-            set_stroke_style(&state.ctx, "#222");
+            // Obstacles in the frame color, like holes down to the base
+            for g in &obstacles {
+                draw_colored_polygon(
+                    &state.ctx,
+                    h,
+                    g,
+                    false,
+                    state.scale,
+                    state.offset,
+                    "#6f4e37",
+                );
+            }
+            state.ctx.set_line_width(1.6);
+            set_stroke_style(&state.ctx, "#222");
+        }
+    }
+}
+
+// Silhouette target: all regions filled as one shape, without outlines, so
+// the seams between regions do not give the solution away. Filled even-odd,
+// so obstacle outlines inside a region leave holes.
+fn draw_silhouette(
+    ctx: &CanvasRenderingContext2d,
+    canvas_h: f64,
+    geoms: &[Vec<Pt>],
+    scale: f64,
+    offset: (f64, f64),
+) {
+    ctx.begin_path();
+    for g in geoms {
+        let Some(first) = g.first() else {
+            continue;
+        };
+        let (sx, sy) = to_screen(*first, canvas_h, scale, offset);
+        ctx.move_to(sx, sy);
+        for p in &g[1..] {
+            let (x, y) = to_screen(*p, canvas_h, scale, offset);
+            ctx.line_to(x, y);
+        }
+        ctx.close_path();
+    }
+    set_fill_style(ctx, SILHOUETTE_COLOR);
+    ctx.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+}
+
+// Staging tray: a dashed area beside the board reaching down to the lowest
+// piece resting in it.
+fn draw_tray(state: &State) {
+    let Some(b) = &state.data.board else {
+        return;
+    };
+    let tray = tray_for(Some(b), &state.data.pieces);
+    let pad = TRAY_SPACING_MM;
+    let mut bottom = board_to_geom(b)
+        .map(|g| bounds_of_points(&g.concat()).1)
+        .unwrap_or(tray.top);
+    for p in &state.data.pieces {
+        if let Some((g, _)) = p.cached_geom() {
+            let bb = bounds_of_points(g);
+            if tray.contains(bb) {
+                bottom = bottom.min(bb.1);
+            }
         }
     }
+    let h = state.canvas.height() as f64;
+    let (x0, y0) = to_screen(
+        Pt {
+            x: tray.x - pad,
+            y: tray.top + pad,
+        },
+        h,
+        state.scale,
+        state.offset,
+    );
+    let (x1, y1) = to_screen(
+        Pt {
+            x: tray.x + tray.width + pad,
+            y: bottom - pad,
+        },
+        h,
+        state.scale,
+        state.offset,
+    );
+    let ctx = &state.ctx;
+    set_fill_style(ctx, TRAY_COLOR);
+    ctx.fill_rect(x0, y0, x1 - x0, y1 - y0);
+    let _ = ctx.set_line_dash(&Array::of2(&6.0.into(), &4.0.into()));
+    ctx.set_line_width(1.0);
+    set_stroke_style(ctx, "#999");
+    ctx.stroke_rect(x0, y0, x1 - x0, y1 - y0);
+    let _ = ctx.set_line_dash(&Array::new());
 }
 
 fn point_in_polygon(
@@ -1217,65 +817,6 @@ fn point_in_polygon(
     inside
 }
 
-fn poly_contains_point(poly: &[Pt], p: Pt) -> bool {
-    let (x, y) = (p.x, p.y);
-    let mut inside = false;
-    let n = poly.len();
-    let mut j = n - 1;
-    for i in 0..n {
-        let xi = poly[i].x;
-        let yi = poly[i].y;
-        let xj = poly[j].x;
-        let yj = poly[j].y;
-        let intersect =
-            ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi + 1e-12) + xi);
-        if intersect {
-            inside = !inside;
-        }
-        j = i;
-    }
-    inside
-}
-
-fn segments_intersect(a1: Pt, a2: Pt, b1: Pt, b2: Pt) -> bool {
-    fn cross(a: Pt, b: Pt, c: Pt) -> f64 {
-        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
-    }
-    let d1 = cross(a1, a2, b1);
-    let d2 = cross(a1, a2, b2);
-    let d3 = cross(b1, b2, a1);
-    let d4 = cross(b1, b2, a2);
-    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
-        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
-    {
-        return true;
-    }
-    false
-}
-
-fn polygons_intersect(a: &[Pt], b: &[Pt]) -> bool {
-    if a.is_empty() || b.is_empty() {
-        return false;
-    }
-    let an = a.len();
-    let bn = b.len();
-    for i in 0..an {
-        let a1 = a[i];
-        let a2 = a[(i + 1) % an];
-        for j in 0..bn {
-            let b1 = b[j];
-            let b2 = b[(j + 1) % bn];
-            if segments_intersect(a1, a2, b1, b2) {
-                return true;
-            }
-        }
-    }
-    if poly_contains_point(a, b[0]) || poly_contains_point(b, a[0]) {
-        return true;
-    }
-    false
-}
-
 // color helper integrated from former puzzle-core
 
 // (removed unused SVG helpers that triggered dead-code lints)
@@ -1307,8 +848,16 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
         let st = state.clone();
         let onclick = Closure::<dyn FnMut()>::wrap(Box::new(move || {
             let mut s = st.borrow_mut();
+            commit_gesture(&mut s);
+            if !same_layout(&s.data.pieces, &s.initial_data.pieces) {
+                let cmd = Command::Replace {
+                    before: s.data.pieces.clone(),
+                    after: s.initial_data.pieces.clone(),
+                };
+                s.history.push(cmd);
+            }
             s.data = s.initial_data.clone();
-            s.dragging_idx = None;
+            s.drags.clear();
             s.rot_vel = 0.0;
             s.slow_mode = false;
             s.restrict_mode = false;
@@ -1322,6 +871,40 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
         onclick.forget();
     }
 
+    // Show solution: cycle the overlay through the known solutions, or
+    // solve (deterministic search over the current pieces, in the
+    // background) if there are none
+    if let Some(btn) = doc.get_element_by_id("showSolution") {
+        let btn: HtmlElement = btn.dyn_into().unwrap();
+        let st = state.clone();
+        let onclick = Closure::<dyn FnMut()>::wrap(Box::new(move || {
+            let mut s = st.borrow_mut();
+            let known = s.data.solutions.len();
+            if known > 0 {
+                let next = match s.solution_overlay {
+                    None => Some(0),
+                    Some(i) if i + 1 < known => Some(i + 1),
+                    Some(_) => None,
+                };
+                set_solution_overlay(&mut s, next);
+                draw(&mut s);
+                return;
+            }
+            drop(s);
+            let st = st.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(Err(e)) = show_solution(&st).await {
+                    let s = st.borrow();
+                    let _ = s
+                        .window
+                        .alert_with_message(search::failure_message(e, &s.lang));
+                }
+            });
+        }));
+        btn.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+        onclick.forget();
+    }
+
     // Export PNG (blueprint; deterministic)
     if let Some(btn) = doc.get_element_by_id("exportPng") {
         let btn: HtmlElement = btn.dyn_into().unwrap();
@@ -1350,7 +933,7 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
                 };
                 update_note_dom(&s);
                 update_status_dom(&s);
-                update_validation_dom(&s);
+                update_validation_dom(&mut s);
             }
         }));
         sel.set_onchange(Some(onchange.as_ref().unchecked_ref()));
@@ -1505,6 +1088,81 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
         }
     }
 
+    // Rotation snapping, persisted next to the rot_speed_* keys
+    {
+        let get_item = |k: &str| {
+            storage_rc
+                .as_ref()
+                .as_ref()
+                .and_then(|s| s.get_item(k).ok().flatten())
+        };
+        {
+            let mut s = state.borrow_mut();
+            if let Some(v) = get_item("rot_snap_mode") {
+                s.snap.mode = SnapMode::parse(&v);
+            }
+            if let Some(v) = get_item("rot_snap_deg").and_then(|v| v.parse::<i32>().ok()) {
+                s.snap.step_deg = v.clamp(1, 90) as f64;
+            }
+            if let Some(v) = get_item("rot_step") {
+                s.snap.tap_step = v == "1";
+            }
+        }
+        if let Some(sel) = doc.get_element_by_id("snapMode")
+            && let Ok(sel) = sel.dyn_into::<web_sys::HtmlSelectElement>()
+        {
+            sel.set_value(state.borrow().snap.mode.as_str());
+            let st = state.clone();
+            let storage = storage_rc.clone();
+            let sel_read = sel.clone();
+            let onchange = Closure::<dyn FnMut()>::wrap(Box::new(move || {
+                let mode = SnapMode::parse(&sel_read.value());
+                st.borrow_mut().snap.mode = mode;
+                if let Some(store) = storage.as_ref().as_ref() {
+                    let _ = store.set_item("rot_snap_mode", mode.as_str());
+                }
+            }));
+            sel.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+            onchange.forget();
+        }
+        if let Some(nb) = doc.get_element_by_id("snapDeg")
+            && let Ok(nb) = nb.dyn_into::<web_sys::HtmlInputElement>()
+        {
+            nb.set_value(&(state.borrow().snap.step_deg as i32).to_string());
+            let st = state.clone();
+            let storage = storage_rc.clone();
+            let nb_read = nb.clone();
+            let oninput = Closure::<dyn FnMut()>::wrap(Box::new(move || {
+                if let Ok(v) = nb_read.value().parse::<i32>() {
+                    let v = v.clamp(1, 90);
+                    st.borrow_mut().snap.step_deg = v as f64;
+                    if let Some(store) = storage.as_ref().as_ref() {
+                        let _ = store.set_item("rot_snap_deg", &v.to_string());
+                    }
+                }
+            }));
+            nb.set_oninput(Some(oninput.as_ref().unchecked_ref()));
+            oninput.forget();
+        }
+        if let Some(cb) = doc.get_element_by_id("tapStep")
+            && let Ok(cb) = cb.dyn_into::<web_sys::HtmlInputElement>()
+        {
+            cb.set_checked(state.borrow().snap.tap_step);
+            let st = state.clone();
+            let storage = storage_rc.clone();
+            let cb_read = cb.clone();
+            let onchange = Closure::<dyn FnMut()>::wrap(Box::new(move || {
+                let on = cb_read.checked();
+                st.borrow_mut().snap.tap_step = on;
+                if let Some(store) = storage.as_ref().as_ref() {
+                    let _ = store.set_item("rot_step", if on { "1" } else { "0" });
+                }
+            }));
+            cb.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+            onchange.forget();
+        }
+    }
+
     // Save JSON
     if let Some(btn) = doc.get_element_by_id("saveJson") {
         let btn: HtmlElement = btn.dyn_into().unwrap();
@@ -1517,88 +1175,8 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
         onclick.forget();
     }
 
-    // Mouse events
-    {
-        let st = state.clone();
-        let mousedown = Closure::<dyn FnMut(MouseEvent)>::wrap(Box::new(move |e: MouseEvent| {
-            let mut s = st.borrow_mut();
-            let pt = event_canvas_coords(&e, &s.canvas);
-            let h = s.canvas.height() as f64;
-            // find topmost piece under cursor
-            for i in (0..s.data.pieces.len()).rev() {
-                if let Some(ref geom) = s.data.pieces[i].__geom
-                    && point_in_polygon(pt, geom, h, s.scale, s.offset)
-                {
-                    s.dragging_idx = Some(i);
-                    let ctr = s.data.pieces[i].__ctr.unwrap_or(Pt { x: 0.0, y: 0.0 });
-                    let (sx, sy) = to_screen(ctr, h, s.scale, s.offset);
-                    s.drag_off = (pt.0 - sx, pt.1 - sy);
-                    // bring to top
-                    let it = s.data.pieces.remove(i);
-                    s.data.pieces.push(it);
-                    s.dragging_idx = Some(s.data.pieces.len() - 1);
-                    break;
-                }
-            }
-        }));
-        state
-            .borrow()
-            .canvas
-            .add_event_listener_with_callback("mousedown", mousedown.as_ref().unchecked_ref())?;
-        mousedown.forget();
-    }
-    {
-        let st = state.clone();
-        let mousemove = Closure::<dyn FnMut(MouseEvent)>::wrap(Box::new(move |e: MouseEvent| {
-            let mut s = st.borrow_mut();
-            if let Some(idx) = s.dragging_idx {
-                let h = s.canvas.height() as f64;
-                let raw = event_canvas_coords(&e, &s.canvas);
-                let pt = (raw.0 - s.drag_off.0, raw.1 - s.drag_off.1);
-                let gp = from_screen(pt.0, pt.1, h, s.scale, s.offset);
-                // move by center using Rapier sweep-and-slide
-                if let Some(ctr) = s.data.pieces[idx].__ctr {
-                    let want_dx = gp.x - ctr.x;
-                    let want_dy = gp.y - ctr.y;
-                    let constraints_active = s.restrict_mode || s.shift_down;
-                    let (dx, dy) =
-                        rapier_allowed_delta(&s, idx, want_dx, want_dy, constraints_active);
-                    let p = &mut s.data.pieces[idx];
-                    if let Some(mut at) = p.at {
-                        at[0] += dx;
-                        at[1] += dy;
-                        p.at = Some(at);
-                    } else if p.points.is_some() {
-                        let pts = p.points.clone().unwrap();
-                        let moved = pts
-                            .into_iter()
-                            .map(|v| [v[0] + dx, v[1] + dy])
-                            .collect::<Vec<_>>();
-                        p.points = Some(moved);
-                    } else {
-                        p.at = Some([dx, dy]);
-                    }
-                }
-                draw(&mut s);
-            }
-        }));
-        state
-            .borrow()
-            .canvas
-            .add_event_listener_with_callback("mousemove", mousemove.as_ref().unchecked_ref())?;
-        mousemove.forget();
-    }
-    {
-        let st = state.clone();
-        let mouseup = Closure::<dyn FnMut(MouseEvent)>::wrap(Box::new(move |_e: MouseEvent| {
-            st.borrow_mut().dragging_idx = None;
-        }));
-        state
-            .borrow()
-            .window
-            .add_event_listener_with_callback("mouseup", mouseup.as_ref().unchecked_ref())?;
-        mouseup.forget();
-    }
+    // Pointer events (mouse, pen and touch)
+    pointer::attach_pointer_events(state.clone())?;
 
     // Keyboard
     {
@@ -1607,28 +1185,56 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
             Closure::<dyn FnMut(KeyboardEvent)>::wrap(Box::new(move |e: KeyboardEvent| {
                 let key = e.key().to_lowercase();
                 let mut s = st.borrow_mut();
+                if s.editor.is_some() {
+                    if board_has_focus(&s) {
+                        editor::key(&mut s, &key);
+                    }
+                    return;
+                }
+                if s.shape_editor.is_some() {
+                    if board_has_focus(&s) {
+                        shape_editor::key(&mut s, &key);
+                    }
+                    return;
+                }
+                // Ctrl+Z undo, Ctrl+Shift+Z redo (Cmd on macOS)
+                if key == "z" && (e.ctrl_key() || e.meta_key()) {
+                    e.prevent_default();
+                    step_history(&mut s, e.shift_key());
+                    return;
+                }
                 if s.data.pieces.is_empty() {
                     return;
                 }
-                let idx = s.data.pieces.len() - 1;
-                let p = &mut s.data.pieces[idx];
+                if key == "q" || key == "e" || key == "f" {
+                    for i in s.selected.clone() {
+                        let p = s.data.pieces[i].clone();
+                        s.history.begin(&p);
+                    }
+                }
                 match key.as_str() {
                     // q counter-clockwise (3→12→9→6), e clockwise; speed depends on mode
-                    "q" => {
-                        let speed = if s.slow_mode {
-                            s.rot_speed_slow
-                        } else {
-                            s.rot_speed_fast
-                        };
-                        s.rot_vel = speed;
-                    }
-                    "e" => {
-                        let speed = if s.slow_mode {
-                            s.rot_speed_slow
+                    "q" | "e" => {
+                        let dir = if key == "q" { 1.0 } else { -1.0 };
+                        if s.snap.tap_step {
+                            // one step per press (auto-repeat keeps stepping)
+                            let sel = s.selected.clone();
+                            snap::step_pieces(&mut s, &sel, dir);
+                            for i in sel {
+                                let label = s.data.pieces[i].__label_idx;
+                                if !is_held(&s, label) {
+                                    commit_piece_gesture(&mut s, label);
+                                }
+                            }
+                            draw(&mut s);
                         } else {
-                            s.rot_speed_fast
-                        };
-                        s.rot_vel = -speed;
+                            let speed = if s.slow_mode {
+                                s.rot_speed_slow
+                            } else {
+                                s.rot_speed_fast
+                            };
+                            s.rot_vel = dir * speed;
+                        }
                     }
                     // toggle slow/fast mode
                     "s" => {
@@ -1650,7 +1256,25 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
                         update_status_dom(&s);
                     }
                     "f" => {
-                        p.flip = Some(!p.flip.unwrap_or(false));
+                        // A flip on its own is a single step; during a drag
+                        // or rotation it folds into that gesture.
+                        for i in s.selected.clone() {
+                            lock::flip_piece(&mut s, i);
+                            let label = s.data.pieces[i].__label_idx;
+                            if !is_held(&s, label) && s.rot_vel == 0.0 {
+                                commit_piece_gesture(&mut s, label);
+                            }
+                        }
+                        draw(&mut s);
+                    }
+                    // Tab / Shift+Tab cycle the selection while the board has focus
+                    "tab" if board_has_focus(&s) => {
+                        e.prevent_default();
+                        selection::cycle(&mut s, e.shift_key());
+                        draw(&mut s);
+                    }
+                    "escape" => {
+                        selection::select(&mut s, Vec::new());
                         draw(&mut s);
                     }
                     // toggle restrict movement mode
@@ -1663,6 +1287,19 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
                         });
                         update_status_dom(&s);
                     }
+                    // next hint: highlight, then ghost, then snap into place
+                    "h" => {
+                        let st = st.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            hint::request_hint(&st).await;
+                            draw(&mut st.borrow_mut());
+                        });
+                    }
+                    // toggle magnetic edge/vertex snapping
+                    "m" => {
+                        s.magnet_mode = !s.magnet_mode;
+                        update_status_dom(&s);
+                    }
                     // track Shift press for temporary constraint
                     "shift" => {
                         s.shift_down = true;
@@ -1683,8 +1320,18 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
         let keyup = Closure::<dyn FnMut(KeyboardEvent)>::wrap(Box::new(move |e: KeyboardEvent| {
             let key = e.key().to_lowercase();
             let mut s = st.borrow_mut();
-            if key == "q" || key == "e" {
+            if (key == "q" || key == "e") && s.rot_vel != 0.0 {
                 s.rot_vel = 0.0;
+                // land the continuous rotation on the nearest snap angle
+                let sel = s.selected.clone();
+                snap::snap_pieces(&mut s, &sel);
+                for i in sel {
+                    let label = s.data.pieces[i].__label_idx;
+                    if !is_held(&s, label) {
+                        commit_piece_gesture(&mut s, label);
+                    }
+                }
+                draw(&mut s);
             }
             if key == "shift" {
                 s.shift_down = false;
@@ -1718,6 +1365,14 @@ fn attach_ui(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
     Ok(())
 }
 
+// Keyboard focus is on the page itself or the canvas, not a toolbar control.
+fn board_has_focus(state: &State) -> bool {
+    state
+        .document
+        .active_element()
+        .is_none_or(|el| matches!(el.tag_name().as_str(), "BODY" | "CANVAS"))
+}
+
 fn export_png_blueprint(state: &State) -> Result<(), JsValue> {
     let px_per_mm = 4.0; // export resolution
     // Set language for labels
@@ -1742,37 +1397,19 @@ fn export_png_blueprint(state: &State) -> Result<(), JsValue> {
     }
 
     // Build a PuzzleSpec (pieces-only), ignoring current poses to match CLI blueprint semantics
-    let board = state.data.board.clone().map(|b| blueprint_core::Board {
-        type_: b.type_,
-        w: b.w,
-        h: b.h,
-        polygons: b.polygons,
-    });
+    // Pieces share the core model; only the pose is reset.
+    let board = state.data.board.clone();
     let pieces = state
         .data
         .pieces
         .iter()
-        .map(|p| blueprint_core::Piece {
+        .map(|p| Piece {
             id: p.id.clone(),
-            type_: p.type_.clone(),
             at: Some([0.0, 0.0]),
             rotation: Some(0.0),
             anchor: Some("bottomleft".to_string()),
             flip: Some(false),
-            w: p.w,
-            h: p.h,
-            side: p.side,
-            a: p.a,
-            b: p.b,
-            n: p.n,
-            d: p.d,
-            r: p.r,
-            base_bottom: p.base_bottom,
-            base_top: p.base_top,
-            height: p.height,
-            base: p.base,
-            offset_top: p.offset_top,
-            points: p.points.clone(),
+            ..Piece::new(p.shape.clone())
         })
         .collect::<Vec<_>>();
     let note = {
@@ -1799,38 +1436,15 @@ fn export_png_blueprint(state: &State) -> Result<(), JsValue> {
         pieces: Some(pieces),
         parts: None,
         counts: None,
+        order: None,
         shapes_file: None,
     };
 
     let (svg, w_px, h_px) = blueprint_core::build_blueprint_svg(&spec, px_per_mm, None);
 
-    // Render SVG to RGBA using embedded font
-    let mut opt = usvg::Options::default();
-    let mut fontdb = usvg::fontdb::Database::new();
-    fontdb.load_font_data(fonts::FONT_BYTES.to_vec());
-    let family_name = {
-        let mut it = fontdb.faces();
-        if let Some(face) = it.next() {
-            face.families.first().map(|(n, _)| n.clone())
-        } else {
-            None
-        }
-    };
-    if let Some(name) = family_name {
-        fontdb.set_sans_serif_family(name);
-    }
-    opt.fontdb = std::sync::Arc::new(fontdb);
-    let tree = usvg::Tree::from_str(&svg, &opt)
-        .map_err(|e| JsValue::from_str(&format!("SVG parse error: {e:?}")))?;
-    let mut pixmap =
-        tiny_skia::Pixmap::new(w_px, h_px).ok_or(JsValue::from_str("pixmap alloc failed"))?;
-    let mut pm = pixmap.as_mut();
-    resvg::render(&tree, tiny_skia::Transform::identity(), &mut pm);
-
-    // Deterministic PNG encoding into memory (shared helper in blueprint-core)
-    let bytes =
-        blueprint_core::encode_rgba_to_png_bytes(pixmap.width(), pixmap.height(), pixmap.data())
-            .map_err(|e| JsValue::from_str(&format!("encode: {e}")))?;
+    // Render SVG to PNG using the embedded font (shared helper in blueprint-core)
+    let bytes = blueprint_core::render_svg_to_png_bytes(&svg, w_px, h_px, fonts::FONT_BYTES)
+        .map_err(|e| JsValue::from_str(&e))?;
 
     // Create Blob and trigger download
     let document = state.document.clone();
@@ -1873,10 +1487,8 @@ fn start_animation(state: Rc<RefCell<State>>) {
             let mut s = state.borrow_mut();
             let vel = s.rot_vel;
             if vel.abs() > 0.0 {
-                if !s.data.pieces.is_empty() {
-                    let idx = s.dragging_idx.unwrap_or_else(|| s.data.pieces.len() - 1);
-                    let p = &mut s.data.pieces[idx];
-                    p.rotation = Some(p.rotation.unwrap_or(0.0) + vel / 60.0);
+                for i in s.selected.clone() {
+                    lock::rotate_piece(&mut s, i, vel / 60.0);
                 }
                 draw(&mut s);
             }
@@ -1890,121 +1502,28 @@ fn start_animation(state: Rc<RefCell<State>>) {
         .request_animation_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref());
 }
 
-fn default_puzzle() -> Puzzle {
+fn default_puzzle() -> (Option<CountsSpec>, Puzzle) {
     // Build from counts + shapes so we don't rely on a positioned piece file
-    let counts_txt = include_str!("../../puzzle/k11.json");
+    let counts_txt = include_str!("../../puzzle/k10.json");
     let shapes_txt = include_str!("../../shapes.json");
     if let (Ok(spec), Ok(catalog)) = (
         serde_json::from_str::<CountsSpec>(counts_txt),
         serde_json::from_str::<ShapesCatalog>(shapes_txt),
     ) {
-        build_puzzle_from_counts(&spec, &catalog)
+        let puzzle = build_puzzle_from_counts(&spec, &catalog);
+        (Some(spec), puzzle)
     } else {
-        Puzzle {
+        let puzzle = Puzzle {
             units: Some("mm".to_string()),
             board: None,
             pieces: Vec::new(),
+            goal: Goal::Contain,
             note_en: None,
             note_zh: None,
-        }
-    }
-}
-
-fn build_puzzle_from_counts(spec: &CountsSpec, catalog: &ShapesCatalog) -> Puzzle {
-    use std::collections::HashMap;
-    let mut by_id: HashMap<&str, &ShapeDef> = HashMap::new();
-    for s in &catalog.shapes {
-        by_id.insert(s.id.as_str(), s);
-    }
-    let mut pieces: Vec<Piece> = Vec::new();
-    for (id, ct) in &spec.counts {
-        if let Some(sd) = by_id.get(id.as_str()) {
-            for _ in 0..*ct {
-                let p = Piece {
-                    id: Some(sd.id.clone()),
-                    type_: sd.type_.clone(),
-                    w: sd.w,
-                    h: sd.h,
-                    side: sd.side,
-                    a: sd.a,
-                    b: sd.b,
-                    n: sd.n,
-                    d: sd.d,
-                    r: sd.r,
-                    base_bottom: sd.base_bottom,
-                    base_top: sd.base_top,
-                    height: sd.height,
-                    base: sd.base,
-                    offset_top: sd.offset_top,
-                    points: sd.points.clone(),
-                    ..Default::default()
-                };
-                // For initial layout: arrange in rows inside board or in a grid starting at (10,10)
-                pieces.push(p);
-            }
-        }
-    }
-
-    // Simple initial placement: grid with 10mm margin and 5mm gap
-    let margin = 10.0;
-    let gap = 5.0;
-    let (bw, _bh) = spec
-        .board
-        .as_ref()
-        .map(|b| (b.w.unwrap_or(200.0), b.h.unwrap_or(200.0)))
-        .unwrap_or((200.0, 200.0));
-    let mut x = margin;
-    let mut y = margin;
-    let maxw = bw - margin;
-    let mut row_h = 0.0;
-    for p in &mut pieces {
-        let (geom, _ctr) = piece_geom(p);
-        let bb = bounds_of_points(&geom);
-        let w = bb.2 - bb.0;
-        let h = bb.3 - bb.1;
-        if x + w > maxw {
-            x = margin;
-            y += row_h + gap;
-            row_h = 0.0;
-        }
-        // Anchor bottomleft by default; circles and regular polygons look better centered
-        match p.type_.as_str() {
-            "circle" | "regular_polygon" => {
-                p.anchor = Some("center".to_string());
-                p.at = Some([x + w / 2.0, y + h / 2.0]);
-            }
-            _ => {
-                p.anchor = Some("bottomleft".to_string());
-                p.at = Some([x, y]);
-            }
-        }
-        x += w + gap;
-        if h > row_h {
-            row_h = h;
-        }
-    }
-
-    Puzzle {
-        units: spec.units.clone().or(Some("mm".to_string())),
-        board: spec.board.clone(),
-        pieces,
-        note_en: spec.note_en.clone(),
-        note_zh: spec.note_zh.clone(),
-    }
-}
-
-fn bounds_of_points(pts: &[Pt]) -> (f64, f64, f64, f64) {
-    let mut minx = f64::INFINITY;
-    let mut miny = f64::INFINITY;
-    let mut maxx = f64::NEG_INFINITY;
-    let mut maxy = f64::NEG_INFINITY;
-    for p in pts {
-        minx = minx.min(p.x);
-        miny = miny.min(p.y);
-        maxx = maxx.max(p.x);
-        maxy = maxy.max(p.y);
+            solutions: Vec::new(),
+        };
+        (None, puzzle)
     }
-    (minx, miny, maxx, maxy)
 }
 
 #[wasm_bindgen(start)]
@@ -2014,10 +1533,10 @@ pub fn start() -> Result<(), JsValue> {
     let document = window.document().ok_or("no document")?;
     let (canvas, ctx) = init_canvas(&document)?;
 
-    let data = default_puzzle();
+    let (spec, data) = default_puzzle();
     // Determine initial puzzle name from URL parameter if present
-    let mut puzzle_name = "k11".to_string();
-    // If URL param p is set, fetch that; otherwise also fetch default 'k11' from server
+    let mut puzzle_name = "k10".to_string();
+    // If URL param p is set, fetch that; otherwise also fetch default 'k10' from server
     if let Ok(search) = window.location().search() {
         if let Some(p) = get_query_param(&search, "p") {
             puzzle_name = p.clone();
@@ -2039,8 +1558,8 @@ pub fn start() -> Result<(), JsValue> {
             let cv = canvas.clone();
             let ctx2 = ctx.clone();
             wasm_bindgen_futures::spawn_local(async move {
-                if let Err(err) = fetch_and_load_puzzle(win, doc, cv, ctx2, "k11").await {
-                    log(&format!("Failed to load default puzzle 'k11': {:?}", err));
+                if let Err(err) = fetch_and_load_puzzle(win, doc, cv, ctx2, "k10").await {
+                    log(&format!("Failed to load default puzzle 'k10': {:?}", err));
                 }
             });
         }
@@ -2052,9 +1571,13 @@ pub fn start() -> Result<(), JsValue> {
         canvas,
         ctx,
         data,
+        spec,
         puzzle_name,
-        dragging_idx: None,
-        drag_off: (0.0, 0.0),
+        selected: Vec::new(),
+        z_order: Vec::new(),
+        snap: Default::default(),
+        drags: Vec::new(),
+        last_tap: None,
         scale: DEFAULT_MM2PX,
         offset: (0.0, 0.0),
         rot_vel: 0.0,
@@ -2062,17 +1585,33 @@ pub fn start() -> Result<(), JsValue> {
         rot_speed_fast: 180.0,
         rot_speed_slow: 15.0,
         restrict_mode: false,
+        magnet_mode: false,
+        guides: Vec::new(),
         shift_down: false,
         initial_data: Puzzle {
             units: None,
             board: None,
             pieces: Vec::new(),
+            goal: Goal::Contain,
             note_en: None,
             note_zh: None,
+            solutions: Vec::new(),
         },
         lang: "en".to_string(),
         shapes_catalog: serde_json::from_str::<ShapesCatalog>(include_str!("../../shapes.json"))
             .ok(),
+        history: Default::default(),
+        lock_world: Default::default(),
+        solution_overlay: None,
+        overlay_pieces: Vec::new(),
+        hint: None,
+        hints_used: 0,
+        searched: None,
+        searching: false,
+        loads: 0,
+        validation: None,
+        editor: None,
+        shape_editor: None,
     }));
 
     STATE.with(|st| st.replace(Some(state.clone())));
@@ -2080,8 +1619,7 @@ pub fn start() -> Result<(), JsValue> {
     STATE.with(|st| {
         if let Some(st_rc) = st.borrow().as_ref() {
             let mut s = st_rc.borrow_mut();
-            assign_piece_colors(&mut s.data);
-            s.initial_data = s.data.clone();
+            puzzle_loaded(&mut s);
             update_note_dom(&s);
             update_status_dom(&s);
         }
@@ -2092,6 +1630,165 @@ pub fn start() -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Solve the loaded puzzle, show the solution on the board and return it as
+/// JSON. The search runs in the background; the promise rejects with the
+/// reason when it finds nothing.
+#[wasm_bindgen]
+pub async fn solve() -> Result<String, JsValue> {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    let st_rc = state.ok_or_else(|| JsValue::from_str("State not initialized"))?;
+    match show_solution(&st_rc).await {
+        Some(Ok(())) => serde_json::to_string_pretty(&st_rc.borrow().data)
+            .map_err(|e| JsValue::from_str(&e.to_string())),
+        Some(Err(e)) => Err(JsValue::from_str(search::failure_message(e, "en"))),
+        None => Err(JsValue::from_str("Search already running or cancelled")),
+    }
+}
+
+/// Validate the current layout and return the report as JSON
+/// (`{ ok, violations: [{ kind, ... }], coverage_pct, uncovered_mm2 }`).
+#[wasm_bindgen]
+pub fn validate() -> Result<String, JsValue> {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    if let Some(st_rc) = state {
+        let mut s = st_rc.borrow_mut();
+        serde_json::to_string(&validation(&mut s).report)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    } else {
+        Err(JsValue::from_str("State not initialized"))
+    }
+}
+
+/// Index of the known solution the current arrangement matches (identical
+/// pieces may be swapped), if any.
+#[wasm_bindgen]
+pub fn check_solution() -> Option<u32> {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    let st_rc = state?;
+    let solution = validation(&mut st_rc.borrow_mut()).solution;
+    solution.map(|i| i as u32)
+}
+
+/// Give the next hint: highlight a misplaced piece, then show where it goes,
+/// then snap it into place. Resolves to the level given (1-3), or nothing
+/// when the layout is solved or no solution is available. Without known
+/// solutions the solver searches in the background first.
+#[wasm_bindgen]
+pub async fn hint() -> Option<u8> {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    let st_rc = state?;
+    let level = hint::request_hint(&st_rc).await;
+    draw(&mut st_rc.borrow_mut());
+    level
+}
+
+/// Number of hints given since the puzzle was loaded.
+#[wasm_bindgen]
+pub fn hint_count() -> u32 {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    state.map_or(0, |st_rc| st_rc.borrow().hints_used)
+}
+
+/// Undo the last edit; returns false when there is nothing to undo.
+#[wasm_bindgen]
+pub fn undo() -> bool {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    state.is_some_and(|st_rc| step_history(&mut st_rc.borrow_mut(), false))
+}
+
+/// Redo the last undone edit; returns false when there is nothing to redo.
+#[wasm_bindgen]
+pub fn redo() -> bool {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    state.is_some_and(|st_rc| step_history(&mut st_rc.borrow_mut(), true))
+}
+
+/// Move every piece back into the tray beside the board, upright and
+/// unflipped. Recorded as one history step.
+#[wasm_bindgen]
+pub fn return_to_tray() {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    if let Some(st_rc) = state {
+        let mut s = st_rc.borrow_mut();
+        commit_gesture(&mut s);
+        let before = s.data.pieces.clone();
+        let tray = tray_for(s.data.board.as_ref(), &s.data.pieces);
+        pack_into_tray(&mut s.data.pieces, &tray);
+        if !same_layout(&before, &s.data.pieces) {
+            let cmd = Command::Replace {
+                before,
+                after: s.data.pieces.clone(),
+            };
+            s.history.push(cmd);
+        }
+        s.drags.clear();
+        s.rot_vel = 0.0;
+        draw(&mut s);
+    }
+}
+
+/// Scramble a freshly built counts puzzle when the URL carries `seed=`, so a
+/// class sharing the link starts from the same layout.
+fn apply_url_seed(window: &Window, puzzle: &mut Puzzle) {
+    let search = window.location().search().unwrap_or_default();
+    if let Some(seed) = get_query_param(&search, "seed") {
+        let tray = tray_for(puzzle.board.as_ref(), &puzzle.pieces);
+        scramble_into_tray(&mut puzzle.pieces, &tray, parse_seed(&seed));
+    }
+}
+
+/// Scatter the pieces over the tray with a fresh random seed (positions,
+/// rotations and flips) as one history step. The seed is written to the
+/// URL as `seed=` so the link reproduces the scramble; returns the seed.
+#[wasm_bindgen]
+pub fn scramble() -> String {
+    let seed = (js_sys::Math::random() * 1e9) as u64;
+    let state = STATE.with(|st| st.borrow().as_ref().cloned());
+    if let Some(st_rc) = state {
+        let mut s = st_rc.borrow_mut();
+        commit_gesture(&mut s);
+        let before = s.data.pieces.clone();
+        let tray = tray_for(s.data.board.as_ref(), &s.data.pieces);
+        scramble_into_tray(&mut s.data.pieces, &tray, seed);
+        let cmd = Command::Replace {
+            before,
+            after: s.data.pieces.clone(),
+        };
+        s.history.push(cmd);
+        s.drags.clear();
+        s.rot_vel = 0.0;
+        let loc = s.window.location();
+        let search = set_query_param(&loc.search().unwrap_or_default(), "seed", &seed.to_string());
+        if let Ok(history) = s.window.history() {
+            let url = format!("{}{}", loc.pathname().unwrap_or_default(), search);
+            let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+        }
+        draw(&mut s);
+    }
+    seed.to_string()
+}
+
+// Solve the loaded puzzle (see `search`) and move the pieces into the
+// solution. `None` when the search was dropped.
+async fn show_solution(st: &Rc<RefCell<State>>) -> Option<Result<(), SolveError>> {
+    let solved = match search::search(st).await? {
+        Ok(solved) => solved,
+        Err(e) => return Some(Err(e)),
+    };
+    let mut s = st.borrow_mut();
+    let state = &mut *s;
+    commit_gesture(state);
+    state.history.push(Command::Replace {
+        before: state.data.pieces.clone(),
+        after: solved.pieces.clone(),
+    });
+    state.data.pieces = solved.pieces;
+    state.drags.clear();
+    state.rot_vel = 0.0;
+    draw(state);
+    Some(Ok(()))
+}
+
 #[wasm_bindgen]
 pub async fn load_puzzle_from_text(text: String) -> Result<(), JsValue> {
     let state = STATE.with(|st| st.borrow().as_ref().cloned());
@@ -2121,7 +1818,7 @@ async fn fetch_and_load_puzzle(
     .await
     .unwrap_or_default();
     // Try parse as counts+shapes first, then fall back to full Puzzle
-    let puzzle: Puzzle = if let Ok(spec) = serde_json::from_str::<CountsSpec>(&text) {
+    let (spec, mut puzzle) = if let Ok(spec) = serde_json::from_str::<CountsSpec>(&text) {
         // Fetch shapes file if provided; else fallback to bundled shapes
         let shapes_text = if let Some(sf) = spec.shapes_file.clone() {
             fetch_text_with_fallbacks(&window, &[&asset_url(&sf), &sf])
@@ -2143,20 +1840,25 @@ async fn fetch_and_load_puzzle(
                 st_rc.borrow_mut().shapes_catalog = Some(catalog.clone());
             }
         });
-        build_puzzle_from_counts(&spec, &catalog)
-    } else if let Ok(p) = serde_json::from_str::<Puzzle>(&text) {
-        p
+        let mut puzzle = build_puzzle_from_counts(&spec, &catalog);
+        apply_url_seed(&window, &mut puzzle);
+        (Some(spec), puzzle)
     } else {
-        return Err(JsValue::from_str("Unrecognized puzzle JSON format"));
+        let puzzle = serde_json::from_str::<Puzzle>(&text)
+            .map_err(|e| JsValue::from_str(&format!("Unrecognized puzzle JSON format: {}", e)))?;
+        (None, puzzle)
     };
+    if puzzle.solutions.is_empty() {
+        puzzle.solutions = fetch_solutions(&window, name).await;
+    }
 
     STATE.with(|st| {
         if let Some(st_rc) = st.borrow().as_ref() {
             let mut s = st_rc.borrow_mut();
             s.data = puzzle;
+            s.spec = spec;
             s.puzzle_name = name.to_string();
-            assign_piece_colors(&mut s.data);
-            s.initial_data = s.data.clone();
+            puzzle_loaded(&mut s);
             update_note_dom(&s);
             update_status_dom(&s);
             s.window = window.clone();
@@ -2169,7 +1871,33 @@ async fn fetch_and_load_puzzle(
     Ok(())
 }
 
+// Solutions from the optional sidecar `puzzle/<name>.solution.json`.
+async fn fetch_solutions(window: &Window, name: &str) -> Vec<Solution> {
+    let Some(text) = fetch_text_with_fallbacks(
+        window,
+        &[
+            &asset_url(&format!("puzzle/{}.solution.json", name)),
+            &format!("/puzzle/{}.solution.json", name),
+        ],
+    )
+    .await
+    else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<SolutionsFile>(&text) {
+        Ok(f) => f.solutions,
+        Err(e) => {
+            log(&format!("Ignoring solutions for '{}': {}", name, e));
+            Vec::new()
+        }
+    }
+}
+
 fn update_viewport(state: &mut State) {
+    // Keep the view still while an editor vertex is dragged
+    if state.editor.as_ref().is_some_and(|ed| ed.drag.is_some()) {
+        return;
+    }
     let canvas_w = state.canvas.width() as f64;
     let canvas_h = state.canvas.height() as f64;
     // Determine content bounds in mm
@@ -2191,15 +1919,21 @@ fn update_viewport(state: &mut State) {
         }
     }
     // Always include pieces in the bounds so off-board pieces remain visible
-    for p in &state.data.pieces {
-        let (geom, _ctr) = piece_geom(p);
-        for q in geom {
+    for (geom, _ctr) in state.data.pieces.iter().filter_map(|p| p.cached_geom()) {
+        for &q in geom {
             minx = minx.min(q.x);
             maxx = maxx.max(q.x);
             miny = miny.min(q.y);
             maxy = maxy.max(q.y);
         }
     }
+    // Editor vertices, including those of unfinished regions
+    for q in state.editor.iter().flat_map(|ed| ed.outlines()).flatten() {
+        minx = minx.min(q.x);
+        maxx = maxx.max(q.x);
+        miny = miny.min(q.y);
+        maxy = maxy.max(q.y);
+    }
     let have_bounds = maxx.is_finite() && maxy.is_finite();
 
     if have_bounds {
@@ -0,0 +1,140 @@
+// Lock mode for angular motion. Translations are swept by Rapier in
+// `rapier_allowed_delta`; rotations and flips are checked against the
+// validation rules instead. A turn is advanced in small steps and stops at
+// first contact, and a flip that would penetrate is rejected. Overlaps that
+// exist before the change never block it, so a piece that is already stuck
+// can still be turned free. Everything but the turning piece stays put, so
+// its proxies are built once per turn or flip.
+
+use puzzle_geom::collide::{
+    PieceProxy, bounds_overlap, obstacle_proxies, piece_proxy, proxies_penetration,
+    proxy_border_depth,
+};
+use puzzle_geom::constants::RING_WIDTH_MM;
+use puzzle_geom::geom::{board_outer_geom, board_to_geom};
+use puzzle_geom::models::Point as Pt;
+
+use crate::state::State;
+
+// Largest rotation (deg) checked in one go, so a big step cannot jump over
+// a thin neighbour.
+const SWEEP_STEP_DEG: f64 = 2.0;
+// Bisection rounds locating the contact inside a blocked step.
+const BISECT_ROUNDS: usize = 8;
+
+/// Whether moves are currently constrained (Lock toggled or Shift held).
+pub fn lock_active(state: &State) -> bool {
+    state.restrict_mode || state.shift_down
+}
+
+/// Rotate piece `idx` by `delta` degrees. In lock mode the turn stops at
+/// the first new contact with another piece or the board ring.
+pub fn rotate_piece(state: &mut State, idx: usize, delta: f64) {
+    let start = state.data.pieces[idx].rotation.unwrap_or(0.0);
+    if !lock_active(state) {
+        state.data.pieces[idx].rotation = Some(start + delta);
+        return;
+    }
+    let around = Surroundings::new(state, idx);
+    let before = around.contacts(state, idx);
+    let steps = (delta.abs() / SWEEP_STEP_DEG).ceil().max(1.0);
+    let step = delta / steps;
+    let mut done = 0.0;
+    for _ in 0..steps as usize {
+        state.data.pieces[idx].rotation = Some(start + done + step);
+        if adds_contact(&before, &around.contacts(state, idx)) {
+            // Narrow down the free part of this step
+            let (mut lo, mut hi) = (0.0, 1.0);
+            for _ in 0..BISECT_ROUNDS {
+                let mid = (lo + hi) / 2.0;
+                state.data.pieces[idx].rotation = Some(start + done + step * mid);
+                if adds_contact(&before, &around.contacts(state, idx)) {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            state.data.pieces[idx].rotation = Some(start + done + step * lo);
+            return;
+        }
+        done += step;
+    }
+}
+
+/// Flip piece `idx` over. In lock mode the flip is undone when the flipped
+/// pose would penetrate something; returns whether the piece was flipped.
+pub fn flip_piece(state: &mut State, idx: usize) -> bool {
+    let around = lock_active(state).then(|| Surroundings::new(state, idx));
+    let before = around.as_ref().map(|a| a.contacts(state, idx));
+    let p = &mut state.data.pieces[idx];
+    p.flip = Some(!p.flip.unwrap_or(false));
+    if let (Some(around), Some(before)) = (&around, before)
+        && adds_contact(&before, &around.contacts(state, idx))
+    {
+        let p = &mut state.data.pieces[idx];
+        p.flip = Some(!p.flip.unwrap_or(false));
+        return false;
+    }
+    true
+}
+
+// What piece `idx` currently overlaps, by the same rules as validation.
+struct Contacts {
+    pieces: Vec<usize>,
+    border: bool,
+}
+
+// Proxies of everything piece `idx` can run into: the other pieces (with
+// their indices), the board outline and ring, and the obstacles.
+struct Surroundings {
+    others: Vec<(usize, PieceProxy)>,
+    inner: Option<Vec<Vec<Pt>>>,
+    outer: Option<Vec<Vec<Pt>>>,
+    obstacles: Vec<PieceProxy>,
+}
+
+impl Surroundings {
+    fn new(state: &State, idx: usize) -> Self {
+        let others = state
+            .data
+            .pieces
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != idx)
+            .map(|(j, q)| (j, piece_proxy(q)))
+            .collect();
+        let frame = state.data.frame();
+        let inner = frame.and_then(board_to_geom);
+        let outer = frame.and_then(|b| board_outer_geom(b, RING_WIDTH_MM));
+        let obstacles = frame.map(obstacle_proxies).unwrap_or_default();
+        Surroundings {
+            others,
+            inner,
+            outer,
+            obstacles,
+        }
+    }
+
+    fn contacts(&self, state: &State, idx: usize) -> Contacts {
+        let pr = piece_proxy(&state.data.pieces[idx]);
+        let hits = |other: &PieceProxy| {
+            bounds_overlap(pr.bounds, other.bounds) && proxies_penetration(&pr, other).is_some()
+        };
+        let pieces = self
+            .others
+            .iter()
+            .filter(|(_, other)| hits(other))
+            .map(|&(j, _)| j)
+            .collect();
+        // Obstacles count as part of the border
+        let border =
+            self.inner.as_ref().is_some_and(|inner| {
+                proxy_border_depth(&pr, inner, self.outer.as_deref()).is_some()
+            }) || self.obstacles.iter().any(hits);
+        Contacts { pieces, border }
+    }
+}
+
+fn adds_contact(before: &Contacts, after: &Contacts) -> bool {
+    (after.border && !before.border) || after.pieces.iter().any(|j| !before.pieces.contains(j))
+}
@@ -0,0 +1,164 @@
+// Magnet mode: while dragging, pull the held piece flush against nearby
+// edges and vertices of other pieces and the board. An almost parallel edge
+// within reach is matched in orientation and position first; otherwise the
+// closest vertex pair is joined. The matched targets are kept as guides for
+// `draw`.
+
+use puzzle_geom::Point as Pt;
+use puzzle_geom::geom::{board_obstacles_geom, board_to_geom, piece_geom, translate_piece};
+
+use crate::constants::{MAGNET_ANGLE_DEG, MAGNET_PX};
+use crate::state::State;
+
+/// A snap target shown while dragging: an edge, or a vertex when `a == b`.
+#[derive(Clone, Copy, Debug)]
+pub struct Guide {
+    pub a: Pt,
+    pub b: Pt,
+}
+
+/// Snap piece `idx` to the closest target and record the guides. Pieces in
+/// `group` (the dragged selection) are not targets; the edge orientation is
+/// only matched when the piece is dragged alone. Returns the translation
+/// applied so the rest of the group can follow.
+pub fn magnet_snap(state: &mut State, idx: usize, group: &[usize]) -> (f64, f64) {
+    state.guides.clear();
+    if state.data.pieces[idx].shape.circle_radius().is_some() {
+        return (0.0, 0.0);
+    }
+    let tol = MAGNET_PX / state.scale;
+    let targets = target_outlines(state, group);
+    let (geom, _ctr) = piece_geom(&state.data.pieces[idx]);
+
+    // 1) Nearly parallel edges within reach whose spans overlap
+    let mut best: Option<(f64, f64, usize, (Pt, Pt))> = None; // (dist, turn, edge, target)
+    for (i, pe) in edges(&geom).enumerate() {
+        for te in targets.iter().flat_map(|t| edges(t)) {
+            let turn = half_turn_diff(edge_angle(te), edge_angle(pe));
+            if turn.abs() > MAGNET_ANGLE_DEG {
+                continue;
+            }
+            let mid = Pt {
+                x: (pe.0.x + pe.1.x) / 2.0,
+                y: (pe.0.y + pe.1.y) / 2.0,
+            };
+            let dist = line_offset(te, mid).abs();
+            if dist <= tol && spans_overlap(pe, te) && best.is_none_or(|b| dist < b.0) {
+                best = Some((dist, turn, i, te));
+            }
+        }
+    }
+
+    let mut shift = (0.0, 0.0);
+    if let Some((_, turn, i, te)) = best {
+        if group.len() <= 1 && turn.abs() > 1e-9 {
+            let p = &mut state.data.pieces[idx];
+            p.rotation = Some(p.rotation.unwrap_or(0.0) + turn);
+        }
+        let (geom, _ctr) = piece_geom(&state.data.pieces[idx]);
+        let pe = edges(&geom).nth(i).unwrap_or(te);
+        // flush: move the edge onto the target's line
+        let n = unit_normal(te);
+        let d = line_offset(te, pe.0);
+        shift = (-n.x * d, -n.y * d);
+        // then slide along it when a corner is within reach of a target corner
+        let u = unit(te.1.x - te.0.x, te.1.y - te.0.y);
+        let mut slide: Option<(f64, Pt)> = None;
+        for pv in [pe.0, pe.1] {
+            let pv = Pt {
+                x: pv.x + shift.0,
+                y: pv.y + shift.1,
+            };
+            for tv in [te.0, te.1] {
+                let t = (tv.x - pv.x) * u.x + (tv.y - pv.y) * u.y;
+                if t.abs() <= tol && slide.is_none_or(|s| t.abs() < s.0.abs()) {
+                    slide = Some((t, tv));
+                }
+            }
+        }
+        state.guides.push(Guide { a: te.0, b: te.1 });
+        if let Some((t, tv)) = slide {
+            shift = (shift.0 + u.x * t, shift.1 + u.y * t);
+            state.guides.push(Guide { a: tv, b: tv });
+        }
+    } else {
+        // 2) Closest vertex pair
+        let mut near: Option<(f64, Pt, Pt)> = None;
+        for pv in &geom {
+            for tv in targets.iter().flatten() {
+                let d = (tv.x - pv.x).hypot(tv.y - pv.y);
+                if d <= tol && near.is_none_or(|n| d < n.0) {
+                    near = Some((d, *pv, *tv));
+                }
+            }
+        }
+        if let Some((_, pv, tv)) = near {
+            shift = (tv.x - pv.x, tv.y - pv.y);
+            state.guides.push(Guide { a: tv, b: tv });
+        }
+    }
+    translate_piece(&mut state.data.pieces[idx], shift.0, shift.1);
+    shift
+}
+
+// Board outline and the outlines of pieces outside the dragged group.
+// Circles are skipped: their polyline has no meaningful edges or corners.
+fn target_outlines(state: &State, group: &[usize]) -> Vec<Vec<Pt>> {
+    let mut out: Vec<Vec<Pt>> = Vec::new();
+    if let Some(b) = &state.data.board {
+        out.extend(board_to_geom(b).unwrap_or_default());
+        out.extend(board_obstacles_geom(b));
+    }
+    for (j, p) in state.data.pieces.iter().enumerate() {
+        if group.contains(&j) || p.shape.circle_radius().is_some() {
+            continue;
+        }
+        out.push(piece_geom(p).0);
+    }
+    out
+}
+
+fn edges(poly: &[Pt]) -> impl Iterator<Item = (Pt, Pt)> + '_ {
+    let n = poly.len();
+    (0..n)
+        .map(move |i| (poly[i], poly[(i + 1) % n]))
+        .filter(|(a, b)| (b.x - a.x).hypot(b.y - a.y) > 1e-6)
+}
+
+fn edge_angle(e: (Pt, Pt)) -> f64 {
+    (e.1.y - e.0.y).atan2(e.1.x - e.0.x).to_degrees()
+}
+
+// Smallest turn (deg) taking direction `from` parallel to `to`, either way round.
+fn half_turn_diff(to: f64, from: f64) -> f64 {
+    let x = (to - from).rem_euclid(180.0);
+    if x > 90.0 { x - 180.0 } else { x }
+}
+
+fn unit(dx: f64, dy: f64) -> Pt {
+    let len = dx.hypot(dy).max(1e-12);
+    Pt {
+        x: dx / len,
+        y: dy / len,
+    }
+}
+
+fn unit_normal(e: (Pt, Pt)) -> Pt {
+    let u = unit(e.1.x - e.0.x, e.1.y - e.0.y);
+    Pt { x: -u.y, y: u.x }
+}
+
+// Signed distance of `p` from the infinite line through edge `e`.
+fn line_offset(e: (Pt, Pt), p: Pt) -> f64 {
+    let n = unit_normal(e);
+    (p.x - e.0.x) * n.x + (p.y - e.0.y) * n.y
+}
+
+// Whether the projections of the two edges onto `b`'s direction overlap.
+fn spans_overlap(a: (Pt, Pt), b: (Pt, Pt)) -> bool {
+    let u = unit(b.1.x - b.0.x, b.1.y - b.0.y);
+    let proj = |p: Pt| (p.x - b.0.x) * u.x + (p.y - b.0.y) * u.y;
+    let (a0, a1) = (proj(a.0), proj(a.1));
+    // `b` itself spans [0, |b|] along its own direction
+    a0.max(a1) > 0.0 && a0.min(a1) < proj(b.1)
+}
@@ -0,0 +1,261 @@
+// Long-lived Rapier world behind lock-mode dragging. Every piece has a
+// fixed body at its center carrying the same exact collider validation uses
+// (balls for circles, earcut triangles for polygons), and the board ring
+// sits on a ground body. `sync` only touches what changed since the last
+// query: a moved piece gets a new translation, a turned or flipped one a new
+// shape. A constrained drag makes the moving body dynamic for one step.
+
+use puzzle_geom::collide::{piece_proxy, proxy_local_shape};
+use puzzle_geom::constants::RING_WIDTH_MM;
+use puzzle_geom::geom::{board_obstacles_geom, board_outer_geom, board_to_geom};
+use puzzle_geom::{Board, Piece, Point as Pt, Shape};
+use rapier2d::na::{Isometry2, Point2};
+use rapier2d::prelude::*;
+
+use crate::constants::EDGE_RADIUS_MM;
+
+pub struct LockWorld {
+    pipeline: PhysicsPipeline,
+    params: IntegrationParameters,
+    islands: IslandManager,
+    broad_phase: BroadPhaseBvh,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    /// Holds the board colliders once built.
+    ground: Option<RigidBodyHandle>,
+    pieces: Vec<PieceBody>,
+}
+
+// A piece's body and the pose its collider was built for.
+struct PieceBody {
+    label: Option<usize>,
+    body: RigidBodyHandle,
+    at: Option<[f64; 2]>,
+    rotation: Option<f64>,
+    flip: Option<bool>,
+    anchor: Option<String>,
+    shape: Shape,
+    ctr: Pt,
+}
+
+impl Default for LockWorld {
+    fn default() -> Self {
+        LockWorld {
+            pipeline: PhysicsPipeline::new(),
+            // one step, velocity encodes full displacement
+            params: IntegrationParameters {
+                dt: 1.0,
+                ..Default::default()
+            },
+            islands: IslandManager::new(),
+            broad_phase: BroadPhaseBvh::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            ground: None,
+            pieces: Vec::new(),
+        }
+    }
+}
+
+impl LockWorld {
+    /// Drop everything, e.g. after loading another puzzle or board.
+    pub fn reset(&mut self) {
+        *self = LockWorld::default();
+    }
+
+    /// How far piece `moving` can travel towards `(dx, dy)` before it hits
+    /// another piece or the board ring, sliding along contacts.
+    pub fn slide(
+        &mut self,
+        pieces: &[Piece],
+        board: Option<&Board>,
+        moving: usize,
+        dx: f64,
+        dy: f64,
+    ) -> (f64, f64) {
+        self.sync(pieces, board);
+        let label = pieces[moving].__label_idx;
+        let Some(pb) = self.pieces.iter().find(|pb| pb.label == label) else {
+            return (0.0, 0.0);
+        };
+        let (h, ctr) = (pb.body, pb.ctr);
+        let start = vector![ctr.x as Real, ctr.y as Real];
+        if let Some(rb) = self.bodies.get_mut(h) {
+            rb.set_body_type(RigidBodyType::Dynamic, true);
+            rb.set_linvel(vector![dx as Real, dy as Real], true);
+            rb.set_angvel(0.0, true);
+        } else {
+            return (0.0, 0.0);
+        }
+
+        // Run one step with CCD. No hooks / events.
+        self.pipeline.step(
+            &vector![0.0, 0.0],
+            &self.params,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            &(),
+            &(),
+        );
+
+        // Park the body again; the caller moves the piece and the next sync
+        // picks up the new pose.
+        let Some(rb) = self.bodies.get_mut(h) else {
+            return (0.0, 0.0);
+        };
+        let end = *rb.translation();
+        rb.set_body_type(RigidBodyType::Fixed, false);
+        rb.set_linvel(vector![0.0, 0.0], false);
+        rb.set_translation(start, false);
+        ((end.x - start.x) as f64, (end.y - start.y) as f64)
+    }
+
+    // Bring the world in line with the pieces: add new ones, drop removed
+    // ones, and update those whose pose changed.
+    fn sync(&mut self, pieces: &[Piece], board: Option<&Board>) {
+        if self.ground.is_none() {
+            self.build_board(board);
+        }
+        let mut stale = std::mem::take(&mut self.pieces);
+        for p in pieces {
+            let pb = match stale.iter().position(|pb| pb.label == p.__label_idx) {
+                Some(i) => self.update(stale.swap_remove(i), p),
+                None => self.insert(p),
+            };
+            self.pieces.push(pb);
+        }
+        for pb in stale {
+            self.bodies.remove(
+                pb.body,
+                &mut self.islands,
+                &mut self.colliders,
+                &mut self.impulse_joints,
+                &mut self.multibody_joints,
+                true,
+            );
+        }
+    }
+
+    fn update(&mut self, mut pb: PieceBody, p: &Piece) -> PieceBody {
+        let same_shape = pb.rotation == p.rotation
+            && pb.flip == p.flip
+            && pb.anchor == p.anchor
+            && pb.shape == p.shape;
+        match (same_shape, pb.at, p.at) {
+            (true, Some(a), Some(b)) if a == b => {}
+            // A plain move only shifts the body
+            (true, Some(a), Some(b)) => {
+                pb.ctr = Pt {
+                    x: pb.ctr.x + b[0] - a[0],
+                    y: pb.ctr.y + b[1] - a[1],
+                };
+                pb.at = p.at;
+                if let Some(rb) = self.bodies.get_mut(pb.body) {
+                    rb.set_translation(vector![pb.ctr.x as Real, pb.ctr.y as Real], false);
+                }
+            }
+            _ => {
+                self.bodies.remove(
+                    pb.body,
+                    &mut self.islands,
+                    &mut self.colliders,
+                    &mut self.impulse_joints,
+                    &mut self.multibody_joints,
+                    true,
+                );
+                return self.insert(p);
+            }
+        }
+        pb
+    }
+
+    fn insert(&mut self, p: &Piece) -> PieceBody {
+        let pr = piece_proxy(p);
+        let body = self.bodies.insert(
+            RigidBodyBuilder::fixed()
+                .translation(vector![pr.ctr.x as Real, pr.ctr.y as Real])
+                .lock_rotations()
+                .ccd_enabled(true)
+                .build(),
+        );
+        if let Some(shape) = proxy_local_shape(&pr) {
+            let col = ColliderBuilder::new(shape)
+                .friction(0.0)
+                .restitution(0.0)
+                .build();
+            self.colliders
+                .insert_with_parent(col, body, &mut self.bodies);
+        }
+        PieceBody {
+            label: p.__label_idx,
+            body,
+            at: p.at,
+            rotation: p.rotation,
+            flip: p.flip,
+            anchor: p.anchor.clone(),
+            shape: p.shape.clone(),
+            ctr: pr.ctr,
+        }
+    }
+
+    // Board as an inner/outer polyline barrier on a shared ground body.
+    fn build_board(&mut self, board: Option<&Board>) {
+        let ground = self.bodies.insert(RigidBodyBuilder::fixed().build());
+        self.ground = Some(ground);
+        let Some(b) = board else {
+            return;
+        };
+        // Obstacles are walled in like the border so pieces stay off them
+        let layers = [
+            board_to_geom(b),
+            board_outer_geom(b, RING_WIDTH_MM),
+            Some(board_obstacles_geom(b)),
+        ];
+        for geoms in layers.into_iter().flatten() {
+            let mut parts: Vec<(Isometry2<Real>, SharedShape)> = Vec::new();
+            for g in geoms {
+                build_capsule_obstacles(&g, &mut parts);
+            }
+            if !parts.is_empty() {
+                let col = ColliderBuilder::compound(parts)
+                    .friction(0.0)
+                    .restitution(0.0)
+                    .build();
+                self.colliders
+                    .insert_with_parent(col, ground, &mut self.bodies);
+            }
+        }
+    }
+}
+
+fn build_capsule_obstacles(points: &[Pt], out: &mut Vec<(Isometry2<Real>, SharedShape)>) {
+    if points.len() < 2 {
+        return;
+    }
+    let r: Real = EDGE_RADIUS_MM as Real;
+    let n = points.len();
+    for i in 0..n {
+        let a = points[i];
+        let b = points[(i + 1) % n];
+        let cap = SharedShape::capsule(
+            Point2::new(a.x as Real, a.y as Real),
+            Point2::new(b.x as Real, b.y as Real),
+            r,
+        );
+        out.push((Isometry2::identity(), cap));
+    }
+}
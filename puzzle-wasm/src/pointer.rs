@@ -0,0 +1,349 @@
+// Canvas input via Pointer Events, so mouse, pen and touch share one path.
+// Every pointer can hold its own piece (two children dragging at once); a
+// second finger on or just beside a held piece twists it, and a double tap
+// flips it.
+// Grabbing a selected piece moves, twists and flips the whole selection.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::PointerEvent;
+
+use puzzle_geom::Point as Pt;
+use puzzle_geom::geom::translate_piece;
+
+use crate::constants::{DOUBLE_TAP_MS, DOUBLE_TAP_PX, TWIST_REACH_PX};
+use crate::lock::{flip_piece, lock_active, rotate_piece};
+use crate::magnet::magnet_snap;
+use crate::selection::{group, raise, select, toggle};
+use crate::snap::snap_pieces;
+use crate::state::{Drag, State, Tap};
+use crate::utils::{from_screen, to_screen};
+use crate::{
+    commit_piece_gesture, draw, event_canvas_coords, point_in_polygon, rapier_allowed_delta,
+};
+use crate::{editor, shape_editor};
+
+/// Index of the piece with the given `__label_idx`.
+pub fn piece_index(state: &State, label: Option<usize>) -> Option<usize> {
+    state
+        .data
+        .pieces
+        .iter()
+        .position(|p| p.__label_idx == label)
+}
+
+/// Whether any pointer currently holds the piece with the given label.
+pub fn is_held(state: &State, label: Option<usize>) -> bool {
+    state.drags.iter().any(|d| d.label == label)
+}
+
+pub fn attach_pointer_events(state: Rc<RefCell<State>>) -> Result<(), JsValue> {
+    let canvas = state.borrow().canvas.clone();
+    {
+        let st = state.clone();
+        let down = Closure::<dyn FnMut(PointerEvent)>::wrap(Box::new(move |e: PointerEvent| {
+            let mut s = st.borrow_mut();
+            // Keep touches from scrolling the page or emulating mouse events
+            e.prevent_default();
+            let pt = event_canvas_coords(&e, &s.canvas);
+            let additive = e.ctrl_key() || e.meta_key();
+            pointer_down(&mut s, e.pointer_id(), pt, e.time_stamp(), additive);
+            // Keep receiving moves when the pointer leaves the canvas
+            let _ = s.canvas.set_pointer_capture(e.pointer_id());
+        }));
+        canvas.add_event_listener_with_callback("pointerdown", down.as_ref().unchecked_ref())?;
+        down.forget();
+    }
+    {
+        let st = state.clone();
+        let moved = Closure::<dyn FnMut(PointerEvent)>::wrap(Box::new(move |e: PointerEvent| {
+            let mut s = st.borrow_mut();
+            let pt = event_canvas_coords(&e, &s.canvas);
+            pointer_move(&mut s, e.pointer_id(), pt);
+        }));
+        canvas.add_event_listener_with_callback("pointermove", moved.as_ref().unchecked_ref())?;
+        moved.forget();
+    }
+    for ev in ["pointerup", "pointercancel"] {
+        let st = state.clone();
+        let up = Closure::<dyn FnMut(PointerEvent)>::wrap(Box::new(move |e: PointerEvent| {
+            pointer_up(&mut st.borrow_mut(), e.pointer_id());
+        }));
+        canvas.add_event_listener_with_callback(ev, up.as_ref().unchecked_ref())?;
+        up.forget();
+    }
+    Ok(())
+}
+
+fn pointer_down(s: &mut State, id: i32, pt: (f64, f64), time_ms: f64, additive: bool) {
+    if s.editor.is_some() {
+        editor::pointer_down(s, id, pt);
+        return;
+    }
+    if s.shape_editor.is_some() {
+        shape_editor::pointer_down(s, id, pt);
+        return;
+    }
+    let h = s.canvas.height() as f64;
+    // topmost piece under the pointer
+    let hit = s.z_order.iter().rev().copied().find(|&i| {
+        s.data.pieces[i]
+            .__geom
+            .as_ref()
+            .is_some_and(|g| point_in_polygon(pt, g, h, s.scale, s.offset))
+    });
+    let label = hit.map(|i| s.data.pieces[i].__label_idx);
+
+    // A second finger on a held piece twists it. While a single piece is
+    // held, a touch just beside it counts too, so small pieces stay
+    // twistable; touches further away are pointers of their own.
+    let twist_target = s
+        .drags
+        .iter()
+        .position(|d| d.twist.is_none() && Some(d.label) == label)
+        .or_else(|| {
+            let beside = hit.is_none()
+                && s.drags.len() == 1
+                && s.drags[0].twist.is_none()
+                && piece_index(s, s.drags[0].label).is_some_and(|i| within_reach(s, i, pt));
+            beside.then_some(0)
+        });
+    if let Some(d) = twist_target {
+        s.drags[d].twist = Some((id, pt));
+        return;
+    }
+
+    let (Some(i), Some(label)) = (hit, label) else {
+        // Tapping the empty board clears the selection
+        if !additive && s.drags.is_empty() {
+            select(s, Vec::new());
+            draw(s);
+        }
+        return;
+    };
+    if is_held(s, label) {
+        return;
+    }
+    if additive {
+        toggle(s, i);
+        if !s.selected.contains(&i) {
+            draw(s);
+            return;
+        }
+    } else if !s.selected.contains(&i) {
+        select(s, vec![i]);
+    }
+    let ctr = s.data.pieces[i].__ctr.unwrap_or(Pt { x: 0.0, y: 0.0 });
+    let (sx, sy) = to_screen(ctr, h, s.scale, s.offset);
+    raise(s, i);
+    let members = group(s, i);
+    for &m in &members {
+        s.history.begin(&s.data.pieces[m]);
+    }
+    s.drags.push(Drag {
+        pointer_id: id,
+        label,
+        followers: members.into_iter().filter(|&m| m != i).collect(),
+        off: (pt.0 - sx, pt.1 - sy),
+        pos: pt,
+        twist: None,
+    });
+
+    // Double tap on the same piece flips it
+    let double = s.last_tap.is_some_and(|t| {
+        t.label == label
+            && time_ms - t.time_ms <= DOUBLE_TAP_MS
+            && (pt.0 - t.pos.0).hypot(pt.1 - t.pos.1) <= DOUBLE_TAP_PX
+    });
+    if double {
+        for m in group(s, i) {
+            flip_piece(s, m);
+        }
+        s.last_tap = None;
+    } else {
+        s.last_tap = Some(Tap {
+            label,
+            time_ms,
+            pos: pt,
+        });
+    }
+    draw(s);
+}
+
+// Whether canvas point `pt` is within `TWIST_REACH_PX` of piece `i`'s outline.
+fn within_reach(s: &State, i: usize, pt: (f64, f64)) -> bool {
+    let Some(g) = s.data.pieces[i].__geom.as_ref() else {
+        return false;
+    };
+    let h = s.canvas.height() as f64;
+    let screen: Vec<(f64, f64)> = g
+        .iter()
+        .map(|&p| to_screen(p, h, s.scale, s.offset))
+        .collect();
+    let n = screen.len();
+    (0..n).any(|k| {
+        let (a, b) = (screen[k], screen[(k + 1) % n]);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 > 0.0 {
+            (((pt.0 - a.0) * dx + (pt.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (pt.0 - a.0 - t * dx).hypot(pt.1 - a.1 - t * dy) <= TWIST_REACH_PX
+    })
+}
+
+fn pointer_move(s: &mut State, id: i32, pt: (f64, f64)) {
+    if s.editor.is_some() {
+        editor::pointer_move(s, id, pt);
+        return;
+    }
+    if s.shape_editor.is_some() {
+        shape_editor::pointer_move(s, id, pt);
+        return;
+    }
+    let Some(d) = s
+        .drags
+        .iter()
+        .position(|d| d.pointer_id == id || d.twist.is_some_and(|(t, _)| t == id))
+    else {
+        return;
+    };
+    let drag = s.drags[d].clone();
+    let Some(idx) = piece_index(s, drag.label) else {
+        return;
+    };
+
+    // Twist: rotate by the change in direction of the line between fingers
+    if let Some((tid, tpos)) = drag.twist {
+        let angle = |a: (f64, f64), b: (f64, f64)| (b.1 - a.1).atan2(b.0 - a.0);
+        let before = angle(drag.pos, tpos);
+        let after = if tid == id {
+            angle(drag.pos, pt)
+        } else {
+            angle(pt, tpos)
+        };
+        let mut delta = (after - before).to_degrees();
+        if delta > 180.0 {
+            delta -= 360.0;
+        } else if delta < -180.0 {
+            delta += 360.0;
+        }
+        // Screen y points down, so the on-screen angle runs clockwise
+        for m in std::iter::once(idx).chain(drag.followers.iter().copied()) {
+            rotate_piece(s, m, -delta);
+        }
+        if tid == id {
+            s.drags[d].twist = Some((tid, pt));
+        }
+    }
+
+    if drag.pointer_id == id {
+        s.drags[d].pos = pt;
+        let h = s.canvas.height() as f64;
+        let target = (pt.0 - drag.off.0, pt.1 - drag.off.1);
+        let gp = from_screen(target.0, target.1, h, s.scale, s.offset);
+        // move by center using Rapier sweep-and-slide
+        if let Some(ctr) = s.data.pieces[idx].__ctr {
+            let want = (gp.x - ctr.x, gp.y - ctr.y);
+            let constraints_active = lock_active(s);
+            // Leading pieces move first so they don't block the ones behind
+            let mut members: Vec<usize> = std::iter::once(idx)
+                .chain(drag.followers.iter().copied())
+                .collect();
+            let lead = |s: &State, m: usize| {
+                s.data.pieces[m]
+                    .__ctr
+                    .map_or(0.0, |c| c.x * want.0 + c.y * want.1)
+            };
+            members.sort_by(|&a, &b| lead(s, b).total_cmp(&lead(s, a)));
+            for &m in &members {
+                let (dx, dy) = rapier_allowed_delta(s, m, want.0, want.1, constraints_active);
+                translate_piece(&mut s.data.pieces[m], dx, dy);
+            }
+            if s.magnet_mode {
+                let (sx, sy) = magnet_snap(s, idx, &members);
+                for &m in &drag.followers {
+                    translate_piece(&mut s.data.pieces[m], sx, sy);
+                }
+            }
+        }
+    }
+    draw(s);
+}
+
+fn pointer_up(s: &mut State, id: i32) {
+    if s.editor.is_some() {
+        editor::pointer_up(s, id);
+        return;
+    }
+    if s.shape_editor.is_some() {
+        shape_editor::pointer_up(s, id);
+        return;
+    }
+    if let Some(d) = s
+        .drags
+        .iter()
+        .position(|d| d.twist.is_some_and(|(t, _)| t == id))
+    {
+        s.drags[d].twist = None;
+        let drag = s.drags[d].clone();
+        snap_drag(s, &drag);
+        draw(s);
+        return;
+    }
+    let Some(d) = s.drags.iter().position(|d| d.pointer_id == id) else {
+        return;
+    };
+    let drag = s.drags.remove(d);
+    if drag.twist.is_some() {
+        snap_drag(s, &drag);
+        draw(s);
+    }
+    // The twisting finger takes over the drag; the gesture stays open
+    if let Some((tid, tpos)) = drag.twist
+        && let Some(ctr) = piece_index(s, drag.label).and_then(|i| s.data.pieces[i].__ctr)
+    {
+        let h = s.canvas.height() as f64;
+        let (sx, sy) = to_screen(ctr, h, s.scale, s.offset);
+        s.drags.push(Drag {
+            pointer_id: tid,
+            label: drag.label,
+            followers: drag.followers,
+            off: (tpos.0 - sx, tpos.1 - sy),
+            pos: tpos,
+            twist: None,
+        });
+        return;
+    }
+    let members = piece_index(s, drag.label)
+        .into_iter()
+        .chain(drag.followers.iter().copied());
+    for m in members.collect::<Vec<_>>() {
+        // Q/E still held: the rotation of the selection continues the gesture
+        if s.rot_vel != 0.0 && s.selected.contains(&m) {
+            continue;
+        }
+        let label = s.data.pieces[m].__label_idx;
+        if !is_held(s, label) {
+            commit_piece_gesture(s, label);
+        }
+    }
+    if s.drags.is_empty() {
+        s.guides.clear();
+    }
+    draw(s);
+}
+
+// Land a finished twist on the nearest snap angle.
+fn snap_drag(s: &mut State, drag: &Drag) {
+    let members: Vec<usize> = piece_index(s, drag.label)
+        .into_iter()
+        .chain(drag.followers.iter().copied())
+        .collect();
+    snap_pieces(s, &members);
+}
@@ -0,0 +1,75 @@
+// Solver runs for the loaded puzzle. The search advances a slice of
+// placement checks at a time and yields to the browser in between, so the
+// page keeps drawing and taking input while it runs. Its outcome is kept
+// until another puzzle is loaded; Show solution and hints share it.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Window;
+
+use puzzle_geom::Puzzle;
+use puzzle_geom::solver::{SolveError, Solver};
+
+use crate::constants::SEARCH_SLICE_CHECKS;
+use crate::state::State;
+
+/// The solved puzzle, or why there is none. Made once per puzzle; `None`
+/// when a search is already running or the puzzle was replaced meanwhile.
+pub async fn search(st: &Rc<RefCell<State>>) -> Option<Result<Puzzle, SolveError>> {
+    let (solver, window, loads) = {
+        let mut s = st.borrow_mut();
+        if let Some(done) = &s.searched {
+            return Some(done.clone());
+        }
+        if s.searching {
+            return None;
+        }
+        let solver = Solver::new(&s.data);
+        s.searching = solver.is_ok();
+        (solver, s.window.clone(), s.loads)
+    };
+    let mut solver = match solver {
+        Ok(solver) => solver,
+        Err(e) => {
+            st.borrow_mut().searched = Some(Err(e));
+            return Some(Err(e));
+        }
+    };
+    let done = loop {
+        if let Some(done) = solver.run(SEARCH_SLICE_CHECKS) {
+            break done;
+        }
+        next_turn(&window).await;
+        if st.borrow().loads != loads {
+            return None;
+        }
+    };
+    let mut s = st.borrow_mut();
+    if s.loads != loads {
+        return None;
+    }
+    s.searching = false;
+    s.searched = Some(done.clone());
+    Some(done)
+}
+
+/// What to tell the user when the search found nothing.
+pub fn failure_message(err: SolveError, lang: &str) -> &'static str {
+    match (err, lang == "zh") {
+        (SolveError::LimitReached, true) => "在搜索上限内未找到解答。",
+        (SolveError::LimitReached, false) => "No solution found within the search limit.",
+        (SolveError::NoBoard | SolveError::NoSolution, true) => "未找到解答。",
+        (SolveError::NoBoard | SolveError::NoSolution, false) => "No solution found.",
+    }
+}
+
+// Resolve on a later turn of the event loop, after pending input and
+// redraws.
+async fn next_turn(window: &Window) {
+    let turn = js_sys::Promise::new(&mut |resolve, _| {
+        let _ = window.set_timeout_with_callback(&resolve);
+    });
+    let _ = JsFuture::from(turn).await;
+}
@@ -0,0 +1,67 @@
+// Piece selection and stacking order. Selected pieces are the target of
+// rotate/flip; stacking is kept in `State::z_order` so that raising a piece
+// never reorders `Puzzle::pieces` (and thus never renumbers saved pieces).
+
+use crate::pointer::is_held;
+use crate::state::State;
+
+/// Replace the selection. Pieces that drop out of it close their open
+/// rotation gesture; while Q/E is held, newly selected pieces open one.
+pub fn select(state: &mut State, sel: Vec<usize>) {
+    for &i in &state.selected {
+        let label = state.data.pieces[i].__label_idx;
+        if !sel.contains(&i) && !is_held(state, label) {
+            state.history.commit_piece(label, &state.data.pieces);
+        }
+    }
+    if state.rot_vel != 0.0 {
+        for &i in &sel {
+            state.history.begin(&state.data.pieces[i]);
+        }
+    }
+    state.selected = sel;
+}
+
+/// Add the piece to the selection or remove it (Ctrl/Cmd-click).
+pub fn toggle(state: &mut State, idx: usize) {
+    let mut sel = state.selected.clone();
+    match sel.iter().position(|&i| i == idx) {
+        Some(pos) => {
+            sel.remove(pos);
+        }
+        None => sel.push(idx),
+    }
+    select(state, sel);
+}
+
+/// Select the next piece in input order (Tab), or the previous one.
+pub fn cycle(state: &mut State, back: bool) {
+    let n = state.data.pieces.len();
+    if n == 0 {
+        return;
+    }
+    let next = match state.selected.last() {
+        Some(&i) if back => (i + n - 1) % n,
+        Some(&i) => (i + 1) % n,
+        None if back => n - 1,
+        None => 0,
+    };
+    select(state, vec![next]);
+    raise(state, next);
+}
+
+/// Pieces a transform of `idx` applies to: the whole selection when `idx`
+/// is part of it, otherwise just `idx`.
+pub fn group(state: &State, idx: usize) -> Vec<usize> {
+    if state.selected.contains(&idx) {
+        state.selected.clone()
+    } else {
+        vec![idx]
+    }
+}
+
+/// Draw `idx` above every other piece.
+pub fn raise(state: &mut State, idx: usize) {
+    state.z_order.retain(|&i| i != idx);
+    state.z_order.push(idx);
+}
@@ -0,0 +1,586 @@
+// Shape catalog editor. One catalog entry at a time is edited and previewed
+// at real size (1 mm on the canvas is 1 mm on a typical screen) over a mm
+// grid. Parametric shapes are edited through the side panel; a free-form
+// polygon is drawn vertex by vertex on the canvas, with points snapped to the
+// grid or, with an angle step set, edges held to multiples of that angle and
+// lengths to the grid. Saved entries go into a working copy of the catalog,
+// which the puzzle editor also uses and which can be exported as
+// `shapes.json`.
+
+use wasm_bindgen::prelude::*;
+
+use puzzle_geom::catalog::{ShapeIssue, def_issues};
+use puzzle_geom::constants::MAX_POLYGON_SIDES;
+use puzzle_geom::geom::{bounds_of_points, piece_geom};
+use puzzle_geom::{Piece, Point as Pt, Shape, ShapeDef, ShapesCatalog};
+
+use crate::canvas::{set_fill_style, set_stroke_style};
+use crate::constants::{
+    CSS_PX_PER_MM, EDITOR_COLOR, EDITOR_HANDLE_PX, SHAPE_EDITOR_ANGLE_DEG, SHAPE_EDITOR_GRID_MM,
+};
+use crate::state::{State, with_state};
+use crate::utils::{dispatch_later, from_screen, to_screen};
+use crate::{draw, draw_colored_circle, draw_colored_polygon, save_text_as_file};
+
+pub struct ShapeEditor {
+    /// Working copy of the catalog.
+    pub catalog: ShapesCatalog,
+    /// Catalog entry being edited; `None` for a new shape.
+    pub index: Option<usize>,
+    pub def: ShapeDef,
+    /// Snap grid (mm); 0 turns snapping off.
+    pub grid_mm: f64,
+    /// Polygon edge angle step (degrees); 0 leaves angles free.
+    pub angle_deg: f64,
+    /// Selected polygon vertex.
+    pub vertex: Option<usize>,
+    /// Pointer dragging the selected vertex.
+    pub drag: Option<i32>,
+    /// Point (mm) shown at the canvas center.
+    pub view: Pt,
+}
+
+impl ShapeEditor {
+    fn points(&mut self) -> Option<&mut Vec<[f64; 2]>> {
+        match &mut self.def.shape {
+            Shape::Polygon { points } => Some(points),
+            _ => None,
+        }
+    }
+
+    // Edit entry `index` of the catalog, or a new shape.
+    fn select(&mut self, index: Option<usize>) {
+        self.index = index.filter(|&i| i < self.catalog.shapes.len());
+        self.def = match self.index {
+            Some(i) => self.catalog.shapes[i].clone(),
+            None => ShapeDef {
+                id: String::new(),
+                shape: default_shape("rect").unwrap_or(Shape::Circle { d: 30.0 }),
+                label: None,
+                label_en: None,
+                label_zh: None,
+            },
+        };
+        self.vertex = None;
+        self.drag = None;
+        self.center_view();
+    }
+
+    fn center_view(&mut self) {
+        let (geom, _) = preview_geom(&self.def.shape);
+        self.view = if geom.is_empty() {
+            Pt { x: 0.0, y: 0.0 }
+        } else {
+            let (x0, y0, x1, y1) = bounds_of_points(&geom);
+            Pt {
+                x: (x0 + x1) / 2.0,
+                y: (y0 + y1) / 2.0,
+            }
+        };
+    }
+
+    /// Problems with the entry, including an id taken by another entry.
+    pub fn issues(&self) -> Vec<ShapeIssue> {
+        let mut out = def_issues(&self.def);
+        let taken = self
+            .catalog
+            .shapes
+            .iter()
+            .enumerate()
+            .any(|(i, s)| Some(i) != self.index && s.id == self.def.id);
+        if taken {
+            out.push(ShapeIssue::DuplicateId {
+                id: self.def.id.clone(),
+            });
+        }
+        out
+    }
+}
+
+/// Parameters a new shape of `type_` starts with (sized like the bundled
+/// catalog); a polygon starts empty and is drawn on the canvas.
+fn default_shape(type_: &str) -> Option<Shape> {
+    let json = match type_ {
+        "rect" => r#"{"type":"rect","w":30,"h":30}"#,
+        "equilateral_triangle" => r#"{"type":"equilateral_triangle","side":30}"#,
+        "right_triangle" => r#"{"type":"right_triangle","a":30,"b":30}"#,
+        "regular_polygon" => r#"{"type":"regular_polygon","n":6,"side":30}"#,
+        "circle" => r#"{"type":"circle","d":30}"#,
+        "isosceles_trapezoid" => {
+            r#"{"type":"isosceles_trapezoid","base_bottom":60,"base_top":30,"height":30}"#
+        }
+        "parallelogram" => r#"{"type":"parallelogram","base":60,"offset_top":15,"height":30}"#,
+        "polygon" => r#"{"type":"polygon","points":[]}"#,
+        _ => return None,
+    };
+    serde_json::from_str(json).ok()
+}
+
+// Outline and center of the shape as built for a piece without a pose.
+fn preview_geom(shape: &Shape) -> (Vec<Pt>, Pt) {
+    match shape {
+        Shape::Polygon { points } if points.len() < 3 => (
+            points.iter().map(|v| Pt { x: v[0], y: v[1] }).collect(),
+            Pt::default(),
+        ),
+        _ => piece_geom(&Piece::new(shape.clone())),
+    }
+}
+
+/// Open the shape editor on a new shape, with the loaded catalog.
+pub fn start(state: &mut State) {
+    state.editor = None;
+    let catalog = state.shapes_catalog.clone().unwrap_or_else(|| {
+        serde_json::from_str(include_str!("../../shapes.json")).unwrap_or_default()
+    });
+    let mut ed = ShapeEditor {
+        catalog,
+        index: None,
+        def: ShapeDef {
+            id: String::new(),
+            shape: Shape::Circle { d: 30.0 },
+            label: None,
+            label_en: None,
+            label_zh: None,
+        },
+        grid_mm: SHAPE_EDITOR_GRID_MM,
+        angle_deg: SHAPE_EDITOR_ANGLE_DEG,
+        vertex: None,
+        drag: None,
+        view: Pt::default(),
+    };
+    ed.select(None);
+    state.shape_editor = Some(ed);
+    changed(state);
+}
+
+/// Close the shape editor and show the puzzle again.
+pub fn stop(state: &mut State) {
+    state.shape_editor = None;
+    draw(state);
+}
+
+fn changed(state: &mut State) {
+    draw(state);
+    dispatch_later(&state.window, "shape-editor-change");
+}
+
+/// Real-size preview with the grid; replaces the puzzle while the editor is
+/// open. Sets `scale`/`offset` so pointer input maps to shape coordinates.
+pub fn draw_preview(state: &mut State) {
+    let Some(ed) = &state.shape_editor else {
+        return;
+    };
+    let (w, h) = (state.canvas.width() as f64, state.canvas.height() as f64);
+    let css_w = state.canvas.client_width().max(1) as f64;
+    let dpr = if css_w > 1.0 { w / css_w } else { 1.0 };
+    let scale = CSS_PX_PER_MM * dpr;
+    state.scale = scale;
+    state.offset = (w / 2.0 - ed.view.x * scale, h / 2.0 - ed.view.y * scale);
+    let offset = state.offset;
+    let ctx = &state.ctx;
+    ctx.clear_rect(0.0, 0.0, w, h);
+
+    // Grid, with the axes through the origin drawn darker
+    let step = if ed.grid_mm > 0.0 { ed.grid_mm } else { 10.0 };
+    if step * scale >= 6.0 {
+        let lo = from_screen(0.0, h, h, scale, offset);
+        let hi = from_screen(w, 0.0, h, scale, offset);
+        ctx.set_line_width(1.0);
+        set_stroke_style(ctx, "#e6e6e6");
+        ctx.begin_path();
+        let mut x = (lo.x / step).floor() * step;
+        while x <= hi.x {
+            let (sx, _) = to_screen(Pt { x, y: 0.0 }, h, scale, offset);
+            ctx.move_to(sx, 0.0);
+            ctx.line_to(sx, h);
+            x += step;
+        }
+        let mut y = (lo.y / step).floor() * step;
+        while y <= hi.y {
+            let (_, sy) = to_screen(Pt { x: 0.0, y }, h, scale, offset);
+            ctx.move_to(0.0, sy);
+            ctx.line_to(w, sy);
+            y += step;
+        }
+        ctx.stroke();
+    }
+    let (ox, oy) = to_screen(Pt::default(), h, scale, offset);
+    set_stroke_style(ctx, "#b0b0b0");
+    ctx.begin_path();
+    ctx.move_to(ox, 0.0);
+    ctx.line_to(ox, h);
+    ctx.move_to(0.0, oy);
+    ctx.line_to(w, oy);
+    ctx.stroke();
+
+    // The shape itself
+    let color = blueprint_core::piece_color(0);
+    let (geom, ctr) = preview_geom(&ed.def.shape);
+    if let Some(r) = ed.def.shape.circle_radius() {
+        draw_colored_circle(ctx, h, ctr, r, scale, offset, &color);
+    } else if geom.len() >= 3 {
+        draw_colored_polygon(ctx, h, &geom, false, scale, offset, &color);
+    }
+    if let Shape::Polygon { points } = &ed.def.shape {
+        set_stroke_style(ctx, EDITOR_COLOR);
+        ctx.set_line_width(1.5);
+        ctx.begin_path();
+        for (i, v) in points.iter().enumerate() {
+            let (x, y) = to_screen(Pt { x: v[0], y: v[1] }, h, scale, offset);
+            if i == 0 {
+                ctx.move_to(x, y);
+            } else {
+                ctx.line_to(x, y);
+            }
+        }
+        ctx.stroke();
+        for (i, v) in points.iter().enumerate() {
+            let (x, y) = to_screen(Pt { x: v[0], y: v[1] }, h, scale, offset);
+            ctx.begin_path();
+            ctx.rect(x - 5.0, y - 5.0, 10.0, 10.0);
+            let chosen = ed.vertex == Some(i);
+            set_fill_style(ctx, if chosen { EDITOR_COLOR } else { "#fff" });
+            ctx.fill();
+            ctx.stroke();
+        }
+    }
+
+    // Overall size
+    if !geom.is_empty() {
+        let (x0, y0, x1, y1) = bounds_of_points(&geom);
+        let size = (12.0 * dpr).round();
+        ctx.set_font(&format!("{}px sans-serif", size));
+        ctx.set_text_align("left");
+        ctx.set_text_baseline("bottom");
+        set_fill_style(ctx, "#555");
+        let text = format!("{:.1} × {:.1} mm", x1 - x0, y1 - y0);
+        let _ = ctx.fill_text(&text, 8.0 * dpr, h - 8.0 * dpr);
+    }
+}
+
+pub fn pointer_down(state: &mut State, id: i32, pt: (f64, f64)) {
+    let h = state.canvas.height() as f64;
+    let at = from_screen(pt.0, pt.1, h, state.scale, state.offset);
+    let tol = EDITOR_HANDLE_PX / state.scale;
+    let Some(ed) = state.shape_editor.as_mut() else {
+        return;
+    };
+    let (grid, angle, selected) = (ed.grid_mm, ed.angle_deg, ed.vertex);
+    let Some(points) = ed.points() else {
+        return;
+    };
+    let hit = points
+        .iter()
+        .position(|v| (v[0] - at.x).hypot(v[1] - at.y) <= tol);
+    let v = match hit {
+        Some(v) => v,
+        None => {
+            let pos = selected.map_or(points.len(), |v| v + 1);
+            let prev = pos.checked_sub(1).map(|i| points[i]);
+            points.insert(pos, constrain(prev, at, grid, angle));
+            pos
+        }
+    };
+    ed.vertex = Some(v);
+    ed.drag = Some(id);
+    changed(state);
+}
+
+pub fn pointer_move(state: &mut State, id: i32, pt: (f64, f64)) {
+    let h = state.canvas.height() as f64;
+    let at = from_screen(pt.0, pt.1, h, state.scale, state.offset);
+    let Some(ed) = state.shape_editor.as_mut() else {
+        return;
+    };
+    let (grid, angle) = (ed.grid_mm, ed.angle_deg);
+    let (Some(v), Some(drag)) = (ed.vertex, ed.drag) else {
+        return;
+    };
+    if drag != id {
+        return;
+    }
+    let Some(points) = ed.points().filter(|p| v < p.len()) else {
+        return;
+    };
+    // Constrained relative to the vertex before it along the outline
+    let n = points.len();
+    let prev = (n > 1).then(|| points[(v + n - 1) % n]);
+    let to = constrain(prev, at, grid, angle);
+    if points[v] == to {
+        return;
+    }
+    points[v] = to;
+    changed(state);
+}
+
+pub fn pointer_up(state: &mut State, id: i32) {
+    if let Some(ed) = state.shape_editor.as_mut()
+        && ed.drag == Some(id)
+    {
+        ed.drag = None;
+    }
+}
+
+/// Delete/Backspace removes the selected vertex, Escape clears the
+/// selection so the next click starts after the last vertex.
+pub fn key(state: &mut State, key: &str) {
+    match key {
+        "delete" | "backspace" => delete_vertex(state),
+        "escape" => {
+            if let Some(ed) = state.shape_editor.as_mut() {
+                ed.vertex = None;
+            }
+            changed(state);
+        }
+        _ => {}
+    }
+}
+
+fn delete_vertex(state: &mut State) {
+    let Some(ed) = state.shape_editor.as_mut() else {
+        return;
+    };
+    let Some(v) = ed.vertex else {
+        return;
+    };
+    if let Some(points) = ed.points()
+        && v < points.len()
+    {
+        points.remove(v);
+        let left = !points.is_empty();
+        ed.vertex = v.checked_sub(1).or(left.then_some(0));
+    }
+    changed(state);
+}
+
+// Pointer position `at` as a polygon vertex. With an angle step the edge from
+// `prev` keeps to that step and its length to the grid; otherwise the point
+// snaps to the grid. Coordinates are rounded to 0.001 mm (and -0 to 0).
+fn constrain(prev: Option<[f64; 2]>, at: Pt, grid: f64, angle_deg: f64) -> [f64; 2] {
+    let snap = |v: f64| {
+        if grid > 0.0 {
+            (v / grid).round() * grid
+        } else {
+            v
+        }
+    };
+    let (x, y) = match prev {
+        Some([px, py]) if angle_deg > 0.0 => {
+            let (dx, dy) = (at.x - px, at.y - py);
+            let step = angle_deg.to_radians();
+            let ang = (dy.atan2(dx) / step).round() * step;
+            let len = snap(dx.hypot(dy));
+            (px + len * ang.cos(), py + len * ang.sin())
+        }
+        _ => (snap(at.x), snap(at.y)),
+    };
+    let round = |v: f64| (v * 1000.0).round() / 1000.0 + 0.0;
+    [round(x), round(y)]
+}
+
+// English wording comes from the issue itself.
+fn issue_text(issue: &ShapeIssue, zh: bool) -> String {
+    if !zh {
+        return issue.to_string();
+    }
+    match issue {
+        ShapeIssue::MissingId => "形状缺少 id".to_string(),
+        ShapeIssue::DuplicateId { id } => format!("id {:?} 已被使用", id),
+        ShapeIssue::BadSize { field } => format!("{} 必须是正数", field),
+        ShapeIssue::TooFewSides { n } => format!("正多边形至少需要 3 条边，而不是 {}", n),
+        ShapeIssue::TooManySides { n } => {
+            format!("正多边形最多 {} 条边，而不是 {}", MAX_POLYGON_SIDES, n)
+        }
+        ShapeIssue::TooFewPoints { count } => format!("多边形至少需要 3 个顶点，当前 {} 个", count),
+        ShapeIssue::RepeatedPoint { index } => format!("顶点 {} 与前一个顶点重合", index + 1),
+        ShapeIssue::SelfIntersecting { a, b } => {
+            format!("第 {} 条边与第 {} 条边相交或相碰", a + 1, b + 1)
+        }
+        ShapeIssue::ZeroArea => "轮廓没有围出面积".to_string(),
+    }
+}
+
+// ---- JS API used by the shape editor panel ----
+
+fn with_editor(f: impl FnOnce(&mut ShapeEditor)) {
+    with_state(|s| {
+        if let Some(ed) = s.shape_editor.as_mut() {
+            f(ed);
+            changed(s);
+        }
+    });
+}
+
+/// Open the shape editor on a new shape.
+#[wasm_bindgen]
+pub fn shape_editor_start() {
+    with_state(start);
+}
+
+/// Close the shape editor; saved shapes stay in the loaded catalog.
+#[wasm_bindgen]
+pub fn shape_editor_stop() {
+    with_state(stop);
+}
+
+/// Editor state for the panel as JSON: `{ index, def, issues, grid_mm,
+/// angle_deg, vertex, shapes: [{ id, label_en, label_zh }] }`, where `def`
+/// is the entry as it would be saved and `issues` are messages in the
+/// current language.
+#[wasm_bindgen]
+pub fn shape_editor_state() -> String {
+    with_state(|s| {
+        let zh = s.lang == "zh";
+        let Some(ed) = &s.shape_editor else {
+            return "null".to_string();
+        };
+        let shapes: Vec<_> = ed
+            .catalog
+            .shapes
+            .iter()
+            .map(|s| serde_json::json!({ "id": s.id, "label_en": s.label_en, "label_zh": s.label_zh }))
+            .collect();
+        let issues: Vec<String> = ed.issues().iter().map(|e| issue_text(e, zh)).collect();
+        serde_json::json!({
+            "index": ed.index,
+            "def": ed.def,
+            "issues": issues,
+            "grid_mm": ed.grid_mm,
+            "angle_deg": ed.angle_deg,
+            "vertex": ed.vertex,
+            "shapes": shapes,
+        })
+        .to_string()
+    })
+    .unwrap_or_else(|| "null".to_string())
+}
+
+/// Edit catalog entry `index`; a negative index starts a new shape.
+#[wasm_bindgen]
+pub fn shape_editor_select(index: i32) {
+    with_editor(|ed| ed.select(usize::try_from(index).ok()));
+}
+
+/// Change the shape type, starting from that type's default parameters.
+#[wasm_bindgen]
+pub fn shape_editor_set_type(type_: String) {
+    with_editor(|ed| {
+        if let Some(shape) = default_shape(&type_) {
+            ed.def.shape = shape;
+            ed.vertex = None;
+            ed.center_view();
+        }
+    });
+}
+
+/// Set a numeric parameter of the shape (e.g. `"w"` or `"n"`). Unknown
+/// parameters and values the type cannot take are ignored.
+#[wasm_bindgen]
+pub fn shape_editor_set_param(key: String, value: f64) {
+    with_editor(|ed| {
+        let Ok(serde_json::Value::Object(mut obj)) = serde_json::to_value(&ed.def.shape) else {
+            return;
+        };
+        if key == "type" || key == "points" || !obj.contains_key(&key) {
+            return;
+        }
+        let value = if key == "n" {
+            serde_json::json!(value.max(0.0).round() as u32)
+        } else {
+            serde_json::json!(value)
+        };
+        obj.insert(key, value);
+        if let Ok(shape) = serde_json::from_value(serde_json::Value::Object(obj)) {
+            ed.def.shape = shape;
+        }
+    });
+}
+
+/// Set `"id"`, `"label_en"` or `"label_zh"`; an empty label is removed.
+#[wasm_bindgen]
+pub fn shape_editor_set_text(key: String, text: String) {
+    with_editor(|ed| {
+        let label = (!text.trim().is_empty()).then(|| text.clone());
+        match key.as_str() {
+            "id" => ed.def.id = text.trim().to_string(),
+            "label_en" => ed.def.label_en = label,
+            "label_zh" => ed.def.label_zh = label,
+            _ => {}
+        }
+    });
+}
+
+/// Polygon drawing constraints: grid (mm) and edge angle step (degrees);
+/// 0 turns either off.
+#[wasm_bindgen]
+pub fn shape_editor_set_constraints(grid_mm: f64, angle_deg: f64) {
+    with_editor(|ed| {
+        ed.grid_mm = grid_mm.max(0.0);
+        ed.angle_deg = angle_deg.clamp(0.0, 180.0);
+    });
+}
+
+/// Remove the selected polygon vertex.
+#[wasm_bindgen]
+pub fn shape_editor_delete_vertex() {
+    with_state(delete_vertex);
+}
+
+/// Store the entry in the catalog (replacing the one being edited). Fails
+/// with the first problem when the shape is not usable.
+#[wasm_bindgen]
+pub fn shape_editor_save() -> Result<(), JsValue> {
+    with_state(|s| {
+        let zh = s.lang == "zh";
+        let Some(ed) = s.shape_editor.as_mut() else {
+            return Err(JsValue::from_str("Shape editor is not open"));
+        };
+        if let Some(issue) = ed.issues().first() {
+            return Err(JsValue::from_str(&issue_text(issue, zh)));
+        }
+        match ed.index {
+            Some(i) => ed.catalog.shapes[i] = ed.def.clone(),
+            None => {
+                ed.catalog.shapes.push(ed.def.clone());
+                ed.index = Some(ed.catalog.shapes.len() - 1);
+            }
+        }
+        s.shapes_catalog = Some(ed.catalog.clone());
+        changed(s);
+        Ok(())
+    })
+    .unwrap_or_else(|| Err(JsValue::from_str("State not initialized")))
+}
+
+/// Remove the entry being edited from the catalog.
+#[wasm_bindgen]
+pub fn shape_editor_remove() {
+    with_state(|s| {
+        let Some(ed) = s.shape_editor.as_mut() else {
+            return;
+        };
+        if let Some(i) = ed.index {
+            ed.catalog.shapes.remove(i);
+            ed.select(None);
+            s.shapes_catalog = Some(ed.catalog.clone());
+        }
+        changed(s);
+    });
+}
+
+/// Download the catalog as `shapes.json` and return the text.
+#[wasm_bindgen]
+pub fn shape_editor_export() -> Result<String, JsValue> {
+    with_state(|s| {
+        let ed = s
+            .shape_editor
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("Shape editor is not open"))?;
+        let text = serde_json::to_string_pretty(&ed.catalog)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        save_text_as_file(&s.document, "shapes.json", &text)?;
+        Ok(text)
+    })
+    .unwrap_or_else(|| Err(JsValue::from_str("State not initialized")))
+}
@@ -0,0 +1,177 @@
+// Rotation snapping. A continuous rotation (held Q/E, two-finger twist) is
+// released onto the nearest target angle, and in tap-to-step mode every Q/E
+// press jumps straight to the next target.
+
+use puzzle_geom::Point as Pt;
+use puzzle_geom::geom::{board_obstacles_geom, board_to_geom, piece_geom};
+
+use crate::lock::{lock_active, rotate_piece};
+use crate::state::State;
+
+// Tolerance (deg) so a piece already on a target steps to the next one.
+const EPS_DEG: f64 = 1e-6;
+// Board outlines with more edges than this are curves, not references.
+const MAX_REF_EDGES: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapMode {
+    Off,
+    /// Multiples of `SnapSettings::step_deg`.
+    Angle,
+    /// Rotations that make one of the piece's edges parallel to a board
+    /// edge or to an axis.
+    Edges,
+}
+
+impl SnapMode {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "angle" => SnapMode::Angle,
+            "edges" => SnapMode::Edges,
+            _ => SnapMode::Off,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SnapMode::Off => "off",
+            SnapMode::Angle => "angle",
+            SnapMode::Edges => "edges",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SnapSettings {
+    pub mode: SnapMode,
+    /// Snap multiple for `SnapMode::Angle`, and the step size when
+    /// stepping without snapping (deg).
+    pub step_deg: f64,
+    /// Q/E rotate by one step per press instead of continuously.
+    pub tap_step: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        SnapSettings {
+            mode: SnapMode::Off,
+            step_deg: 15.0,
+            tap_step: false,
+        }
+    }
+}
+
+/// Snap the rotation of the given pieces to the nearest target.
+pub fn snap_pieces(state: &mut State, pieces: &[usize]) {
+    for &i in pieces {
+        let r = snapped_rotation(state, i);
+        turn_to(state, i, r);
+    }
+}
+
+/// Rotate the given pieces by one step; `dir` is 1 for counter-clockwise
+/// (Q) and -1 for clockwise (E).
+pub fn step_pieces(state: &mut State, pieces: &[usize], dir: f64) {
+    for &i in pieces {
+        let r = stepped_rotation(state, i, dir);
+        turn_to(state, i, r);
+    }
+}
+
+// Turn towards `r`, stopping at first contact in lock mode.
+fn turn_to(state: &mut State, idx: usize, r: f64) {
+    if lock_active(state) {
+        let current = state.data.pieces[idx].rotation.unwrap_or(0.0);
+        rotate_piece(state, idx, r - current);
+    } else {
+        state.data.pieces[idx].rotation = Some(r);
+    }
+}
+
+fn snapped_rotation(state: &State, idx: usize) -> f64 {
+    let r = state.data.pieces[idx].rotation.unwrap_or(0.0);
+    let deg = state.snap.step_deg.max(1.0);
+    match state.snap.mode {
+        SnapMode::Off => r,
+        SnapMode::Angle => (r / deg).round() * deg,
+        SnapMode::Edges => {
+            r + edge_deltas(state, idx)
+                .into_iter()
+                .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0)
+        }
+    }
+}
+
+fn stepped_rotation(state: &State, idx: usize, dir: f64) -> f64 {
+    let r = state.data.pieces[idx].rotation.unwrap_or(0.0);
+    let deg = state.snap.step_deg.max(1.0);
+    match state.snap.mode {
+        SnapMode::Off => r + dir * deg,
+        SnapMode::Angle => {
+            let k = r / deg;
+            let next = if dir > 0.0 {
+                (k + EPS_DEG).floor() + 1.0
+            } else {
+                (k - EPS_DEG).ceil() - 1.0
+            };
+            next * deg
+        }
+        SnapMode::Edges => {
+            // Smallest turn in `dir` that lands on a target; targets repeat
+            // every 180° so one always exists within half a turn.
+            let turn = edge_deltas(state, idx)
+                .into_iter()
+                .map(|d| {
+                    let t = (dir * d).rem_euclid(180.0);
+                    if t < EPS_DEG { 180.0 } else { t }
+                })
+                .min_by(f64::total_cmp)
+                .unwrap_or(deg);
+            r + dir * turn
+        }
+    }
+}
+
+// Rotation deltas (deg, in (-90, 90]) that bring one of the piece's edges
+// parallel to a board edge or an axis. Circles have no edges to align.
+fn edge_deltas(state: &State, idx: usize) -> Vec<f64> {
+    let p = &state.data.pieces[idx];
+    if p.shape.circle_radius().is_some() {
+        return Vec::new();
+    }
+    let (geom, _ctr) = piece_geom(p);
+    let mut refs = vec![0.0, 90.0];
+    if let Some(b) = &state.data.board {
+        let polys = board_to_geom(b).unwrap_or_default();
+        for poly in polys.iter().chain(&board_obstacles_geom(b)) {
+            if poly.len() <= MAX_REF_EDGES {
+                refs.extend(edge_angles(poly));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for e in edge_angles(&geom) {
+        for r in &refs {
+            out.push(wrap_half_turn(r - e));
+        }
+    }
+    out
+}
+
+fn edge_angles(poly: &[Pt]) -> Vec<f64> {
+    let n = poly.len();
+    (0..n)
+        .filter_map(|i| {
+            let (a, b) = (poly[i], poly[(i + 1) % n]);
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            (dx.hypot(dy) > 1e-6).then(|| dy.atan2(dx).to_degrees())
+        })
+        .collect()
+}
+
+// Wrap into (-90, 90]; edge directions repeat every 180°.
+fn wrap_half_turn(d: f64) -> f64 {
+    let x = d.rem_euclid(180.0);
+    if x > 90.0 { x - 180.0 } else { x }
+}
@@ -3,34 +3,121 @@ use std::rc::Rc;
 
 use web_sys::{CanvasRenderingContext2d, Document, HtmlCanvasElement, Window};
 
-use crate::models::{Puzzle, ShapesCatalog};
+use puzzle_geom::solver::SolveError;
+use puzzle_geom::validate::ValidationReport;
+use puzzle_geom::{CountsSpec, Piece, Puzzle, ShapesCatalog};
+
+use crate::editor::Editor;
+use crate::hint::Hint;
+use crate::history::History;
+use crate::magnet::Guide;
+use crate::physics::LockWorld;
+use crate::shape_editor::ShapeEditor;
+use crate::snap::SnapSettings;
 
 /// Global application state stored behind an `Rc<RefCell<_>>` so it can be
 /// shared across the WASM callbacks.
-#[derive(Clone)]
 pub struct State {
     pub window: Window,
     pub document: Document,
     pub canvas: HtmlCanvasElement,
     pub ctx: CanvasRenderingContext2d,
     pub data: Puzzle,
+    /// Counts spec `data` was built from, when it was loaded from one; the
+    /// puzzle editor starts from it.
+    pub spec: Option<CountsSpec>,
     pub puzzle_name: String,
-    pub dragging_idx: Option<usize>,
-    pub drag_off: (f64, f64),
+    /// Selected pieces (indices into `data.pieces`), targets of rotate/flip.
+    pub selected: Vec<usize>,
+    /// Drawing order, bottom to top (indices into `data.pieces`).
+    pub z_order: Vec<usize>,
+    pub drags: Vec<Drag>,
+    pub last_tap: Option<Tap>,
     pub scale: f64,
     pub offset: (f64, f64),
     pub rot_vel: f64,
     pub slow_mode: bool,
     pub rot_speed_fast: f64,
     pub rot_speed_slow: f64,
+    pub snap: SnapSettings,
     pub restrict_mode: bool,
+    /// Magnet mode: dragged pieces snap flush to nearby edges and vertices.
+    pub magnet_mode: bool,
+    /// Snap targets shown while dragging in magnet mode.
+    pub guides: Vec<Guide>,
     pub shift_down: bool,
     pub initial_data: Puzzle,
     pub lang: String,
     pub shapes_catalog: Option<ShapesCatalog>,
+    pub history: History,
+    /// Collision world for lock mode, kept in sync with `data.pieces`.
+    pub lock_world: LockWorld,
+    /// Known solution drawn as an overlay (index into `data.solutions`).
+    pub solution_overlay: Option<usize>,
+    /// The pieces posed as that solution, with their outlines cached.
+    pub overlay_pieces: Vec<Piece>,
+    /// Hint currently shown.
+    pub hint: Option<Hint>,
+    /// Hints given since the puzzle was loaded, for teachers.
+    pub hints_used: u32,
+    /// Outcome of the solver search for the loaded puzzle (see `search`).
+    pub searched: Option<Result<Puzzle, SolveError>>,
+    /// A solver search is running in the background.
+    pub searching: bool,
+    /// Puzzles loaded so far; a search started for an earlier one stops.
+    pub loads: u32,
+    /// Validation of the layout as of the last command; see `Validation`.
+    pub validation: Option<Validation>,
+    /// Puzzle editor, while open.
+    pub editor: Option<Editor>,
+    /// Shape catalog editor, while open. It replaces the puzzle on the
+    /// canvas.
+    pub shape_editor: Option<ShapeEditor>,
+}
+
+/// A piece held by one pointer (mouse, pen or finger).
+#[derive(Clone, Debug)]
+pub struct Drag {
+    pub pointer_id: i32,
+    /// `__label_idx` of the held piece; indices shift when pieces are raised.
+    pub label: Option<usize>,
+    /// Other selected pieces moving along with the held one.
+    pub followers: Vec<usize>,
+    /// Pointer offset from the piece center (canvas px).
+    pub off: (f64, f64),
+    /// Last pointer position (canvas px).
+    pub pos: (f64, f64),
+    /// Second finger twisting the piece: pointer id and last position.
+    pub twist: Option<(i32, (f64, f64))>,
+}
+
+/// Last tap on a piece, for double-tap detection.
+#[derive(Clone, Copy, Debug)]
+pub struct Tap {
+    pub label: Option<usize>,
+    pub time_ms: f64,
+    pub pos: (f64, f64),
+}
+
+/// Validation results for the layout at one history revision. The report
+/// holds the costly coverage union; the known solution matched is looked up
+/// along with it.
+#[derive(Clone, Debug)]
+pub struct Validation {
+    pub revision: u64,
+    pub report: ValidationReport,
+    /// Index of the known solution the layout matches, if any.
+    pub solution: Option<usize>,
 }
 
 // Thread local storage for the single runtime state instance.
 thread_local! {
     pub static STATE: RefCell<Option<Rc<RefCell<State>>>> = const { RefCell::new(None) };
 }
+
+/// Run `f` on the runtime state, if the app has started.
+pub fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> Option<R> {
+    let state = STATE.with(|st| st.borrow().as_ref().cloned())?;
+    let mut s = state.borrow_mut();
+    Some(f(&mut s))
+}
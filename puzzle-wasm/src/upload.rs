@@ -6,12 +6,12 @@ use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
 use web_sys::{Document, Event, FileReader, HtmlInputElement, Window};
 
-use crate::models::{CountsSpec, Puzzle, ShapesCatalog};
+use puzzle_geom::layout::build_puzzle_from_counts;
+use puzzle_geom::{CountsSpec, Puzzle, ShapesCatalog};
+
 use crate::state::State;
 use crate::utils::{asset_url, log};
-use crate::{
-    assign_piece_colors, build_puzzle_from_counts, draw, update_note_dom, update_status_dom,
-};
+use crate::{apply_url_seed, draw, puzzle_loaded, update_note_dom, update_status_dom};
 
 // Shared loader for puzzle JSON text (counts format or full puzzle)
 pub async fn load_puzzle_from_text(state: Rc<RefCell<State>>, text: String) {
@@ -60,36 +60,43 @@ pub async fn load_puzzle_from_text(state: Rc<RefCell<State>>, text: String) {
         };
         match serde_json::from_str::<ShapesCatalog>(&shapes_text) {
             Ok(catalog) => {
-                let p = build_puzzle_from_counts(&spec, &catalog);
+                let mut p = build_puzzle_from_counts(&spec, &catalog);
+                apply_url_seed(&win, &mut p);
                 let mut s = st_clone.borrow_mut();
                 s.data = p;
+                s.spec = Some(spec);
                 s.shapes_catalog = Some(catalog);
-                assign_piece_colors(&mut s.data);
-                s.initial_data = s.data.clone();
+                puzzle_loaded(&mut s);
                 s.puzzle_name = "local".to_string();
                 update_note_dom(&s);
                 update_status_dom(&s);
                 draw(&mut s);
             }
             Err(e) => {
-                log(&format!("Failed to parse shapes catalog: {}", e));
+                // A bad catalog entry must not silently drop pieces.
+                let msg = format!("Failed to parse shapes catalog: {}", e);
+                log(&msg);
+                let _ = st_clone.borrow().window.alert_with_message(&msg);
             }
         }
-    } else if let Ok(p) = serde_json::from_str::<Puzzle>(&text) {
-        let mut s = state.borrow_mut();
-        s.data = p;
-        assign_piece_colors(&mut s.data);
-        s.initial_data = s.data.clone();
-        s.puzzle_name = "local".to_string();
-        update_note_dom(&s);
-        update_status_dom(&s);
-        draw(&mut s);
     } else {
-        log("Unrecognized puzzle JSON format");
-        let _ = state
-            .borrow()
-            .window
-            .alert_with_message("Unrecognized puzzle JSON format.");
+        match serde_json::from_str::<Puzzle>(&text) {
+            Ok(p) => {
+                let mut s = state.borrow_mut();
+                s.data = p;
+                s.spec = None;
+                puzzle_loaded(&mut s);
+                s.puzzle_name = "local".to_string();
+                update_note_dom(&s);
+                update_status_dom(&s);
+                draw(&mut s);
+            }
+            Err(e) => {
+                let msg = format!("Unrecognized puzzle JSON format: {}", e);
+                log(&msg);
+                let _ = state.borrow().window.alert_with_message(&msg);
+            }
+        }
     }
 }
 
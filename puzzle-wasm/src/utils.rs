@@ -1,7 +1,9 @@
+use wasm_bindgen::closure::Closure;
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::Window;
+use web_sys::{Event, Window};
+
+use puzzle_geom::Point;
 
-use crate::models::Point;
 use crate::state::State;
 
 /// Log a message to the browser console.
@@ -9,6 +11,20 @@ pub fn log(s: &str) {
     web_sys::console::log_1(&JsValue::from_str(s));
 }
 
+/// Dispatch the event `name` on `window` as a microtask, once the state
+/// borrow of the current call is released, so listeners can call back into
+/// the exported functions.
+pub fn dispatch_later(window: &Window, name: &str) {
+    let target = window.clone();
+    let name = name.to_string();
+    let cb = Closure::once_into_js(move || {
+        if let Ok(e) = Event::new(&name) {
+            let _ = target.dispatch_event(&e);
+        }
+    });
+    window.queue_microtask(cb.unchecked_ref());
+}
+
 /// Ensure the canvas backing store matches the CSS size and device pixel ratio
 /// to prevent non-uniform stretching.
 pub fn sync_canvas_size(state: &mut State) {
@@ -108,6 +124,22 @@ pub fn get_query_param(search: &str, key: &str) -> Option<String> {
     None
 }
 
+/// `search` with `key` set to `value`, replacing an existing entry.
+pub fn set_query_param(search: &str, key: &str, value: &str) -> String {
+    let s = search.trim_start_matches('?');
+    let mut pairs: Vec<String> = s
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(key))
+        .map(str::to_string)
+        .collect();
+    pairs.push(format!(
+        "{}={}",
+        key,
+        percent_encoding::utf8_percent_encode(value, percent_encoding::NON_ALPHANUMERIC)
+    ));
+    format!("?{}", pairs.join("&"))
+}
+
 fn url_decode(s: &str) -> String {
     percent_encoding::percent_decode_str(s)
         .decode_utf8()
@@ -4,7 +4,7 @@ import { ThemeToggle } from "../theme/ThemeToggle";
 import { TutorModal } from "./TutorModal";
 import Home from "./Home";
 import { takeUploadedPuzzle } from "../utils/localFile";
-import type { PuzzleWasm } from "../global";
+import type { EditorState, PuzzleWasm, ShapeEditorState } from "../global";
 
 // WASM bootstrapping: we will dynamically import the wasm-pack JS from /public
 // so Vite doesn't try to process it. See useEffect below.
@@ -41,6 +41,17 @@ const PuzzleApp: React.FC<{
 }> = ({ lang, setLang, t }) => {
   const [ready, setReady] = useState(false);
   const [showTutor, setShowTutor] = useState(false);
+  const [mode, setMode] = useState<EditMode>("play");
+  // Close the open editor (if any) and open `next`; the same mode toggles off
+  const switchMode = (next: EditMode) => {
+    const wasm = window.__puzzleWasm;
+    if (mode === "board") wasm?.editor_stop();
+    if (mode === "shapes") wasm?.shape_editor_stop();
+    const to = next === mode ? "play" : next;
+    if (to === "board") wasm?.editor_start();
+    if (to === "shapes") wasm?.shape_editor_start();
+    setMode(to);
+  };
 
   const syncLang = useCallback(() => {
     const sel = document.getElementById("langSel") as HTMLSelectElement | null;
@@ -185,6 +196,74 @@ const PuzzleApp: React.FC<{
                   </svg>
                   <span>{t.reset}</span>
                 </button>
+                <button
+                  id="undoBtn"
+                  className="icon-btn"
+                  title={`${t.undo} (Ctrl+Z)`}
+                  type="button"
+                  disabled
+                  onClick={() => window.__puzzleWasm?.undo()}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M12.5 8c-2.65 0-5.05 1-6.9 2.6L2 7v9h9l-3.62-3.62A7.95 7.95 0 0112.5 10c3.54 0 6.55 2.31 7.6 5.5l2.37-.78A10.02 10.02 0 0012.5 8z" />
+                  </svg>
+                  <span>{t.undo}</span>
+                </button>
+                <button
+                  id="redoBtn"
+                  className="icon-btn"
+                  title={`${t.redo} (Ctrl+Shift+Z)`}
+                  type="button"
+                  disabled
+                  onClick={() => window.__puzzleWasm?.redo()}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M18.4 10.6A10.02 10.02 0 0011.5 8c-4.47 0-8.24 2.92-9.47 6.72l2.37.78A8.01 8.01 0 0111.5 10c1.95 0 3.73.72 5.12 1.88L13 15.5h9v-9l-3.6 3.1z" />
+                  </svg>
+                  <span>{t.redo}</span>
+                </button>
+                <button
+                  id="trayBtn"
+                  className="icon-btn"
+                  title={t.tray}
+                  type="button"
+                  onClick={() => window.__puzzleWasm?.return_to_tray()}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M19 3H5a2 2 0 00-2 2v14a2 2 0 002 2h14a2 2 0 002-2V5a2 2 0 00-2-2zm0 12h-4a3 3 0 01-6 0H5V5h14v10z" />
+                  </svg>
+                  <span>{t.tray}</span>
+                </button>
+                <button
+                  id="scrambleBtn"
+                  className="icon-btn"
+                  title={t.scramble}
+                  type="button"
+                  onClick={() => window.__puzzleWasm?.scramble()}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M10.59 9.17L5.41 4 4 5.41l5.17 5.17 1.42-1.41zM14.5 4l2.04 2.04L4 18.59 5.41 20 17.96 7.46 20 9.5V4h-5.5zm.33 9.41l-1.41 1.41 3.13 3.13L14.5 20H20v-5.5l-2.04 2.04-3.13-3.13z" />
+                  </svg>
+                  <span>{t.scramble}</span>
+                </button>
+                <button
+                  id="hintBtn"
+                  className="icon-btn"
+                  title={t.hint}
+                  type="button"
+                  onClick={() => window.__puzzleWasm?.hint()}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M11 18h2v-2h-2v2zm1-16C6.48 2 2 6.48 2 12s4.48 10 10 10 10-4.48 10-10S17.52 2 12 2zm0 18c-4.41 0-8-3.59-8-8s3.59-8 8-8 8 3.59 8 8-3.59 8-8 8zm0-14c-2.21 0-4 1.79-4 4h2c0-1.1.9-2 2-2s2 .9 2 2c0 2-3 1.75-3 5h2c0-2.25 3-2.5 3-5 0-2.21-1.79-4-4-4z" />
+                  </svg>
+                  <span>{t.hint}</span>
+                </button>
+                <button id="showSolution" className="icon-btn" title={t.solution} type="button">
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M9 21h6v-1H9v1zm3-19a7 7 0 00-4 12.74V17h8v-2.26A7 7 0 0012 2z" />
+                  </svg>
+                  <span>{t.solution}</span>
+                </button>
                 <button id="exportPng" className="icon-btn" title={t.download} type="button">
                   <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
                     <path d="M5 20h14v-2H5v2zm7-18l-5.5 5.5h3.5V15h4V7.5H17.5L12 2z" />
@@ -205,6 +284,32 @@ const PuzzleApp: React.FC<{
                   </svg>
                   <span>{t.loadLocal}</span>
                 </button>
+                <button
+                  id="editBtn"
+                  className="icon-btn"
+                  title={t.edit}
+                  type="button"
+                  aria-pressed={mode === "board"}
+                  onClick={() => switchMode("board")}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M3 17.25V21h3.75L17.81 9.94l-3.75-3.75L3 17.25zM20.71 7.04a1 1 0 000-1.41l-2.34-2.34a1 1 0 00-1.41 0l-1.83 1.83 3.75 3.75 1.83-1.83z" />
+                  </svg>
+                  <span>{t.edit}</span>
+                </button>
+                <button
+                  id="shapesBtn"
+                  className="icon-btn"
+                  title={t.shapes}
+                  type="button"
+                  aria-pressed={mode === "shapes"}
+                  onClick={() => switchMode("shapes")}
+                >
+                  <svg viewBox="0 0 24 24" width="18" height="18" aria-hidden>
+                    <path d="M11 2L6 10.5h10L11 2zm6 11a4.5 4.5 0 100 9 4.5 4.5 0 000-9zM3 13.5h8v8H3v-8z" />
+                  </svg>
+                  <span>{t.shapes}</span>
+                </button>
                 <button
                   id="tutorBtn"
                   className="icon-btn"
@@ -283,6 +388,29 @@ const PuzzleApp: React.FC<{
                   style={{ width: 64 }}
                 />
               </div>
+              <div style={{ display: "flex", gap: 10, alignItems: "center" }}>
+                <label htmlFor="snapMode">{t.snap}</label>
+                <select id="snapMode" defaultValue="off">
+                  <option value="off">{t.snapOff}</option>
+                  <option value="angle">{t.snapAngle}</option>
+                  <option value="edges">{t.snapEdges}</option>
+                </select>
+                <input
+                  id="snapDeg"
+                  type="number"
+                  min={1}
+                  max={90}
+                  step={1}
+                  defaultValue={15}
+                  title={t.snapDeg}
+                  style={{ width: 56 }}
+                />
+                <span aria-hidden>°</span>
+                <label style={{ display: "inline-flex", gap: 4, alignItems: "center" }}>
+                  <input id="tapStep" type="checkbox" />
+                  {t.tapStep}
+                </label>
+              </div>
             </div>
             <span id="help" style={{ display: "none" }} />
           </div>
@@ -310,11 +438,18 @@ const PuzzleApp: React.FC<{
               }}
             >
               <div style={{ flex: 1, minHeight: 0 }}>
-                <canvas id="cv" ref={canvasRef} width={1200} height={800} />
+                {/* touch-action: none lets pointer events drive drags and twists instead of scrolling */}
+                <canvas
+                  id="cv"
+                  ref={canvasRef}
+                  width={1200}
+                  height={800}
+                  style={{ touchAction: "none" }}
+                />
               </div>
               <div id="note" style={{ padding: "8px 12px" }} />
             </div>
-            <ValidationPanel lang={lang} />
+            <ValidationPanel lang={lang} mode={mode} onDone={() => switchMode("play")} />
           </div>
         </div>
         {showTutor && <TutorModal lang={lang} onClose={() => setShowTutor(false)} />}
@@ -323,7 +458,13 @@ const PuzzleApp: React.FC<{
   );
 };
 
-const ValidationPanel: React.FC<{ lang: Lang }> = ({ lang }) => {
+type EditMode = "play" | "board" | "shapes";
+
+const ValidationPanel: React.FC<{ lang: Lang; mode: EditMode; onDone: () => void }> = ({
+  lang,
+  mode,
+  onDone,
+}) => {
   const [open, setOpen] = useState(true);
   const t = strings[lang];
   return (
@@ -351,13 +492,23 @@ const ValidationPanel: React.FC<{ lang: Lang }> = ({ lang }) => {
         >
           <span aria-hidden>{open ? "⟨" : "⟩"}</span>
         </button>
-        {open && <h3 style={{ margin: "0 0 0 8px", fontSize: 16 }}>{t.validation}</h3>}
+        {open && (
+          <h3 style={{ margin: "0 0 0 8px", fontSize: 16 }}>
+            {mode === "board" ? t.editor : mode === "shapes" ? t.shapeEditor : t.validation}
+          </h3>
+        )}
       </div>
+      {open && mode === "board" && <EditorPanel lang={lang} onDone={onDone} />}
+      {open && mode === "shapes" && <ShapeEditorPanel lang={lang} onDone={onDone} />}
       {open && (
         <div
           id="validationContent"
           className="panel-body"
-          style={{ padding: "8px 10px", fontSize: 14 }}
+          style={{
+            padding: "8px 10px",
+            fontSize: 14,
+            display: mode === "shapes" ? "none" : undefined,
+          }}
         >
           <div style={{ opacity: 0.7 }}>{t.success}</div>
         </div>
@@ -365,3 +516,290 @@ const ValidationPanel: React.FC<{ lang: Lang }> = ({ lang }) => {
     </aside>
   );
 };
+
+// Side panel of the puzzle editor. The board itself is edited on the canvas;
+// this panel mirrors `editor_state()` after every "editor-change" event.
+const EditorPanel: React.FC<{ lang: Lang; onDone: () => void }> = ({ lang, onDone }) => {
+  const t = strings[lang];
+  const [st, setSt] = useState<EditorState | null>(null);
+  useEffect(() => {
+    const refresh = () => {
+      const txt = window.__puzzleWasm?.editor_state();
+      setSt(txt ? (JSON.parse(txt) as EditorState | null) : null);
+    };
+    refresh();
+    window.addEventListener("editor-change", refresh);
+    return () => window.removeEventListener("editor-change", refresh);
+  }, []);
+  if (!st) return null;
+  const wasm = window.__puzzleWasm;
+  const row: React.CSSProperties = { display: "flex", gap: 6, alignItems: "center" };
+  return (
+    <div
+      id="editorContent"
+      className="panel-body"
+      style={{ padding: "8px 10px", fontSize: 14, display: "grid", gap: 10 }}
+    >
+      <div style={{ opacity: 0.7 }}>{t.editorHelp}</div>
+      <div style={row}>
+        <span>
+          {t.region} {st.regions ? st.region + 1 : 0}/{st.regions}
+        </span>
+        <button type="button" onClick={() => wasm?.editor_add_region()}>
+          {t.addRegion}
+        </button>
+        <button
+          type="button"
+          disabled={!st.regions}
+          onClick={() => wasm?.editor_remove_region()}
+        >
+          {t.removeRegion}
+        </button>
+      </div>
+      {st.vertex && (
+        <div style={row}>
+          <span>
+            {t.vertex} ({st.vertex.x}, {st.vertex.y})
+          </span>
+          <label style={row} title={t.radius}>
+            r
+            <input
+              type="number"
+              min={0}
+              step={1}
+              value={st.vertex.r ?? 0}
+              style={{ width: 56 }}
+              onChange={(e) => wasm?.editor_set_radius(Number(e.target.value) || 0)}
+            />
+          </label>
+          <button type="button" onClick={() => wasm?.editor_delete_vertex()}>
+            {t.deleteVertex}
+          </button>
+        </div>
+      )}
+      <div>
+        <strong>{t.pieces}</strong>
+        {st.shapes.map((s) => (
+          <label key={s.id} style={{ ...row, justifyContent: "space-between" }}>
+            <span>{(lang === "zh" ? s.label_zh : s.label_en) ?? s.id}</span>
+            <input
+              type="number"
+              min={0}
+              step={1}
+              value={st.counts[s.id] ?? 0}
+              style={{ width: 56 }}
+              onChange={(e) =>
+                wasm?.editor_set_count(s.id, Math.max(0, Math.floor(Number(e.target.value) || 0)))
+              }
+            />
+          </label>
+        ))}
+      </div>
+      <label style={row}>
+        {t.goal}
+        <select
+          value={st.goal}
+          onChange={(e) => wasm?.editor_set_goal(e.target.value as EditorState["goal"])}
+        >
+          <option value="contain">{t.goalContain}</option>
+          <option value="fill">{t.goalFill}</option>
+          <option value="silhouette">{t.goalSilhouette}</option>
+        </select>
+      </label>
+      {(["en", "zh"] as const).map((l) => (
+        <label key={l} style={{ display: "grid", gap: 4 }}>
+          {l === "en" ? t.noteEn : t.noteZh}
+          <textarea
+            rows={2}
+            value={(l === "en" ? st.note_en : st.note_zh) ?? ""}
+            onChange={(e) => wasm?.editor_set_note(l, e.target.value)}
+          />
+        </label>
+      ))}
+      <div style={row}>
+        <button type="button" onClick={() => wasm?.editor_export()}>
+          {t.exportJson}
+        </button>
+        <button type="button" onClick={onDone}>
+          {t.done}
+        </button>
+      </div>
+    </div>
+  );
+};
+
+// Numeric parameters per shape type, in catalog order
+const SHAPE_TYPES: Record<string, string[]> = {
+  rect: ["w", "h"],
+  equilateral_triangle: ["side"],
+  right_triangle: ["a", "b"],
+  regular_polygon: ["n", "side"],
+  circle: ["d"],
+  isosceles_trapezoid: ["base_bottom", "base_top", "height"],
+  parallelogram: ["base", "offset_top", "height"],
+  polygon: [],
+};
+
+// Side panel of the shape catalog editor, mirroring `shape_editor_state()`
+// after every "shape-editor-change" event. Polygons are drawn on the canvas.
+const ShapeEditorPanel: React.FC<{ lang: Lang; onDone: () => void }> = ({ lang, onDone }) => {
+  const t = strings[lang];
+  const [st, setSt] = useState<ShapeEditorState | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  useEffect(() => {
+    const refresh = () => {
+      const txt = window.__puzzleWasm?.shape_editor_state();
+      setSt(txt ? (JSON.parse(txt) as ShapeEditorState | null) : null);
+    };
+    refresh();
+    window.addEventListener("shape-editor-change", refresh);
+    return () => window.removeEventListener("shape-editor-change", refresh);
+  }, [lang]);
+  if (!st) return null;
+  const wasm = window.__puzzleWasm;
+  const row: React.CSSProperties = { display: "flex", gap: 6, alignItems: "center" };
+  const def = st.def as Record<string, unknown>;
+  const type = String(def.type);
+  const points = Array.isArray(def.points) ? def.points.length : 0;
+  const text = (key: "id" | "label_en" | "label_zh", label: string) => (
+    <label style={{ display: "grid", gap: 4 }}>
+      {label}
+      <input
+        type="text"
+        value={typeof def[key] === "string" ? (def[key] as string) : ""}
+        onChange={(e) => wasm?.shape_editor_set_text(key, e.target.value)}
+      />
+    </label>
+  );
+  return (
+    <div
+      id="shapeEditorContent"
+      className="panel-body"
+      style={{ padding: "8px 10px", fontSize: 14, display: "grid", gap: 10 }}
+    >
+      <select
+        value={st.index ?? -1}
+        onChange={(e) => {
+          setError(null);
+          wasm?.shape_editor_select(Number(e.target.value));
+        }}
+      >
+        <option value={-1}>{t.newShape}</option>
+        {st.shapes.map((s, i) => (
+          <option key={`${s.id}-${i}`} value={i}>
+            {(lang === "zh" ? s.label_zh : s.label_en) ?? s.id}
+          </option>
+        ))}
+      </select>
+      {text("id", "id")}
+      <label style={row}>
+        {t.shapeType}
+        <select value={type} onChange={(e) => wasm?.shape_editor_set_type(e.target.value)}>
+          {Object.keys(SHAPE_TYPES).map((k) => (
+            <option key={k} value={k}>
+              {k}
+            </option>
+          ))}
+        </select>
+      </label>
+      {(SHAPE_TYPES[type] ?? []).map((k) => (
+        <label key={k} style={{ ...row, justifyContent: "space-between" }}>
+          {k}
+          <input
+            type="number"
+            min={k === "offset_top" ? undefined : 0}
+            step={k === "n" ? 1 : 0.5}
+            value={Number(def[k] ?? 0)}
+            style={{ width: 72 }}
+            onChange={(e) => wasm?.shape_editor_set_param(k, Number(e.target.value))}
+          />
+        </label>
+      ))}
+      {type === "polygon" && (
+        <>
+          <div style={{ opacity: 0.7 }}>{t.polygonHelp}</div>
+          <div style={row}>
+            <label style={row}>
+              {t.gridMm}
+              <input
+                type="number"
+                min={0}
+                step={0.5}
+                value={st.grid_mm}
+                style={{ width: 56 }}
+                onChange={(e) =>
+                  wasm?.shape_editor_set_constraints(Number(e.target.value) || 0, st.angle_deg)
+                }
+              />
+            </label>
+            <label style={row}>
+              {t.angleDeg}
+              <input
+                type="number"
+                min={0}
+                max={180}
+                step={1}
+                value={st.angle_deg}
+                style={{ width: 56 }}
+                onChange={(e) =>
+                  wasm?.shape_editor_set_constraints(st.grid_mm, Number(e.target.value) || 0)
+                }
+              />
+            </label>
+          </div>
+          <div style={row}>
+            <span>
+              {t.points}: {points}
+            </span>
+            <button
+              type="button"
+              disabled={st.vertex == null}
+              onClick={() => wasm?.shape_editor_delete_vertex()}
+            >
+              {t.deleteVertex}
+            </button>
+          </div>
+        </>
+      )}
+      {text("label_en", t.labelEn)}
+      {text("label_zh", t.labelZh)}
+      {st.issues.length > 0 && (
+        <ul style={{ margin: 0, paddingLeft: 18, color: "#c62828" }}>
+          {st.issues.map((msg) => (
+            <li key={msg}>{msg}</li>
+          ))}
+        </ul>
+      )}
+      {error && <div style={{ color: "#c62828" }}>{error}</div>}
+      <div style={{ ...row, flexWrap: "wrap" }}>
+        <button
+          type="button"
+          disabled={st.issues.length > 0}
+          onClick={() => {
+            try {
+              wasm?.shape_editor_save();
+              setError(null);
+            } catch (err) {
+              setError(String(err));
+            }
+          }}
+        >
+          {t.saveShape}
+        </button>
+        <button
+          type="button"
+          disabled={st.index == null}
+          onClick={() => wasm?.shape_editor_remove()}
+        >
+          {t.removeShape}
+        </button>
+        <button type="button" onClick={() => wasm?.shape_editor_export()}>
+          {t.exportCatalog}
+        </button>
+        <button type="button" onClick={onDone}>
+          {t.done}
+        </button>
+      </div>
+    </div>
+  );
+};
@@ -1,6 +1,85 @@
 // Global types for WASM bridge exposed on window
 export type PuzzleWasm = {
   load_puzzle_from_text: (txt: string) => Promise<void>;
+  // Searches in the background; rejects with the reason when nothing is found
+  solve: () => Promise<string>;
+  // JSON ValidationReport: { ok, violations: [{ kind, ... }], coverage_pct, uncovered_mm2 }
+  validate: () => string;
+  // Next hint level given (1 highlight, 2 ghost, 3 snap); undefined if none
+  hint: () => Promise<number | undefined>;
+  // Hints given since the puzzle was loaded
+  hint_count: () => number;
+  // Index of the known solution the current layout matches, if any
+  check_solution: () => number | undefined;
+  // Return false when there is nothing to undo/redo
+  undo: () => boolean;
+  redo: () => boolean;
+  // Pack every piece back into the tray beside the board
+  return_to_tray: () => void;
+  // Random scramble into the tray; returns the seed (also set as ?seed=)
+  scramble: () => string;
+  // Puzzle editor; the canvas edits board regions while it is open
+  editor_start: () => void;
+  editor_stop: () => void;
+  // JSON EditorState, or "null" when the editor is closed
+  editor_state: () => string;
+  editor_set_count: (id: string, n: number) => void;
+  editor_set_note: (lang: "en" | "zh", text: string) => void;
+  editor_set_goal: (goal: "contain" | "fill" | "silhouette") => void;
+  // Radius (mm) of the selected corner; 0 makes it sharp
+  editor_set_radius: (r: number) => void;
+  editor_delete_vertex: () => void;
+  editor_add_region: () => void;
+  editor_remove_region: () => void;
+  // Downloads the counts JSON and returns its text
+  editor_export: () => string;
+  // Shape catalog editor; the canvas shows the shape at real size
+  shape_editor_start: () => void;
+  shape_editor_stop: () => void;
+  // JSON ShapeEditorState, or "null" when the editor is closed
+  shape_editor_state: () => string;
+  // Negative index starts a new shape
+  shape_editor_select: (index: number) => void;
+  shape_editor_set_type: (type: string) => void;
+  shape_editor_set_param: (key: string, value: number) => void;
+  shape_editor_set_text: (key: "id" | "label_en" | "label_zh", text: string) => void;
+  // Polygon grid (mm) and edge angle step (degrees); 0 turns either off
+  shape_editor_set_constraints: (gridMm: number, angleDeg: number) => void;
+  shape_editor_delete_vertex: () => void;
+  // Throws the first problem when the shape is not usable
+  shape_editor_save: () => void;
+  shape_editor_remove: () => void;
+  // Downloads shapes.json and returns its text
+  shape_editor_export: () => string;
+};
+
+// Snapshot of the puzzle editor, refreshed on the "editor-change" window event
+export type EditorState = {
+  regions: number;
+  region: number;
+  vertex: { x: number; y: number; r: number | null } | null;
+  counts: Record<string, number>;
+  goal: "contain" | "fill" | "silhouette";
+  note_en: string | null;
+  note_zh: string | null;
+  shapes: { id: string; label_en: string | null; label_zh: string | null }[];
+};
+
+// Snapshot of the shape editor, refreshed on the "shape-editor-change" window event
+export type ShapeEditorState = {
+  // Catalog entry being edited; null for a new shape
+  index: number | null;
+  // The ShapeDef as it would be saved (id, type, parameters, labels)
+  def: { id: string; type: string; label_en?: string; label_zh?: string } & Record<
+    string,
+    unknown
+  >;
+  // Problems in the current language; the shape cannot be saved while any remain
+  issues: string[];
+  grid_mm: number;
+  angle_deg: number;
+  vertex: number | null;
+  shapes: { id: string; label_en: string | null; label_zh: string | null }[];
 };
 
 declare global {
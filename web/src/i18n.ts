@@ -4,19 +4,64 @@ export const strings = {
   en: {
     home: "Home",
     reset: "Reset",
+    undo: "Undo",
+    redo: "Redo",
+    tray: "Return all to tray",
+    scramble: "Scramble",
+    hint: "Hint",
+    solution: "Show solution",
     download: "Download Blueprint",
     tutor: "Tutor",
+    edit: "Edit",
+    editor: "Editor",
+    editorHelp:
+      "Click to add a vertex after the selected one, click an edge to split it, drag a vertex to move it. Delete removes it, R rounds it, Esc deselects.",
+    region: "Region",
+    addRegion: "Add region",
+    removeRegion: "Remove region",
+    vertex: "Vertex",
+    radius: "Corner radius (mm)",
+    deleteVertex: "Delete vertex",
+    pieces: "Pieces",
+    goal: "Goal",
+    goalContain: "Fit inside",
+    goalFill: "Fill exactly",
+    goalSilhouette: "Silhouette",
+    noteEn: "Note (English)",
+    noteZh: "Note (Chinese)",
+    exportJson: "Export JSON",
+    done: "Done",
+    shapes: "Shapes",
+    shapeEditor: "Shape editor",
+    newShape: "New shape",
+    shapeType: "Type",
+    polygonHelp:
+      "Click to add a point after the selected one, drag points to move them. Delete removes a point, Esc deselects.",
+    gridMm: "Grid (mm)",
+    angleDeg: "Angle step (°)",
+    points: "Points",
+    labelEn: "Label (English)",
+    labelZh: "Label (Chinese)",
+    saveShape: "Save to catalog",
+    removeShape: "Remove from catalog",
+    exportCatalog: "Export catalog",
     language: "Language:",
     theme: "Theme:",
     themeLight: "Light",
     themeDark: "Dark",
     themeAuto: "Auto",
-    help: "Drag; Q/E rotate; F flip; L lock; S toggle fast/slow mode; Shift temp lock",
+    help: "Click to select (Ctrl+click adds, Tab cycles); drag; Q/E rotate; F flip; L lock; M magnet; S toggle fast/slow mode; Shift temp lock; Ctrl+Z undo; Ctrl+Shift+Z redo",
     lockUnlocked: "Lock: Unlocked",
     lockLocked: "Lock: Locked",
     lockTemp: "Lock: Temporary",
     speedFast: "Speed: Fast",
     speedSlow: "Speed: Slow",
+    snap: "Snap:",
+    snapOff: "Off",
+    snapAngle: "Angle multiples",
+    snapEdges: "Piece edges",
+    snapDeg: "Snap / step angle (degrees)",
+    tapStep: "Q/E step",
     metaTitle: "Kindergarten Puzzle",
     metaDesc: "A modern puzzle inspired by a kindergarten teaching tool.",
     landingIntro: "A modern puzzle inspired by a kindergarten teaching tool.",
@@ -36,27 +81,75 @@ export const strings = {
       title: "Tutorial",
       close: "Close",
       lines: [
-        "Controls: drag with mouse; Q/E rotate; F flip; L lock; S toggle fast/slow mode; Shift temp lock.",
-        "Adjustments: the two sliders set fast and slow rotation speeds.",
+        "Controls: click a piece to select it (Ctrl+click to select several, Tab to cycle, Esc to clear); drag with mouse; Q/E rotate and F flip the selection; L lock; M magnet (snap flush to nearby edges and corners); S toggle fast/slow mode; Shift temp lock.",
+        "Touch: drag with a finger; put a second finger down and twist to rotate; double-tap to flip. Several pieces can be dragged at once.",
+        "Adjustments: the two sliders set fast and slow rotation speeds. Snap lands a rotation on multiples of the given angle or lines a piece edge up with the board; with Q/E step on, each press turns by one step.",
+        "History: Ctrl+Z undoes the last move, rotation, flip, reset or solution; Ctrl+Shift+Z redoes it.",
+        "Stuck? Press H (or Hint): the first time a misplaced piece is outlined, the second time its place is shown, the third time it moves there.",
+        "Edit opens the puzzle editor: click to add board vertices, drag them, R rounds a corner; pick piece counts and notes in the side panel, then Export JSON.",
+        "Shapes opens the shape editor: pick a type and its sizes, or draw a polygon point by point on the grid; the shape is shown at real size. Save it to the catalog, then Export catalog.",
       ],
     },
   },
   zh: {
     home: "返回主页",
     reset: "重开",
+    undo: "撤销",
+    redo: "重做",
+    tray: "全部放回托盘",
+    scramble: "打乱",
+    hint: "提示",
+    solution: "显示答案",
     download: "下载蓝图",
     tutor: "教程",
+    edit: "编辑",
+    editor: "编辑器",
+    editorHelp: "点击在选中顶点后添加顶点，点击边可将其分开，拖动顶点可移动。Delete 删除，R 变圆角，Esc 取消选择。",
+    region: "区域",
+    addRegion: "添加区域",
+    removeRegion: "删除区域",
+    vertex: "顶点",
+    radius: "圆角半径（毫米）",
+    deleteVertex: "删除顶点",
+    pieces: "拼图块",
+    goal: "目标",
+    goalContain: "放进底板",
+    goalFill: "正好铺满",
+    goalSilhouette: "剪影",
+    noteEn: "说明（英文）",
+    noteZh: "说明（中文）",
+    exportJson: "导出 JSON",
+    done: "完成",
+    shapes: "形状",
+    shapeEditor: "形状编辑器",
+    newShape: "新形状",
+    shapeType: "类型",
+    polygonHelp: "点击在选中顶点后添加顶点，拖动顶点可移动。Delete 删除顶点，Esc 取消选择。",
+    gridMm: "网格（毫米）",
+    angleDeg: "角度步长（°）",
+    points: "顶点",
+    labelEn: "名称（英文）",
+    labelZh: "名称（中文）",
+    saveShape: "保存到目录",
+    removeShape: "从目录删除",
+    exportCatalog: "导出目录",
     language: "语言:",
     theme: "主题:",
     themeLight: "浅色",
     themeDark: "暗色",
     themeAuto: "自动",
-    help: "拖拽；Q/E旋转；F翻转；L锁定；S切换快/慢模式；按住Shift临时锁定",
+    help: "点击选中（Ctrl+点击多选，Tab切换）；拖拽；Q/E旋转；F翻转；L锁定；M磁吸；S切换快/慢模式；按住Shift临时锁定；Ctrl+Z撤销；Ctrl+Shift+Z重做",
     lockUnlocked: "锁定：未锁定",
     lockLocked: "锁定：已锁定",
     lockTemp: "锁定：临时锁定",
     speedFast: "速度：快",
     speedSlow: "速度：慢",
+    snap: "吸附:",
+    snapOff: "关闭",
+    snapAngle: "角度倍数",
+    snapEdges: "拼图边",
+    snapDeg: "吸附/步进角度（度）",
+    tapStep: "Q/E 步进",
     metaTitle: "Kindergarten Puzzle",
     metaDesc: "灵感来源于一款幼儿园教具的现代化拼图。",
     landingIntro: "灵感来源于一款幼儿园教具的现代化拼图。",
@@ -76,8 +169,13 @@ export const strings = {
       title: "教程 / 快速上手",
       close: "关闭",
       lines: [
-        "按键说明：鼠标拖动，Q/E 旋转，F 翻转，L 锁定，S 切换快/慢模式，按住 Shift 临时锁定。",
-        "调整说明：两个速度调节条分别控制快/慢旋转速度。",
+        "按键说明：点击拼图选中（Ctrl+点击多选，Tab 依次切换，Esc 取消选择）；鼠标拖动；Q/E 旋转、F 翻转选中的拼图；L 锁定，M 磁吸（贴合附近的边和角），S 切换快/慢模式，按住 Shift 临时锁定。",
+        "触屏：单指拖动；再放一根手指并转动即可旋转；双击翻转。可以同时拖动多块拼图。",
+        "调整说明：两个速度调节条分别控制快/慢旋转速度。吸附会让旋转停在给定角度的整数倍，或让拼图的边与底板对齐；勾选 Q/E 步进后每按一次旋转一步。",
+        "历史记录：Ctrl+Z 撤销上一次移动、旋转、翻转、重开或显示答案；Ctrl+Shift+Z 重做。",
+        "卡住了？按 H（或点“提示”）：第一次标出一块放错的拼图，第二次显示它的位置，第三次把它放过去。",
+        "“编辑”打开拼图编辑器：点击添加底板顶点，拖动调整，R 变圆角；在侧栏选择拼图块数量和说明，然后导出 JSON。",
+        "“形状”打开形状编辑器：选择类型并填写尺寸，或在网格上逐点绘制多边形；形状按实际尺寸显示。保存到目录后即可导出目录。",
       ],
     },
   },